@@ -1,20 +1,108 @@
 use std::{io::Write, process::exit};
 
 use crate::libpoulet::backtrack;
+use crate::libpoulet::intern::PropRef;
+use crate::libpoulet::kernel;
+use crate::libpoulet::kripke;
 use crate::libpoulet::logic;
+use crate::libpoulet::pretty::PrettyOptions;
+use crate::libpoulet::sat;
+use crate::libpoulet::script;
 use crate::libpoulet::strategies;
+use crate::libpoulet::tactics;
+
+fn print_terms(proof: &strategies::Proof) {
+    for (theorem, term) in proof.proof_terms() {
+        println!("{}", theorem);
+        println!("    {}", term);
+        if term.is_complete() {
+            match kernel::check_theorem(&theorem, &term) {
+                Ok(()) => println!("    checked by kernel"),
+                Err(err) => println!("    REJECTED by kernel: {}", err),
+            }
+        }
+    }
+}
+
+fn replay_steps(
+    proof: &mut strategies::Proof,
+    steps: &[(usize, usize, strategies::StrategyArg)],
+) -> Result<u8, &'static str> {
+    println!("Solved using auto:");
+    for (_, goalnum, strat) in steps.iter() {
+        println!("    goal: {} - {}", goalnum, strat);
+    }
+    match backtrack::replay(proof, steps) {
+        Ok(solved) => {
+            println!("Proof terms:");
+            print_terms(&solved);
+            // kept so that 'derivation' and 'latex' can show it, and that
+            // it can be saved as a lemma
+            *proof = solved;
+            Ok(1)
+        }
+        Err(err) => Err(format!("the steps found could not be replayed: {}", err).leak()),
+    }
+}
+
+fn run_auto(proof: &mut strategies::Proof, args: &str) -> Result<u8, &'static str> {
+    match backtrack::search(proof, args) {
+        Ok(steps) => replay_steps(proof, &steps),
+        Err(err) => Err(err.leak()),
+    }
+}
+
+// `label` then the proposition, its continuation lines aligned after it
+fn print_prop(label: &str, prop: &logic::Prop, display: &PrettyOptions) {
+    let options = PrettyOptions {
+        width: display.width.map(|width| width.saturating_sub(label.len())),
+        ..*display
+    };
+    let pretty = prop.pretty(&options);
+    let mut lines = pretty.lines();
+    println!("{}{}", label, lines.next().unwrap_or(""));
+    for line in lines {
+        println!("{}{}", " ".repeat(label.len()), line);
+    }
+}
 
 fn parse_input<'a>(
     proof: &'a mut strategies::Proof,
     prevs: &mut Vec<strategies::Proof>,
+    display: &mut PrettyOptions,
     input: &'a str,
 ) -> Result<u8, &'a str> {
     let input = input.trim();
+    if tactics::is_tactic(input) {
+        return match tactics::parse(input) {
+            Ok(tactic) => match tactic.run(proof) {
+                Ok(()) => Ok(1),
+                Err(err) => Err(err.to_string().leak()),
+            },
+            Err(err) => Err(err.leak()),
+        };
+    }
     match input.split_once(char::is_whitespace) {
+        Some(("display", rest)) => {
+            match rest.split_whitespace().collect::<Vec<&str>>().as_slice() {
+                ["unicode"] => display.unicode = true,
+                ["ascii"] => display.unicode = false,
+                ["negation", "on"] => display.negation = true,
+                ["negation", "off"] => display.negation = false,
+                ["width", "off"] => display.width = None,
+                ["width", columns] => match columns.parse::<usize>() {
+                    Ok(columns) => display.width = Some(columns),
+                    Err(_) => return Err("Invalid argument"),
+                },
+                _ => return Err("Invalid argument"),
+            }
+            Ok(2)
+        }
         Some(("load", rest)) => {
             let path = rest.trim();
             match strategies::Proof::from_file(path) {
-                Ok(loaded_proof) => {
+                Ok(mut loaded_proof) => {
+                    loaded_proof.inherit_lemmas(proof);
                     *proof = loaded_proof;
                     Ok(1)
                 }
@@ -28,106 +116,133 @@ fn parse_input<'a>(
                 Err(msg) => Err(msg.leak()),
             }
         }
+        Some(("load_dimacs", rest)) => {
+            let path = rest.trim();
+            match strategies::Proof::from_dimacs(path) {
+                Ok(mut loaded_proof) => {
+                    loaded_proof.inherit_lemmas(proof);
+                    *proof = loaded_proof;
+                    Ok(1)
+                }
+                Err(msg) => Err(msg.leak()),
+            }
+        }
+        Some(("save_dimacs", rest)) => {
+            let path = rest.trim();
+            match proof.to_dimacs(path) {
+                Ok(_) => Ok(2),
+                Err(msg) => Err(msg.leak()),
+            }
+        }
+        Some(("load_tptp", rest)) => {
+            let path = rest.trim();
+            match strategies::Proof::from_tptp(path) {
+                Ok(mut loaded_proof) => {
+                    loaded_proof.inherit_lemmas(proof);
+                    *proof = loaded_proof;
+                    Ok(1)
+                }
+                Err(msg) => Err(msg.leak()),
+            }
+        }
+        Some(("save_tptp", rest)) => {
+            let path = rest.trim();
+            match proof.to_tptp(path) {
+                Ok(_) => Ok(2),
+                Err(msg) => Err(msg.leak()),
+            }
+        }
+        Some(("save_lemma", rest)) => {
+            let name = rest.trim();
+            if name.contains(char::is_whitespace) {
+                return Err("Invalid argument");
+            }
+            match proof.save_lemma(name) {
+                Ok(()) => {
+                    println!("Saved lemma {}.", name);
+                    Ok(2)
+                }
+                Err(msg) => Err(msg.leak()),
+            }
+        }
+        Some(("latex", rest)) => {
+            let path = rest.trim();
+            let latex: String = proof.derivations().iter().map(|d| d.to_latex()).collect();
+            match std::fs::write(path, latex) {
+                Ok(()) => Ok(2),
+                Err(_) => Err(format!("failed to write to file '{}'", path).leak()),
+            }
+        }
+        Some(("auto", rest)) => run_auto(proof, rest),
+        Some(("normal_form", rest)) => {
+            let Some((goal, _)) = proof.goals.get(proof.active_goal_index()) else {
+                return Err("No goals");
+            };
+            match rest.trim() {
+                "nnf" => println!("{}", goal.nnf().to_string()),
+                "cnf" | "dnf" => {
+                    let (form, prop) = if rest.trim() == "cnf" {
+                        let cnf = goal.cnf();
+                        (cnf.to_string(), cnf.to_prop())
+                    } else {
+                        let dnf = goal.dnf();
+                        (dnf.to_string(), dnf.to_prop())
+                    };
+                    println!("{}", form);
+                    let equivalent = logic::Prop::equiv(goal.as_ref().clone(), prop);
+                    if sat::classically_valid(&[], &equivalent) {
+                        println!("(checked classically equivalent to the goal)");
+                    } else {
+                        println!("(NOT equivalent to the goal)");
+                    }
+                }
+                "tseitin" => {
+                    let (clauses, fresh) = goal.tseitin_cnf();
+                    println!("{}", clauses);
+                    println!("fresh atoms: {}", fresh.join(", "));
+                    let satisfiable = sat::satisfying_valuation(&clauses.to_prop()).is_some();
+                    if satisfiable == sat::satisfying_valuation(goal).is_some() {
+                        println!("(checked satisfiable exactly when the goal is)");
+                    } else {
+                        println!("(NOT equisatisfiable with the goal)");
+                    }
+                }
+                _ => return Err("argument incorrect: <nnf | cnf | tseitin | dnf>"),
+            }
+            Ok(2)
+        }
+        Some(("add_goal", rest)) => match logic::Prop::parse_infix(rest) {
+            Ok(prop) => {
+                proof.add_goal_from_prop(prop);
+                Ok(1)
+            }
+            Err(err) => Err(format!("{}\n{}", err, err.underline(rest)).leak()),
+        },
         Some(("add_goal_rpn", rest)) => match logic::Prop::parse_rpn(rest) {
             Ok(prop) => {
                 proof.add_goal_from_prop(prop);
                 Ok(1)
             }
-            Err(msg) => Err(msg),
+            Err(err) => Err(format!("{}\n{}", err, err.underline(rest)).leak()),
         },
         Some(("set_active", rest)) => match rest.trim().parse::<usize>() {
             Ok(0) => Err("Invalid argument"),
             Ok(goal_num) => match proof.set_active_goal(goal_num - 1) {
                 Ok(()) => Ok(1),
-                Err(msg) => Err(msg),
-            },
-            Err(_) => Err("Invalid argument"),
-        },
-        Some(("hyp_split", rest)) => match rest.trim().parse::<usize>() {
-            Ok(goal_num) => match proof.execute(&strategies::StrategyArg::HypSplit(goal_num)) {
-                Ok(()) => Ok(1),
-                Err(msg) => Err(msg),
-            },
-            Err(_) => Err("Invalid argument"),
-        },
-        Some(("hyp_left", rest)) => match rest.trim().parse::<usize>() {
-            Ok(goal_num) => {
-                match proof.execute(&strategies::StrategyArg::HypOrSplit(goal_num, true)) {
-                    Ok(()) => Ok(1),
-                    Err(msg) => Err(msg),
-                }
-            }
-            Err(_) => Err("Invalid argument"),
-        },
-        Some(("hyp_right", rest)) => match rest.trim().parse::<usize>() {
-            Ok(goal_num) => {
-                match proof.execute(&strategies::StrategyArg::HypOrSplit(goal_num, false)) {
-                    Ok(()) => Ok(1),
-                    Err(msg) => Err(msg),
-                }
-            }
-            Err(_) => Err("Invalid argument"),
-        },
-        Some(("exact", rest)) => match rest.trim().parse::<usize>() {
-            Ok(goal_num) => match proof.execute(&strategies::StrategyArg::Exact(goal_num)) {
-                Ok(()) => Ok(1),
-                Err(msg) => Err(msg),
-            },
-            Err(_) => Err("Invalid argument"),
-        },
-        Some(("apply", rest)) => match rest.trim().parse::<usize>() {
-            Ok(goal_num) => match proof.execute(&strategies::StrategyArg::Apply(goal_num)) {
-                Ok(()) => Ok(1),
-                Err(msg) => Err(msg),
+                Err(err) => Err(err.to_string().leak()),
             },
             Err(_) => Err("Invalid argument"),
         },
-        Some(("apply_in", rest)) => match rest.trim().split_once(char::is_whitespace) {
-            Some((first, second)) => {
-                match (
-                    first.trim().parse::<usize>(),
-                    second.trim().parse::<usize>(),
-                ) {
-                    (Ok(first_num), Ok(second_num)) => {
-                        match proof.execute(&strategies::StrategyArg::ApplyIn(
-                            first_num, second_num, false,
-                        )) {
-                            Ok(()) => Ok(1),
-                            Err(msg) => Err(msg),
-                        }
-                    }
-                    (Ok(_), _) => Err("first argument incorrect: <hyp id (0..N) target>"),
-                    (_, Ok(_)) => Err("second argument incorrect: <hyp id (0..N) to apply>"),
-                    (_, _) => {
-                        Err("arguments incorrect: <hyp id (0..N) target> <hyp id (0..N) to apply>")
-                    }
-                }
-            }
-            None => Err("missing argument: <hyp id (0..N) to apply>"),
-        },
-        Some(("apply_in_keep", rest)) => match rest.trim().split_once(char::is_whitespace) {
-            Some((first, second)) => {
-                match (
-                    first.trim().parse::<usize>(),
-                    second.trim().parse::<usize>(),
-                ) {
-                    (Ok(first_num), Ok(second_num)) => {
-                        match proof.execute(&strategies::StrategyArg::ApplyIn(
-                            first_num, second_num, true,
-                        )) {
-                            Ok(()) => Ok(1),
-                            Err(msg) => Err(msg),
-                        }
-                    }
-                    (Ok(_), _) => Err("first argument incorrect: <hyp id (0..N) target>"),
-                    (_, Ok(_)) => Err("second argument incorrect: <hyp id (0..N) to apply>"),
-                    (_, _) => {
-                        Err("arguments incorrect: <hyp id (0..N) target> <hyp id (0..N) to apply>")
-                    }
-                }
-            }
-            None => Err("missing argument: <hyp id (0..N) to apply>"),
-        },
+        Some(("logic", rest)) => {
+            let logic = match rest.trim() {
+                "minimal" => logic::Logic::Minimal,
+                "intuitionistic" => logic::Logic::Intuitionistic,
+                "classical" => logic::Logic::Classical,
+                _ => return Err("argument incorrect: <minimal | intuitionistic | classical>"),
+            };
+            proof.set_logic(logic);
+            Ok(1)
+        }
         Some((_, _)) => Err("Unknown command"),
         None => match input {
             "quit" => Ok(0),
@@ -144,79 +259,237 @@ fn parse_input<'a>(
                 Ok(1)
             }
             "purge" => {
-                *proof = strategies::Proof::new();
+                let mut purged = strategies::Proof::new();
+                purged.inherit_lemmas(proof);
+                *proof = purged;
                 *prevs = vec![];
                 Ok(1)
             }
+            // lemmas are kept whatever happened to the proof
             "back" => match prevs.pop() {
-                Some(a) => {
+                Some(mut a) => {
+                    a.inherit_lemmas(proof);
                     *proof = a;
                     Ok(2)
                 }
                 None => Err("Cannot go back further"),
             },
-            "auto" => match backtrack::auto(proof) {
-                Ok(steps) => {
-                    println!("Solved using auto:");
-                    for (_, goalnum, strat) in steps {
-                        println!("    goal: {} - {}", goalnum, strat);
+            "auto" => run_auto(proof, ""),
+            "derivation" => {
+                for derivation in proof.derivations() {
+                    print!("{}", derivation);
+                    if !derivation.is_complete() {
+                        println!("(unfinished, '?' leaves are open goals)");
                     }
-                    *proof = strategies::Proof::new();
-                    Ok(1)
                 }
-                Err(()) => Err("Could not solve using auto"),
-            },
-            "intro" => match proof.execute(&strategies::StrategyArg::Intro) {
-                Ok(()) => Ok(1),
-                Err(msg) => Err(msg),
+                Ok(2)
+            }
+            "latex" => {
+                for derivation in proof.derivations() {
+                    print!("{}", derivation.to_latex());
+                }
+                Ok(2)
+            }
+            "term" => {
+                if proof.finished_terms().is_some() {
+                    println!("All goals closed, finished proof terms:");
+                }
+                print_terms(proof);
+                Ok(2)
+            }
+            "check" => match proof.finished_terms() {
+                Some(terms) => {
+                    for (theorem, term) in terms {
+                        if let Err(err) = kernel::check_theorem(&theorem, &term) {
+                            return Err(format!("{}: {}", theorem, err).leak());
+                        }
+                    }
+                    println!("All proof terms checked by kernel.");
+                    Ok(2)
+                }
+                None => Err("Some goals are still open"),
             },
+            "countermodel" => {
+                let Some((goal, hyps)) = proof.goals.get(proof.active_goal_index()) else {
+                    return Err("No goals");
+                };
+                if !strategies::is_propositional(goal, hyps) {
+                    return Err("only available for propositional goals");
+                }
+                if proof.logic() != logic::Logic::Intuitionistic {
+                    return Err("Kripke countermodels refute intuitionistic provability only");
+                }
+                match kripke::countermodel(hyps, goal) {
+                    Some(model) => {
+                        print!("{}", model);
+                        match model.check_countermodel(hyps, goal) {
+                            Ok(()) => println!(
+                                "w0 forces every hypothesis but not the goal, it cannot be proven"
+                            ),
+                            Err(err) => println!("countermodel is wrong: {}", err),
+                        }
+                        Ok(2)
+                    }
+                    None => Err("the active goal is provable, it has no countermodel"),
+                }
+            }
+            "truth_table" => {
+                let Some((goal, hyps)) = proof.goals.get(proof.active_goal_index()) else {
+                    return Err("No goals");
+                };
+                if !strategies::is_propositional(goal, hyps) {
+                    return Err("only available for propositional goals");
+                }
+                let names = proof.hyp_names(proof.active_goal_index());
+                let mut props: Vec<(String, PropRef)> =
+                    names.into_iter().zip(hyps.iter().cloned()).collect();
+                props.push((String::from("Goal"), goal.clone()));
+                let table = match logic::TruthTable::new(&props) {
+                    Ok(table) => table,
+                    Err(err) => return Err(err.to_string().leak()),
+                };
+                print!("{}", table);
+                let sequent = hyps.iter().rev().fold(goal.as_ref().clone(), |acc, hyp| {
+                    logic::Prop::Implies(hyp.clone(), PropRef::new(acc))
+                });
+                if sequent.is_tautology() {
+                    println!("The goal is a classical consequence of the hypotheses.");
+                } else if !goal.is_satisfiable() {
+                    println!("Not classically valid, the goal is false in every row.");
+                } else {
+                    // a row where every hypothesis holds but the goal does not
+                    let (atoms, _) = table
+                        .rows
+                        .iter()
+                        .find(|(_, values)| {
+                            let (goal_value, hyp_values) = values.split_last().unwrap();
+                            hyp_values.iter().all(|v| *v) && !goal_value
+                        })
+                        .unwrap();
+                    let atoms: Vec<String> = table
+                        .atoms
+                        .iter()
+                        .zip(atoms)
+                        .map(|(atom, value)| format!("{}={}", atom, *value as u8))
+                        .collect();
+                    println!(
+                        "Not classically valid, for instance when {}.",
+                        atoms.join(" ")
+                    );
+                }
+                Ok(2)
+            }
+            "classical" => {
+                let Some((goal, hyps)) = proof.goals.get(proof.active_goal_index()) else {
+                    return Err("No goals");
+                };
+                if !strategies::is_propositional(goal, hyps) {
+                    return Err("only available for propositional goals");
+                }
+                match sat::classical_counterexample(hyps, goal) {
+                    None => println!("The goal is a classical consequence of the hypotheses."),
+                    Some(valuation) => {
+                        let atoms: Vec<String> = valuation
+                            .iter()
+                            .map(|(atom, value)| format!("{}={}", atom, *value as u8))
+                            .collect();
+                        println!(
+                            "Not classically valid, so not provable, for instance when {}.",
+                            atoms.join(" ")
+                        )
+                    }
+                }
+                Ok(2)
+            }
             "clean" => {
                 proof.clean();
                 Ok(1)
             }
-            "split" => match proof.execute(&strategies::StrategyArg::Split) {
-                Ok(()) => Ok(1),
-                Err(msg) => Err(msg),
-            },
-            "left" => match proof.execute(&strategies::StrategyArg::OrSplit(true)) {
-                Ok(()) => Ok(1),
-                Err(msg) => Err(msg),
-            },
-            "right" => match proof.execute(&strategies::StrategyArg::OrSplit(false)) {
-                Ok(()) => Ok(1),
-                Err(msg) => Err(msg),
-            },
-            "false" => match proof.execute(&strategies::StrategyArg::FalseIsHyp) {
-                Ok(()) => Ok(1),
-                Err(msg) => Err(msg),
-            },
+            "logic" => {
+                println!("Current logic: {}", proof.logic());
+                Ok(2)
+            }
+            "lemmas" => {
+                let lemmas = proof.lemmas();
+                if lemmas.is_empty() {
+                    println!("No lemmas saved.");
+                }
+                for (name, statement) in lemmas {
+                    print_prop(&format!(" {} : ", name), &statement, display);
+                }
+                Ok(2)
+            }
+            "save_lemma" => Err("missing argument: <lemma name>"),
+            "add_goal" => Err("missing argument: <proposition infix format>"),
+            "normal_form" => Err("missing argument: <nnf | cnf | tseitin | dnf>"),
+            "display" => Err(DISPLAY_USAGE),
             "add_goal_rpn" => Err("missing argument: <proposition rpn format>"),
             "set_active" => Err("missing argument: <goal index (1..N)>"),
-            "hyp_split" | "hyp_left" | "hyp_right" | "exact" | "apply" => {
-                Err("missing argument: <hyp id (0..N)>")
-            }
-            "apply_in" => Err("missing arguments: <hyp id (0..N) target> <hyp id (0..N) to apply>"),
             _ => Err("Unknown command"),
         },
     }
 }
 
+const DISPLAY_USAGE: &str =
+    "missing argument: <unicode | ascii | negation on|off | width <columns>|off>";
+
+// batch mode, exiting with 1 when a theorem of the script fails and with 2
+// when the script cannot be read at all
+pub fn check_script(path: &str) {
+    let Ok(input) = std::fs::read_to_string(path) else {
+        eprintln!("failed to open file '{}'", path);
+        exit(2);
+    };
+    let results = match script::check(&input) {
+        Ok(results) => results,
+        Err(err) => {
+            eprintln!("Error while parsing file '{}', {}", path, err);
+            exit(2);
+        }
+    };
+    for theorem in results.iter() {
+        match &theorem.result {
+            Ok(()) => println!("theorem {} (line {}): ok", theorem.name, theorem.line),
+            Err(err) => println!(
+                "theorem {} (line {}): FAILED, {}",
+                theorem.name, theorem.line, err
+            ),
+        }
+    }
+    let failed = results
+        .iter()
+        .filter(|theorem| theorem.result.is_err())
+        .count();
+    println!("{} theorem(s) checked, {} failed", results.len(), failed);
+    if failed > 0 {
+        exit(1);
+    }
+}
+
 pub fn repl() {
     let mut proof = strategies::Proof::new();
     let mut prevs: Vec<strategies::Proof> = vec![];
+    let mut display = PrettyOptions {
+        width: Some(80),
+        ..PrettyOptions::default()
+    };
     loop {
         if proof.goals.is_empty() {
             println!("Goals: None")
         } else {
             for (index, goal) in proof.goals.iter().enumerate() {
                 if index == proof.active_goal_index() {
-                    for (index_hyp, hyp) in goal.1.iter().enumerate() {
-                        println!(" Hyp {} : {}", index_hyp, hyp.to_string());
+                    let names = proof.hyp_names(index);
+                    for (name, hyp) in names.iter().zip(goal.1.iter()) {
+                        print_prop(&format!(" Hyp {} : ", name), hyp, &display);
                     }
                     println!("-----");
-                    println!(" Goal : {}", goal.0.to_string());
+                    print_prop(" Goal : ", &goal.0, &display);
                 }
             }
+            if proof.logic() != logic::Logic::Intuitionistic {
+                println!("Logic: {}", proof.logic());
+            }
             println!(
                 "Goals: {} (active: n°{}) ",
                 proof.number_of_goals(),
@@ -235,7 +508,7 @@ pub fn repl() {
             .read_line(&mut buffer)
             .expect("Unable to read from stdin");
         let proof_before = proof.clone();
-        match parse_input(&mut proof, &mut prevs, &buffer) {
+        match parse_input(&mut proof, &mut prevs, &mut display, &buffer) {
             Ok(flag) => {
                 if flag == 0 {
                     exit(0);
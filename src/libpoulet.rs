@@ -1,3 +1,16 @@
 pub mod backtrack;
+pub mod derivation;
+pub mod fol;
+pub mod g4ip;
+pub mod intern;
+pub mod kernel;
+pub mod kripke;
 pub mod logic;
+pub mod normal;
+pub mod pretty;
+pub mod sat;
+pub mod script;
 pub mod strategies;
+pub mod tactics;
+pub mod terms;
+pub mod tptp;
@@ -1,7 +1,34 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use crate::libpoulet::g4ip;
+use crate::libpoulet::logic;
+use crate::libpoulet::sat;
 use crate::libpoulet::strategies;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchMode {
+    // the original unbounded search, following strategy priorities
+    DepthFirst,
+    // depth-first searches bounded by 1, 2, ... up to the given depth
+    IterativeDeepening(usize),
+    // explores the cheapest state first, giving up after the given number of states
+    BestFirst(usize),
+}
+
+// a goal that is not even classically valid can never be proven, so no
+// state containing one is worth exploring; the check says nothing about
+// first-order goals, those are never pruned
+fn refuted(proof: &strategies::Proof) -> bool {
+    proof.goals.iter().any(|(goal, hyps)| {
+        strategies::is_propositional(goal, hyps) && !sat::classically_valid(hyps, goal)
+    })
+}
+
 pub fn auto(proof: &strategies::Proof) -> Result<Vec<(usize, usize, strategies::StrategyArg)>, ()> {
-    let mut visited_states: Vec<strategies::Proof> = vec![];
+    let mut visited_states: HashSet<strategies::Proof> = HashSet::new();
     let mut steps: Vec<(usize, usize, strategies::StrategyArg)> = vec![];
 
     let mut starting_proof = proof.clone();
@@ -9,19 +36,25 @@ pub fn auto(proof: &strategies::Proof) -> Result<Vec<(usize, usize, strategies::
 
     fn local_backtrack(
         proof: &strategies::Proof,
-        visited_states: &mut Vec<strategies::Proof>,
+        visited_states: &mut HashSet<strategies::Proof>,
         steps: &mut Vec<(usize, usize, strategies::StrategyArg)>,
     ) -> Result<(), ()> {
         let mut local_proof = proof.clone();
         local_proof.clean();
         if visited_states.contains(&local_proof) {
             println!("{} | already visited", " ".repeat(steps.len()));
+            // forget the step that led back here
+            steps.pop();
+            return Err(());
+        } else if refuted(&local_proof) {
+            println!("{} | not classically valid", " ".repeat(steps.len()));
+            steps.pop();
             return Err(());
         } else {
-            visited_states.push(local_proof.clone());
+            visited_states.insert(local_proof.clone());
         }
         if !steps.is_empty() {
-            let (_, _, strat) = steps[steps.len() - 1];
+            let (_, _, strat) = &steps[steps.len() - 1];
             println!("{}{}", " ".repeat(steps.len()), strat)
         }
         if local_proof.goals.is_empty() {
@@ -30,10 +63,10 @@ pub fn auto(proof: &strategies::Proof) -> Result<Vec<(usize, usize, strategies::
         } else {
             let applicable_strats = local_proof.get_applicable_strategies();
             for elt in applicable_strats {
-                let (_, goalnum, strat) = elt;
+                let (_, goalnum, strat) = &elt;
                 let mut loop_proof = local_proof.clone();
-                if let Ok(()) = loop_proof.set_active_goal(goalnum) {
-                    if let Ok(()) = loop_proof.execute(&strat) {
+                if let Ok(()) = loop_proof.set_active_goal(*goalnum) {
+                    if let Ok(()) = loop_proof.execute(strat) {
                         steps.push(elt);
                         match local_backtrack(&loop_proof, visited_states, steps) {
                             Ok(()) => return Ok(()),
@@ -55,14 +88,201 @@ pub fn auto(proof: &strategies::Proof) -> Result<Vec<(usize, usize, strategies::
     }
 }
 
+pub fn auto_with(
+    proof: &strategies::Proof,
+    mode: SearchMode,
+) -> Result<Vec<(usize, usize, strategies::StrategyArg)>, ()> {
+    match mode {
+        SearchMode::DepthFirst => auto(proof),
+        SearchMode::IterativeDeepening(max_depth) => iterative_deepening(proof, max_depth),
+        SearchMode::BestFirst(max_states) => best_first(proof, max_states),
+    }
+}
+
+fn is_propositional(proof: &strategies::Proof) -> bool {
+    proof
+        .goals
+        .iter()
+        .all(|(goal, hyps)| strategies::is_propositional(goal, hyps))
+}
+
+fn decide(
+    proof: &strategies::Proof,
+) -> Result<Vec<(usize, usize, strategies::StrategyArg)>, String> {
+    if !is_propositional(proof) {
+        return Err(String::from("g4ip only decides propositional goals"));
+    }
+    if proof.logic() != logic::Logic::Intuitionistic {
+        return Err(String::from(
+            "g4ip only decides intuitionistic logic, try 'auto best'",
+        ));
+    }
+    g4ip::prove(proof).map_err(|err| err.to_string())
+}
+
+// the steps found by `auto <args>`; without a mode g4ip decides the
+// propositional intuitionistic goals and the other ones are searched
+pub fn search(
+    proof: &strategies::Proof,
+    args: &str,
+) -> Result<Vec<(usize, usize, strategies::StrategyArg)>, String> {
+    let mut args = args.split_whitespace();
+    let mode = args.next();
+    let bound = args.next().map(|arg| arg.parse::<usize>());
+    let mode = match (mode, bound) {
+        (None, _) if is_propositional(proof) && proof.logic() == logic::Logic::Intuitionistic => {
+            return decide(proof);
+        }
+        (None, _) => SearchMode::BestFirst(10000),
+        (Some("dfs"), None) => SearchMode::DepthFirst,
+        (Some("id"), None) => SearchMode::IterativeDeepening(20),
+        (Some("id"), Some(Ok(depth))) => SearchMode::IterativeDeepening(depth),
+        (Some("best"), None) => SearchMode::BestFirst(10000),
+        (Some("best"), Some(Ok(states))) => SearchMode::BestFirst(states),
+        (Some("g4ip"), None) => return decide(proof),
+        _ => {
+            return Err(String::from(
+                "arguments incorrect: [g4ip | dfs | id <max depth> | best <max states>]",
+            ));
+        }
+    };
+    auto_with(proof, mode).map_err(|()| String::from("Could not solve using auto"))
+}
+
+// every state reachable in one step from a cleaned state, cleaned as well,
+// leaving out the refuted ones
+fn successors(
+    proof: &strategies::Proof,
+) -> Vec<((usize, usize, strategies::StrategyArg), strategies::Proof)> {
+    let mut result = vec![];
+    for elt in proof.get_applicable_strategies() {
+        let (_, goalnum, strat) = &elt;
+        let mut next = proof.clone();
+        if next.set_active_goal(*goalnum).is_ok() && next.execute(strat).is_ok() {
+            next.clean();
+            if !refuted(&next) {
+                result.push((elt, next));
+            }
+        }
+    }
+    result
+}
+
+fn iterative_deepening(
+    proof: &strategies::Proof,
+    max_depth: usize,
+) -> Result<Vec<(usize, usize, strategies::StrategyArg)>, ()> {
+    // a state is only worth revisiting with more remaining depth than before
+    fn depth_limited(
+        proof: &strategies::Proof,
+        depth: usize,
+        visited_states: &mut HashMap<strategies::Proof, usize>,
+        steps: &mut Vec<(usize, usize, strategies::StrategyArg)>,
+    ) -> bool {
+        if proof.goals.is_empty() {
+            return true;
+        }
+        if depth == 0 || visited_states.get(proof).is_some_and(|d| *d >= depth) {
+            return false;
+        }
+        visited_states.insert(proof.clone(), depth);
+        for (elt, next) in successors(proof) {
+            steps.push(elt);
+            if depth_limited(&next, depth - 1, visited_states, steps) {
+                return true;
+            }
+            steps.pop();
+        }
+        false
+    }
+
+    let mut starting_proof = proof.clone();
+    starting_proof.clean();
+    if refuted(&starting_proof) {
+        return Err(());
+    }
+    for depth in 0..=max_depth {
+        let mut steps = vec![];
+        if depth_limited(&starting_proof, depth, &mut HashMap::new(), &mut steps) {
+            return Ok(steps);
+        }
+    }
+    Err(())
+}
+
+// strategy priorities paid so far plus the size of what remains to prove
+fn remaining_size(proof: &strategies::Proof) -> usize {
+    proof.goals.iter().map(|goal| goal.0.items()).sum()
+}
+
+fn best_first(
+    proof: &strategies::Proof,
+    max_states: usize,
+) -> Result<Vec<(usize, usize, strategies::StrategyArg)>, ()> {
+    let mut starting_proof = proof.clone();
+    starting_proof.clean();
+    if refuted(&starting_proof) {
+        return Err(());
+    }
+    let mut visited_states: HashSet<strategies::Proof> = HashSet::new();
+    // (state, steps to reach it, priorities paid to reach it)
+    let mut nodes = vec![(starting_proof, vec![], 0)];
+    // ties are broken by insertion order to keep the search deterministic
+    let mut queue = BinaryHeap::new();
+    queue.push(Reverse((remaining_size(&nodes[0].0), 0)));
+
+    while let Some(Reverse((_, index))) = queue.pop() {
+        let (state, steps, paid) = nodes[index].clone();
+        if state.goals.is_empty() {
+            return Ok(steps);
+        }
+        if visited_states.contains(&state) {
+            continue;
+        }
+        if visited_states.len() >= max_states {
+            return Err(());
+        }
+        visited_states.insert(state.clone());
+        for (elt, next) in successors(&state) {
+            let next_paid = paid + elt.0;
+            let mut next_steps = steps.clone();
+            next_steps.push(elt);
+            queue.push(Reverse((next_paid + remaining_size(&next), nodes.len())));
+            nodes.push((next, next_steps, next_paid));
+        }
+    }
+    Err(())
+}
+
+// re-executes the steps returned by `auto`, cleaning before each one like
+// the search does, so that goal indices line up
+pub fn replay(
+    proof: &strategies::Proof,
+    steps: &[(usize, usize, strategies::StrategyArg)],
+) -> Result<strategies::Proof, strategies::StrategyError> {
+    let mut replayed = proof.clone();
+    for (_, goalnum, strat) in steps {
+        replayed.clean();
+        replayed.set_active_goal(*goalnum)?;
+        replayed.execute(strat)?;
+    }
+    replayed.clean();
+    Ok(replayed)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::libpoulet::strategies::Proof;
 
-    use crate::libpoulet::logic::Prop;
+    use crate::libpoulet::kernel;
+    use crate::libpoulet::logic::{Logic, Prop};
 
     use super::*;
 
+    fn h(n: usize) -> String {
+        format!("H{}", n)
+    }
+
     #[test]
     fn empty() {
         let empty_proof = Proof::new();
@@ -88,8 +308,8 @@ mod tests {
         assert_eq!(
             auto(&proof),
             Ok(vec![
-                (3, 0, strategies::StrategyArg::Intro),
-                (1, 0, strategies::StrategyArg::Exact(0))
+                (3, 0, strategies::StrategyArg::Intro(None)),
+                (1, 0, strategies::StrategyArg::Exact(h(0)))
             ])
         )
     }
@@ -113,4 +333,153 @@ mod tests {
         ));
         assert_eq!(auto(&proof), Err(()))
     }
+
+    #[test]
+    fn or_elimination_sound() {
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::imply(
+            Prop::or(Prop::Name(String::from("a")), Prop::Name(String::from("b"))),
+            Prop::Name(String::from("a")),
+        ));
+        assert_eq!(auto(&proof), Err(()));
+    }
+
+    #[test]
+    fn or_elimination_commute() {
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::imply(
+            Prop::or(Prop::Name(String::from("a")), Prop::Name(String::from("b"))),
+            Prop::or(Prop::Name(String::from("b")), Prop::Name(String::from("a"))),
+        ));
+        let steps = auto(&proof).unwrap();
+        assert!(replay(&proof, &steps).unwrap().goals.is_empty());
+    }
+
+    #[test]
+    fn replay_builds_term() {
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::imply(
+            Prop::and(Prop::Name(String::from("a")), Prop::Name(String::from("b"))),
+            Prop::and(Prop::Name(String::from("b")), Prop::Name(String::from("a"))),
+        ));
+        let steps = auto(&proof).unwrap();
+        let replayed = replay(&proof, &steps).unwrap();
+        assert!(replayed.goals.is_empty());
+        let terms = replayed.finished_terms().unwrap();
+        assert_eq!(terms.len(), 1);
+        assert_eq!(terms[0].0.goal, proof.goals[0].0);
+        assert_eq!(kernel::check_theorem(&terms[0].0, &terms[0].1), Ok(()));
+    }
+
+    #[test]
+    fn classical_pruning() {
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::parse_infix("(a | b) ^ (c => d) => b | a").unwrap());
+        let mut wrong_side = proof.clone();
+        for strat in [
+            strategies::StrategyArg::Intro(None),
+            strategies::StrategyArg::HypSplit(h(0)),
+            strategies::StrategyArg::OrSplit(true),
+        ] {
+            assert_eq!(wrong_side.execute(&strat), Ok(()));
+        }
+        assert!(refuted(&wrong_side));
+        assert!(!refuted(&proof));
+        let steps = auto_with(&proof, SearchMode::IterativeDeepening(10)).unwrap();
+        assert!(replay(&proof, &steps).unwrap().goals.is_empty());
+
+        let mut impossible = Proof::new();
+        impossible.add_goal_from_prop(Prop::parse_infix("(a => b) => (b => c) => c").unwrap());
+        assert!(refuted(&impossible));
+        assert!(successors(&impossible).is_empty());
+    }
+
+    #[test]
+    fn notations() {
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::parse_infix("!(a | b) <=> !a ^ !b").unwrap());
+        let steps = auto(&proof).unwrap();
+        assert!(
+            steps
+                .iter()
+                .any(|(_, _, strat)| { matches!(strat, strategies::StrategyArg::UnfoldIff(None)) })
+        );
+        let solved = replay(&proof, &steps).unwrap();
+        assert!(solved.goals.is_empty());
+        let terms = solved.finished_terms().unwrap();
+        assert_eq!(kernel::check_theorem(&terms[0].0, &terms[0].1), Ok(()));
+    }
+
+    fn tricky() -> Proof {
+        // depth-first commits to the first conjunct's long detour
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(
+            Prop::parse_infix("(a => b => c) => (a ^ b) => (c | (a => a))").unwrap(),
+        );
+        proof
+    }
+
+    #[test]
+    fn iterative_deepening_shortest() {
+        let proof = tricky();
+        let dfs = auto(&proof).unwrap();
+        let steps = auto_with(&proof, SearchMode::IterativeDeepening(10)).unwrap();
+        assert!(steps.len() <= dfs.len());
+        assert!(replay(&proof, &steps).unwrap().goals.is_empty());
+        assert_eq!(
+            auto_with(&proof, SearchMode::IterativeDeepening(steps.len() - 1)),
+            Err(())
+        );
+    }
+
+    #[test]
+    fn best_first() {
+        let proof = tricky();
+        let steps = auto_with(&proof, SearchMode::BestFirst(1000)).unwrap();
+        assert!(replay(&proof, &steps).unwrap().goals.is_empty());
+        assert_eq!(auto_with(&proof, SearchMode::BestFirst(1)), Err(()));
+
+        let mut impossible = Proof::new();
+        impossible.add_goal_from_prop(Prop::parse_infix("a | b => a").unwrap());
+        assert_eq!(auto_with(&impossible, SearchMode::BestFirst(1000)), Err(()));
+        assert_eq!(
+            auto_with(&impossible, SearchMode::IterativeDeepening(6)),
+            Err(())
+        );
+        assert_eq!(
+            auto_with(&Proof::new(), SearchMode::BestFirst(0)),
+            Ok(vec![])
+        );
+    }
+
+    #[test]
+    fn first_order() {
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(
+            Prop::parse_infix("(forall x. P(x) ^ Q(x)) => (exists y. P(y)) => exists z. Q(z)")
+                .unwrap(),
+        );
+        assert!(!refuted(&proof));
+        let steps = auto_with(&proof, SearchMode::BestFirst(1000)).unwrap();
+        let solved = replay(&proof, &steps).unwrap();
+        assert!(solved.goals.is_empty());
+        let terms = solved.finished_terms().unwrap();
+        assert_eq!(kernel::check_theorem(&terms[0].0, &terms[0].1), Ok(()));
+
+        let mut wrong = Proof::new();
+        wrong.add_goal_from_prop(Prop::parse_infix("(exists x. P(x)) => forall y. P(y)").unwrap());
+        assert_eq!(auto_with(&wrong, SearchMode::BestFirst(1000)), Err(()));
+    }
+
+    #[test]
+    fn classical() {
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::parse_infix("((a => b) => a) => a").unwrap());
+        assert_eq!(auto_with(&proof, SearchMode::BestFirst(1000)), Err(()));
+        proof.set_logic(Logic::Classical);
+        let steps = auto_with(&proof, SearchMode::BestFirst(1000)).unwrap();
+        let solved = replay(&proof, &steps).unwrap();
+        let terms = solved.finished_terms().unwrap();
+        assert_eq!(kernel::check_theorem(&terms[0].0, &terms[0].1), Ok(()));
+    }
 }
@@ -0,0 +1,466 @@
+use std::{collections::HashMap, fmt};
+
+use crate::libpoulet::fol::{self, FoTerm};
+use crate::libpoulet::intern::PropRef;
+use crate::libpoulet::kernel::KernelError;
+use crate::libpoulet::logic::Prop;
+use crate::libpoulet::terms::{Term, Theorem};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rule {
+    Axiom,
+    ImpliesIntro,
+    ImpliesElim,
+    AndIntro,
+    AndElimLeft,
+    AndElimRight,
+    OrIntroLeft,
+    OrIntroRight,
+    OrElim,
+    FalseElim,
+    TrueIntro,
+    ForallIntro,
+    ForallElim,
+    ExistsIntro,
+    ExistsElim,
+    ExcludedMiddle,
+    // a lemma proven first, then assumed to prove the goal
+    Cut,
+    // goal that is not proven yet
+    Open,
+}
+
+// natural deduction tree, every node being a sequent `hyps |- goal`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Derivation {
+    pub hyps: Vec<PropRef>,
+    pub goal: PropRef,
+    pub rule: Rule,
+    pub children: Vec<Derivation>,
+}
+
+impl Rule {
+    fn latex(&self) -> &'static str {
+        match self {
+            Rule::Axiom => "Ax",
+            Rule::ImpliesIntro => "$\\to$I",
+            Rule::ImpliesElim => "$\\to$E",
+            Rule::AndIntro => "$\\land$I",
+            Rule::AndElimLeft => "$\\land$E$_1$",
+            Rule::AndElimRight => "$\\land$E$_2$",
+            Rule::OrIntroLeft => "$\\lor$I$_1$",
+            Rule::OrIntroRight => "$\\lor$I$_2$",
+            Rule::OrElim => "$\\lor$E",
+            Rule::FalseElim => "$\\bot$E",
+            Rule::TrueIntro => "$\\top$I",
+            Rule::ForallIntro => "$\\forall$I",
+            Rule::ForallElim => "$\\forall$E",
+            Rule::ExistsIntro => "$\\exists$I",
+            Rule::ExistsElim => "$\\exists$E",
+            Rule::ExcludedMiddle => "EM",
+            Rule::Cut => "Cut",
+            Rule::Open => "",
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rule::Axiom => write!(f, "Ax"),
+            Rule::ImpliesIntro => write!(f, "=>I"),
+            Rule::ImpliesElim => write!(f, "=>E"),
+            Rule::AndIntro => write!(f, "^I"),
+            Rule::AndElimLeft => write!(f, "^E1"),
+            Rule::AndElimRight => write!(f, "^E2"),
+            Rule::OrIntroLeft => write!(f, "|I1"),
+            Rule::OrIntroRight => write!(f, "|I2"),
+            Rule::OrElim => write!(f, "|E"),
+            Rule::FalseElim => write!(f, "FE"),
+            Rule::TrueIntro => write!(f, "TI"),
+            Rule::ForallIntro => write!(f, "forallI"),
+            Rule::ForallElim => write!(f, "forallE"),
+            Rule::ExistsIntro => write!(f, "existsI"),
+            Rule::ExistsElim => write!(f, "existsE"),
+            Rule::ExcludedMiddle => write!(f, "EM"),
+            Rule::Cut => write!(f, "cut"),
+            Rule::Open => write!(f, "?"),
+        }
+    }
+}
+
+fn latex_prop(prop: &Prop) -> String {
+    match prop {
+        Prop::True => String::from("\\top"),
+        Prop::False => String::from("\\bot"),
+        Prop::Name(name) => name.replace('_', "\\_"),
+        Prop::Pred(p, args) => format!(
+            "{}({})",
+            p.replace('_', "\\_"),
+            fol::join_terms(args).replace('_', "\\_")
+        ),
+        Prop::Forall(x, a) => format!("(\\forall {}.\\, {})", x, latex_prop(a)),
+        Prop::Exists(x, a) => format!("(\\exists {}.\\, {})", x, latex_prop(a)),
+        Prop::Implies(a, b) => format!("({} \\to {})", latex_prop(a), latex_prop(b)),
+        Prop::And(a, b) => format!("({} \\land {})", latex_prop(a), latex_prop(b)),
+        Prop::Or(a, b) => format!("({} \\lor {})", latex_prop(a), latex_prop(b)),
+        Prop::Not(a) => format!("\\neg {}", latex_prop(a)),
+        Prop::Iff(a, b) => format!("({} \\leftrightarrow {})", latex_prop(a), latex_prop(b)),
+    }
+}
+
+// a term whose type did not have the expected shape
+fn shape_mismatch(expected: &'static str, found: &Prop) -> KernelError {
+    KernelError::ShapeMismatch {
+        expected,
+        found: found.clone(),
+    }
+}
+
+struct Builder<'a> {
+    ctx: Vec<(usize, PropRef)>,
+    holes: &'a HashMap<usize, PropRef>,
+}
+
+impl Builder<'_> {
+    fn node(&self, goal: PropRef, rule: Rule, children: Vec<Derivation>) -> Derivation {
+        Derivation {
+            hyps: self.ctx.iter().map(|(_, hyp)| hyp.clone()).collect(),
+            goal,
+            rule,
+            children,
+        }
+    }
+
+    fn bound(&mut self, x: usize, prop: PropRef, term: &Term) -> Result<Derivation, KernelError> {
+        self.ctx.push((x, prop));
+        let derivation = self.build(term);
+        self.ctx.pop();
+        derivation
+    }
+
+    fn build(&mut self, term: &Term) -> Result<Derivation, KernelError> {
+        match term {
+            Term::Var(x) => {
+                let prop = self
+                    .ctx
+                    .iter()
+                    .rev()
+                    .find(|(y, _)| y == x)
+                    .map(|(_, prop)| prop.clone())
+                    .ok_or(KernelError::UnboundVariable(*x))?;
+                Ok(self.node(prop, Rule::Axiom, vec![]))
+            }
+            Term::Hole(h) => {
+                let prop = self.holes.get(h).ok_or(KernelError::OpenHole(*h))?;
+                Ok(self.node(prop.clone(), Rule::Open, vec![]))
+            }
+            Term::Unit => Ok(self.node(PropRef::new(Prop::True), Rule::TrueIntro, vec![])),
+            Term::Lambda(x, a, t) => {
+                let child = self.bound(*x, a.clone(), t)?;
+                let goal = PropRef::new(Prop::Implies(a.clone(), child.goal.clone()));
+                Ok(self.node(goal, Rule::ImpliesIntro, vec![child]))
+            }
+            Term::App(t, u) => {
+                let function = self.build(t)?;
+                let argument = self.build(u)?;
+                match function.goal.unfold_head() {
+                    Prop::Implies(_, b) => {
+                        Ok(self.node(b.clone(), Rule::ImpliesElim, vec![function, argument]))
+                    }
+                    other => Err(shape_mismatch("an implication", &other)),
+                }
+            }
+            Term::Pair(t, u) => {
+                let left = self.build(t)?;
+                let right = self.build(u)?;
+                let goal = PropRef::new(Prop::And(left.goal.clone(), right.goal.clone()));
+                Ok(self.node(goal, Rule::AndIntro, vec![left, right]))
+            }
+            Term::Fst(t) | Term::Snd(t) => {
+                let child = self.build(t)?;
+                match (term, child.goal.unfold_head()) {
+                    (Term::Fst(_), Prop::And(a, _)) => {
+                        Ok(self.node(a.clone(), Rule::AndElimLeft, vec![child]))
+                    }
+                    (_, Prop::And(_, b)) => {
+                        Ok(self.node(b.clone(), Rule::AndElimRight, vec![child]))
+                    }
+                    (_, other) => Err(shape_mismatch("a conjunction", &other)),
+                }
+            }
+            Term::Inl(t, b) => {
+                let child = self.build(t)?;
+                let goal = PropRef::new(Prop::Or(child.goal.clone(), b.clone()));
+                Ok(self.node(goal, Rule::OrIntroLeft, vec![child]))
+            }
+            Term::Inr(a, t) => {
+                let child = self.build(t)?;
+                let goal = PropRef::new(Prop::Or(a.clone(), child.goal.clone()));
+                Ok(self.node(goal, Rule::OrIntroRight, vec![child]))
+            }
+            Term::Case(t, x, u, y, v) => {
+                let scrutinee = self.build(t)?;
+                let (a, b) = match scrutinee.goal.as_ref() {
+                    Prop::Or(a, b) => (a.clone(), b.clone()),
+                    other => return Err(shape_mismatch("a disjunction", other)),
+                };
+                let left = self.bound(*x, a, u)?;
+                let right = self.bound(*y, b, v)?;
+                Ok(self.node(
+                    left.goal.clone(),
+                    Rule::OrElim,
+                    vec![scrutinee, left, right],
+                ))
+            }
+            Term::Let(x, u, t) => {
+                let definition = self.build(u)?;
+                let body = self.bound(*x, definition.goal.clone(), t)?;
+                Ok(self.node(body.goal.clone(), Rule::Cut, vec![definition, body]))
+            }
+            Term::Absurd(t, a) => {
+                let child = self.build(t)?;
+                Ok(self.node(a.clone(), Rule::FalseElim, vec![child]))
+            }
+            Term::ForallIntro(x, t) => {
+                let child = self.build(t)?;
+                let goal = PropRef::new(Prop::Forall(x.clone(), child.goal.clone()));
+                Ok(self.node(goal, Rule::ForallIntro, vec![child]))
+            }
+            Term::Inst(t, u) => {
+                let child = self.build(t)?;
+                match child.goal.as_ref() {
+                    Prop::Forall(x, a) => {
+                        let goal = PropRef::new(a.subst(x, u));
+                        Ok(self.node(goal, Rule::ForallElim, vec![child]))
+                    }
+                    other => Err(shape_mismatch("a universal quantification", other)),
+                }
+            }
+            Term::Witness(_, t, a) => {
+                let child = self.build(t)?;
+                Ok(self.node(a.clone(), Rule::ExistsIntro, vec![child]))
+            }
+            Term::ExcludedMiddle(a) => {
+                let goal = Prop::Or(a.clone(), PropRef::new(Prop::Not(a.clone())));
+                Ok(self.node(PropRef::new(goal), Rule::ExcludedMiddle, vec![]))
+            }
+            Term::Unpack(t, x, h, u) => {
+                let scrutinee = self.build(t)?;
+                let instance = match scrutinee.goal.as_ref() {
+                    Prop::Exists(y, a) => a.subst(y, &FoTerm::Var(x.clone())),
+                    other => return Err(shape_mismatch("an existential quantification", other)),
+                };
+                let body = self.bound(*h, PropRef::new(instance), u)?;
+                Ok(self.node(body.goal.clone(), Rule::ExistsElim, vec![scrutinee, body]))
+            }
+        }
+    }
+}
+
+impl Derivation {
+    // `holes` gives the goal each remaining hole of the term stands for
+    pub fn from_term(
+        theorem: &Theorem,
+        term: &Term,
+        holes: &HashMap<usize, PropRef>,
+    ) -> Result<Derivation, KernelError> {
+        let mut builder = Builder {
+            ctx: theorem.hyps.clone(),
+            holes,
+        };
+        builder.build(term)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.rule != Rule::Open && self.children.iter().all(|c| c.is_complete())
+    }
+
+    fn sequent_latex(&self) -> String {
+        let hyps: Vec<String> = self.hyps.iter().map(|hyp| latex_prop(hyp)).collect();
+        format!("${} \\vdash {}$", hyps.join(", "), latex_prop(&self.goal))
+    }
+
+    fn write_latex(&self, out: &mut String) {
+        if self.rule == Rule::Open {
+            out.push_str(&format!("\\AxiomC{{{}}}\n", self.sequent_latex()));
+            return;
+        }
+        if self.children.is_empty() {
+            out.push_str("\\AxiomC{}\n");
+        }
+        for child in self.children.iter() {
+            child.write_latex(out);
+        }
+        let inference = match self.children.len() {
+            0 | 1 => "UnaryInfC",
+            2 => "BinaryInfC",
+            _ => "TrinaryInfC",
+        };
+        out.push_str(&format!(
+            "\\RightLabel{{\\scriptsize {}}}\n\\{}{{{}}}\n",
+            self.rule.latex(),
+            inference,
+            self.sequent_latex()
+        ));
+    }
+
+    // a bussproofs `prooftree` environment, to be used with \usepackage{bussproofs}
+    pub fn to_latex(&self) -> String {
+        let mut out = String::from("\\begin{prooftree}\n");
+        self.write_latex(&mut out);
+        out.push_str("\\end{prooftree}\n");
+        out
+    }
+
+    fn write_tree(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+        let hyps: Vec<String> = self.hyps.iter().map(|hyp| hyp.to_string()).collect();
+        writeln!(
+            f,
+            "{}[{}] {} |- {}",
+            "  ".repeat(depth),
+            self.rule,
+            hyps.join(", "),
+            self.goal.to_string()
+        )?;
+        for child in self.children.iter() {
+            child.write_tree(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Derivation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_tree(f, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::libpoulet::strategies::{Proof, StrategyArg};
+
+    fn h(n: usize) -> String {
+        format!("H{}", n)
+    }
+
+    #[test]
+    fn from_session() {
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::parse_infix("a ^ b => b").unwrap());
+        assert_eq!(proof.execute(&StrategyArg::Intro(None)), Ok(()));
+        let open = proof.derivations();
+        assert_eq!(open.len(), 1);
+        assert!(!open[0].is_complete());
+        assert_eq!(
+            open[0].to_string(),
+            "[=>I]  |- ( ( a ^ b ) => b )\n  [?] ( a ^ b ) |- b\n"
+        );
+
+        assert_eq!(proof.execute(&StrategyArg::HypSplit(h(0))), Ok(()));
+        // both halves are hypotheses of the open goal
+        let split = proof.derivations();
+        let mut leaf = &split[0];
+        while leaf.rule != Rule::Open {
+            leaf = leaf.children.last().unwrap();
+        }
+        let hyps: Vec<String> = leaf.hyps.iter().map(|hyp| hyp.to_string()).collect();
+        assert_eq!(hyps, ["( a ^ b )", "a", "b"]);
+
+        assert_eq!(proof.execute(&StrategyArg::Exact(h(1))), Ok(()));
+        let done = proof.derivations();
+        assert!(done[0].is_complete());
+        assert_eq!(
+            done[0].to_string(),
+            "[=>I]  |- ( ( a ^ b ) => b )\n  \
+             [cut] ( a ^ b ) |- b\n    \
+             [^E1] ( a ^ b ) |- a\n      \
+             [Ax] ( a ^ b ) |- ( a ^ b )\n    \
+             [cut] ( a ^ b ), a |- b\n      \
+             [^E2] ( a ^ b ), a |- b\n        \
+             [Ax] ( a ^ b ), a |- ( a ^ b )\n      \
+             [Ax] ( a ^ b ), a, b |- b\n"
+        );
+        assert_eq!(
+            done[0].to_latex(),
+            "\\begin{prooftree}\n\
+             \\AxiomC{}\n\
+             \\RightLabel{\\scriptsize Ax}\n\
+             \\UnaryInfC{$(a \\land b) \\vdash (a \\land b)$}\n\
+             \\RightLabel{\\scriptsize $\\land$E$_1$}\n\
+             \\UnaryInfC{$(a \\land b) \\vdash a$}\n\
+             \\AxiomC{}\n\
+             \\RightLabel{\\scriptsize Ax}\n\
+             \\UnaryInfC{$(a \\land b), a \\vdash (a \\land b)$}\n\
+             \\RightLabel{\\scriptsize $\\land$E$_2$}\n\
+             \\UnaryInfC{$(a \\land b), a \\vdash b$}\n\
+             \\AxiomC{}\n\
+             \\RightLabel{\\scriptsize Ax}\n\
+             \\UnaryInfC{$(a \\land b), a, b \\vdash b$}\n\
+             \\RightLabel{\\scriptsize Cut}\n\
+             \\BinaryInfC{$(a \\land b), a \\vdash b$}\n\
+             \\RightLabel{\\scriptsize Cut}\n\
+             \\BinaryInfC{$(a \\land b) \\vdash b$}\n\
+             \\RightLabel{\\scriptsize $\\to$I}\n\
+             \\UnaryInfC{$ \\vdash ((a \\land b) \\to b)$}\n\
+             \\end{prooftree}\n"
+        );
+    }
+
+    #[test]
+    fn binary_and_trinary() {
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::parse_infix("a | b => b | a").unwrap());
+        assert_eq!(proof.execute(&StrategyArg::Intro(None)), Ok(()));
+        assert_eq!(proof.execute(&StrategyArg::Destruct(h(0))), Ok(()));
+        assert_eq!(proof.execute(&StrategyArg::OrSplit(false)), Ok(()));
+        assert_eq!(proof.execute(&StrategyArg::Exact(h(0))), Ok(()));
+        let _ = proof.set_active_goal(1);
+        let latex = proof.derivations()[0].to_latex();
+        assert!(latex.contains("\\TrinaryInfC{$(a \\lor b) \\vdash (b \\lor a)$}"));
+        assert!(latex.contains("\\AxiomC{$(a \\lor b), b \\vdash (b \\lor a)$}"));
+        assert!(!latex.contains("BinaryInfC"));
+
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::parse_infix("(a => b) => a => b").unwrap());
+        assert_eq!(proof.execute(&StrategyArg::Intro(None)), Ok(()));
+        assert_eq!(proof.execute(&StrategyArg::Intro(None)), Ok(()));
+        assert_eq!(proof.execute(&StrategyArg::Apply(h(0))), Ok(()));
+        assert_eq!(proof.execute(&StrategyArg::Exact(h(1))), Ok(()));
+        let derivation = &proof.derivations()[0];
+        assert_eq!(derivation.children[0].children[0].rule, Rule::ImpliesElim);
+        assert!(derivation.to_latex().contains("\\BinaryInfC"));
+    }
+
+    #[test]
+    fn quantifiers() {
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::parse_infix("(forall x. P(x)) => exists y. P(y)").unwrap());
+        let x = crate::libpoulet::fol::FoTerm::Var(String::from("x"));
+        for strat in [
+            StrategyArg::Intro(None),
+            StrategyArg::Instantiate(h(0), x.clone()),
+            StrategyArg::Witness(x),
+            StrategyArg::Exact(h(1)),
+        ] {
+            assert_eq!(proof.execute(&strat), Ok(()));
+        }
+        let derivation = &proof.derivations()[0];
+        assert!(derivation.is_complete());
+        assert_eq!(
+            derivation.to_string(),
+            "[=>I]  |- ( ( forall x. P(x) ) => ( exists y. P(y) ) )\n  \
+             [cut] ( forall x. P(x) ) |- ( exists y. P(y) )\n    \
+             [forallE] ( forall x. P(x) ) |- P(x)\n      \
+             [Ax] ( forall x. P(x) ) |- ( forall x. P(x) )\n    \
+             [existsI] ( forall x. P(x) ), P(x) |- ( exists y. P(y) )\n      \
+             [Ax] ( forall x. P(x) ), P(x) |- P(x)\n"
+        );
+        assert!(
+            derivation
+                .to_latex()
+                .contains("\\UnaryInfC{$(\\forall x.\\, P(x)) \\vdash P(x)$}")
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::libpoulet::logic::Prop;
+
+// Terms of first-order formulas. A bare name is a variable, bound by a
+// quantifier or free, free variables standing for arbitrary individuals as
+// constants would.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FoTerm {
+    Var(String),
+    // function symbol applied to at least one argument
+    App(String, Vec<FoTerm>),
+}
+
+impl FoTerm {
+    pub fn vars(&self) -> BTreeSet<String> {
+        let mut vars = BTreeSet::new();
+        self.collect_vars(&mut vars);
+        vars
+    }
+
+    fn collect_vars(&self, vars: &mut BTreeSet<String>) {
+        match self {
+            FoTerm::Var(x) => {
+                vars.insert(x.clone());
+            }
+            FoTerm::App(_, args) => args.iter().for_each(|arg| arg.collect_vars(vars)),
+        }
+    }
+
+    pub fn subst(&self, x: &str, t: &FoTerm) -> FoTerm {
+        match self {
+            FoTerm::Var(y) if y == x => t.clone(),
+            FoTerm::Var(_) => self.clone(),
+            FoTerm::App(f, args) => {
+                FoTerm::App(f.clone(), args.iter().map(|arg| arg.subst(x, t)).collect())
+            }
+        }
+    }
+
+    // without spaces, so that it stays a single RPN token
+    pub fn to_string_rpn(&self) -> String {
+        match self {
+            FoTerm::Var(x) => x.clone(),
+            FoTerm::App(f, args) => {
+                let args: Vec<String> = args.iter().map(|arg| arg.to_string_rpn()).collect();
+                format!("{}({})", f, args.join(","))
+            }
+        }
+    }
+}
+
+impl fmt::Display for FoTerm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FoTerm::Var(x) => write!(f, "{}", x),
+            FoTerm::App(name, args) => write!(f, "{}({})", name, join_terms(args)),
+        }
+    }
+}
+
+pub fn join_terms(terms: &[FoTerm]) -> String {
+    let terms: Vec<String> = terms.iter().map(|t| t.to_string()).collect();
+    terms.join(", ")
+}
+
+// `base` followed by as many primes as needed to avoid every name in `avoid`
+pub fn fresh_var(base: &str, avoid: &BTreeSet<String>) -> String {
+    let mut name = String::from(base);
+    while avoid.contains(&name) {
+        name.push('\'');
+    }
+    name
+}
+
+impl Prop {
+    pub fn forall(x: String, body: Prop) -> Prop {
+        Prop::Forall(x, body.into())
+    }
+
+    pub fn exists(x: String, body: Prop) -> Prop {
+        Prop::Exists(x, body.into())
+    }
+
+    pub fn is_propositional(&self) -> bool {
+        match self {
+            Prop::True | Prop::False | Prop::Name(_) => true,
+            Prop::Pred(_, _) | Prop::Forall(_, _) | Prop::Exists(_, _) => false,
+            Prop::Not(a) => a.is_propositional(),
+            Prop::Implies(a, b) | Prop::And(a, b) | Prop::Or(a, b) | Prop::Iff(a, b) => {
+                a.is_propositional() && b.is_propositional()
+            }
+        }
+    }
+
+    pub fn free_vars(&self) -> BTreeSet<String> {
+        let mut vars = BTreeSet::new();
+        self.collect_free_vars(&mut vars);
+        vars
+    }
+
+    fn collect_free_vars(&self, vars: &mut BTreeSet<String>) {
+        match self {
+            Prop::True | Prop::False | Prop::Name(_) => (),
+            Prop::Pred(_, args) => args.iter().for_each(|arg| arg.collect_vars(vars)),
+            Prop::Not(a) => a.collect_free_vars(vars),
+            Prop::Implies(a, b) | Prop::And(a, b) | Prop::Or(a, b) | Prop::Iff(a, b) => {
+                a.collect_free_vars(vars);
+                b.collect_free_vars(vars);
+            }
+            Prop::Forall(x, a) | Prop::Exists(x, a) => {
+                let mut inner = a.free_vars();
+                inner.remove(x);
+                vars.extend(inner);
+            }
+        }
+    }
+
+    // every free occurrence of `x` replaced by `t`, bound variables being
+    // renamed when they would capture a variable of `t`
+    pub fn subst(&self, x: &str, t: &FoTerm) -> Prop {
+        let go = |a: &Prop| a.subst(x, t);
+        match self {
+            Prop::True | Prop::False | Prop::Name(_) => self.clone(),
+            Prop::Pred(p, args) => {
+                Prop::Pred(p.clone(), args.iter().map(|arg| arg.subst(x, t)).collect())
+            }
+            Prop::Implies(a, b) => Prop::imply(go(a), go(b)),
+            Prop::And(a, b) => Prop::and(go(a), go(b)),
+            Prop::Or(a, b) => Prop::or(go(a), go(b)),
+            Prop::Not(a) => Prop::not(go(a)),
+            Prop::Iff(a, b) => Prop::equiv(go(a), go(b)),
+            Prop::Forall(y, a) | Prop::Exists(y, a) => {
+                let body_vars = a.free_vars();
+                if y == x || !body_vars.contains(x) {
+                    return self.clone();
+                }
+                let (y, a) = if t.vars().contains(y) {
+                    let mut avoid = body_vars;
+                    avoid.extend(t.vars());
+                    avoid.insert(String::from(x));
+                    let z = fresh_var(y, &avoid);
+                    let renamed = a.subst(y, &FoTerm::Var(z.clone()));
+                    (z, renamed)
+                } else {
+                    (y.clone(), a.as_ref().clone())
+                };
+                if let Prop::Forall(_, _) = self {
+                    Prop::forall(y, a.subst(x, t))
+                } else {
+                    Prop::exists(y, a.subst(x, t))
+                }
+            }
+        }
+    }
+
+    // equality up to the names of bound variables
+    pub fn alpha_eq(&self, other: &Prop) -> bool {
+        if self == other {
+            return true;
+        }
+        match (self, other) {
+            (Prop::Implies(a, b), Prop::Implies(c, d))
+            | (Prop::And(a, b), Prop::And(c, d))
+            | (Prop::Or(a, b), Prop::Or(c, d))
+            | (Prop::Iff(a, b), Prop::Iff(c, d)) => a.alpha_eq(c) && b.alpha_eq(d),
+            (Prop::Not(a), Prop::Not(b)) => a.alpha_eq(b),
+            (Prop::Forall(x, a), Prop::Forall(y, b)) | (Prop::Exists(x, a), Prop::Exists(y, b)) => {
+                let mut avoid = a.free_vars();
+                avoid.extend(b.free_vars());
+                let z = FoTerm::Var(fresh_var(x, &avoid));
+                a.subst(x, &z).alpha_eq(&b.subst(y, &z))
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prop(s: &str) -> Prop {
+        Prop::parse_infix(s).unwrap()
+    }
+
+    fn var(x: &str) -> FoTerm {
+        FoTerm::Var(String::from(x))
+    }
+
+    #[test]
+    fn free_vars() {
+        let vars = |s: &str| prop(s).free_vars().into_iter().collect::<Vec<String>>();
+        assert_eq!(vars("forall x. P(x, y) ^ Q(f(x, z))"), ["y", "z"]);
+        assert_eq!(vars("(exists x. P(x)) ^ Q(x)"), ["x"]);
+        assert_eq!(vars("a => b"), Vec::<String>::new());
+        assert!(prop("a => !b <=> T").is_propositional());
+        assert!(!prop("a => P(a)").is_propositional());
+    }
+
+    #[test]
+    fn substitution() {
+        assert_eq!(
+            prop("P(x) ^ forall x. Q(x)")
+                .subst("x", &FoTerm::App(String::from("f"), vec![var("y")])),
+            prop("P(f(y)) ^ forall x. Q(x)")
+        );
+        // y would be captured, the bound one is renamed
+        assert_eq!(
+            prop("forall y. R(x, y)").subst("x", &var("y")),
+            prop("forall y'. R(y, y')")
+        );
+        assert_eq!(
+            prop("exists y. R(x, y, y')").subst("x", &var("y")),
+            prop("exists y''. R(y, y'', y')")
+        );
+        assert_eq!(
+            prop("forall y. P(y)").subst("x", &var("y")),
+            prop("forall y. P(y)")
+        );
+    }
+
+    #[test]
+    fn alpha_equivalence() {
+        assert!(prop("forall x. P(x)").alpha_eq(&prop("forall y. P(y)")));
+        assert!(prop("forall x. exists y. R(x, y)").alpha_eq(&prop("forall y. exists x. R(y, x)")));
+        assert!(
+            !prop("forall x. exists y. R(x, y)").alpha_eq(&prop("forall y. exists x. R(x, y)"))
+        );
+        assert!(!prop("forall x. P(x)").alpha_eq(&prop("exists x. P(x)")));
+        assert!(!prop("forall x. P(x, z)").alpha_eq(&prop("forall z. P(z, z)")));
+        assert_eq!(fresh_var("x", &BTreeSet::from([String::from("x")])), "x'");
+    }
+}
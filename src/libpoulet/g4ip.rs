@@ -0,0 +1,350 @@
+use std::{error::Error, fmt};
+
+use crate::libpoulet::intern::PropRef;
+use crate::libpoulet::logic::Prop;
+use crate::libpoulet::sat;
+use crate::libpoulet::strategies::{Proof, StrategyArg};
+
+// Dyckhoff's contraction-free sequent calculus G4ip (also called LJT).
+// Every rule makes the sequent smaller for a well-founded order, so the
+// search always terminates, and the calculus is complete for intuitionistic
+// propositional logic: when the search fails the sequent is not a theorem.
+// Only the right or-rules and the left rule for nested implications need
+// backtracking, all the other rules are invertible.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotProvable {
+    pub hyps: Vec<PropRef>,
+    pub goal: PropRef,
+}
+
+impl fmt::Display for NotProvable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hyps: Vec<String> = self.hyps.iter().map(|hyp| hyp.to_string()).collect();
+        write!(
+            f,
+            "not a theorem of intuitionistic logic: {} |- {}",
+            hyps.join(", "),
+            self.goal.to_string()
+        )
+    }
+}
+
+impl Error for NotProvable {}
+
+// the hypothesis each rule works on, found back by value when replaying
+#[derive(Clone, Debug)]
+enum Rule {
+    TrueRight,
+    FalseLeft,
+    Axiom(PropRef),
+    AndLeft(PropRef),
+    OrLeft(PropRef),
+    // the atom and the implication it is the premise of
+    AtomImpliesLeft(PropRef, PropRef),
+    ImpliesLeft(PropRef),
+    AndRight,
+    ImpliesRight,
+    OrRight(bool),
+}
+
+#[derive(Clone, Debug)]
+struct Tree {
+    rule: Rule,
+    premises: Vec<Tree>,
+}
+
+// hypotheses are kept as sets, the way `Proof::clean` leaves them;
+// contraction being admissible in G4ip this loses no theorem
+fn normalize(mut hyps: Vec<PropRef>) -> Vec<PropRef> {
+    hyps.sort();
+    hyps.dedup();
+    hyps.retain(|hyp| *hyp.as_ref() != Prop::True);
+    hyps
+}
+
+fn replaced(hyps: &[PropRef], index: usize, new: Vec<PropRef>) -> Vec<PropRef> {
+    let mut hyps = hyps.to_vec();
+    hyps.remove(index);
+    hyps.extend(new);
+    normalize(hyps)
+}
+
+fn implies(a: &PropRef, b: &PropRef) -> PropRef {
+    PropRef::new(Prop::Implies(a.clone(), b.clone()))
+}
+
+fn node(rule: Rule, premises: Vec<Tree>) -> Option<Tree> {
+    Some(Tree { rule, premises })
+}
+
+fn search(hyps: &[PropRef], goal: &PropRef) -> Option<Tree> {
+    if *goal.as_ref() == Prop::True {
+        return node(Rule::TrueRight, vec![]);
+    }
+    if hyps.iter().any(|hyp| *hyp.as_ref() == Prop::False) {
+        return node(Rule::FalseLeft, vec![]);
+    }
+    if hyps.contains(goal) {
+        return node(Rule::Axiom(goal.clone()), vec![]);
+    }
+
+    // invertible left rules, the first one that applies is enough
+    for (index, hyp) in hyps.iter().enumerate() {
+        let (rule, new) = match hyp.as_ref() {
+            Prop::And(a, b) => (Rule::AndLeft(hyp.clone()), vec![a.clone(), b.clone()]),
+            Prop::Or(a, b) => {
+                let left = search(&replaced(hyps, index, vec![a.clone()]), goal)?;
+                let right = search(&replaced(hyps, index, vec![b.clone()]), goal)?;
+                return node(Rule::OrLeft(hyp.clone()), vec![left, right]);
+            }
+            Prop::Implies(a, c) => {
+                let rule = Rule::ImpliesLeft(hyp.clone());
+                match a.as_ref() {
+                    Prop::Name(_) if hyps.contains(a) => (
+                        Rule::AtomImpliesLeft(a.clone(), hyp.clone()),
+                        vec![c.clone()],
+                    ),
+                    Prop::True => (rule, vec![c.clone()]),
+                    Prop::False => (rule, vec![]),
+                    Prop::And(p, q) => (rule, vec![implies(p, &implies(q, c))]),
+                    Prop::Or(p, q) => (rule, vec![implies(p, c), implies(q, c)]),
+                    _ => continue,
+                }
+            }
+            _ => continue,
+        };
+        let premise = search(&replaced(hyps, index, new), goal)?;
+        return node(rule, vec![premise]);
+    }
+
+    // invertible right rules
+    match goal.as_ref() {
+        Prop::And(a, b) => {
+            let left = search(hyps, a)?;
+            let right = search(hyps, b)?;
+            return node(Rule::AndRight, vec![left, right]);
+        }
+        Prop::Implies(a, b) => {
+            let mut new_hyps = hyps.to_vec();
+            new_hyps.push(a.clone());
+            let premise = search(&normalize(new_hyps), b)?;
+            return node(Rule::ImpliesRight, vec![premise]);
+        }
+        _ => (),
+    }
+
+    // the remaining rules may fail where another choice succeeds
+    if let Prop::Or(a, b) = goal.as_ref() {
+        for (left, side) in [(true, a), (false, b)] {
+            if let Some(premise) = search(hyps, side) {
+                return node(Rule::OrRight(left), vec![premise]);
+            }
+        }
+    }
+    for (index, hyp) in hyps.iter().enumerate() {
+        if let Prop::Implies(a, c) = hyp.as_ref()
+            && let Prop::Implies(p, q) = a.as_ref()
+        {
+            let first_hyps = replaced(hyps, index, vec![implies(q, c), p.clone()]);
+            if let Some(first) = search(&first_hyps, q)
+                && let Some(second) = search(&replaced(hyps, index, vec![c.clone()]), goal)
+            {
+                return node(Rule::ImpliesLeft(hyp.clone()), vec![first, second]);
+            }
+        }
+    }
+    None
+}
+
+// the search only knows the connectives negations and biconditionals
+// stand for
+fn unfolded(prop: &PropRef) -> PropRef {
+    PropRef::new(prop.unfold_all())
+}
+
+pub fn provable(hyps: &[PropRef], goal: &PropRef) -> bool {
+    let hyps = hyps.iter().map(unfolded).collect();
+    search(&normalize(hyps), &unfolded(goal)).is_some()
+}
+
+// the strategy carrying out `rule` on a goal with these hypotheses, named
+// after `names`
+fn strategy(rule: &Rule, hyps: &[PropRef], names: &[String]) -> Option<StrategyArg> {
+    let find = |prop: &PropRef| {
+        let index = hyps.iter().position(|hyp| hyp == prop)?;
+        Some(names[index].clone())
+    };
+    Some(match rule {
+        Rule::TrueRight => return None,
+        Rule::FalseLeft => StrategyArg::FalseIsHyp,
+        Rule::Axiom(prop) => StrategyArg::Exact(find(prop)?),
+        Rule::AndLeft(hyp) => StrategyArg::HypSplit(find(hyp)?),
+        Rule::OrLeft(hyp) => StrategyArg::Destruct(find(hyp)?),
+        Rule::AtomImpliesLeft(atom, hyp) => StrategyArg::ApplyIn(find(atom)?, find(hyp)?, true),
+        Rule::ImpliesLeft(hyp) => StrategyArg::ImplyLeft(find(hyp)?),
+        Rule::AndRight => StrategyArg::Split,
+        Rule::ImpliesRight => StrategyArg::Intro(None),
+        Rule::OrRight(left) => StrategyArg::OrSplit(*left),
+    })
+}
+
+// decides every goal of the proof, the steps returned being replayable
+// with `backtrack::replay` like the ones found by `backtrack::auto`
+pub fn prove(proof: &Proof) -> Result<Vec<(usize, usize, StrategyArg)>, NotProvable> {
+    let mut state = proof.clone();
+    state.clean();
+    let mut pending = vec![];
+    for (goal, hyps) in state.goals.iter().rev() {
+        let sequent = (
+            unfolded(goal),
+            normalize(hyps.iter().map(unfolded).collect()),
+        );
+        // much cheaper than exhausting the search when it fails
+        let tree = if sat::classically_valid(hyps, goal) {
+            search(&sequent.1, &sequent.0)
+        } else {
+            None
+        };
+        match tree {
+            Some(tree) => pending.push((sequent, tree)),
+            None => {
+                return Err(NotProvable {
+                    hyps: hyps.clone(),
+                    goal: goal.clone(),
+                });
+            }
+        }
+    }
+
+    // the goals are unfolded first, to be the sequents that were searched
+    let mut steps = vec![];
+    while let Some((_, index, strat)) =
+        state
+            .get_applicable_strategies()
+            .into_iter()
+            .find(|(_, _, strat)| {
+                matches!(strat, StrategyArg::UnfoldNot(_) | StrategyArg::UnfoldIff(_))
+            })
+    {
+        if state.set_active_goal(index).is_err() || state.execute(&strat).is_err() {
+            unreachable!("unfolding could not be replayed with {}", strat);
+        }
+        steps.push((0, index, strat));
+        state.clean();
+    }
+    while let Some((sequent, tree)) = pending.pop() {
+        // a goal merged with an identical one by cleaning is already proven
+        let Some(index) = state.goals.iter().position(|goal| *goal == sequent) else {
+            continue;
+        };
+        let Some(strat) = strategy(&tree.rule, &state.goals[index].1, &state.hyp_names(index))
+        else {
+            continue;
+        };
+        if state.set_active_goal(index).is_err() || state.execute(&strat).is_err() {
+            unreachable!("G4ip rule could not be replayed with {}", strat);
+        }
+        steps.push((0, index, strat));
+        let mut sequents = vec![state.goals[index].clone()];
+        if tree.premises.len() == 2 {
+            sequents.push(state.goals[state.goals.len() - 1].clone());
+        }
+        for ((goal, hyps), premise) in sequents.into_iter().zip(tree.premises).rev() {
+            pending.push(((goal, normalize(hyps)), premise));
+        }
+        state.clean();
+    }
+    Ok(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::libpoulet::backtrack;
+    use crate::libpoulet::kernel;
+
+    use super::*;
+
+    fn decide(statement: &str) -> Result<Proof, NotProvable> {
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::parse_infix(statement).unwrap());
+        let steps = prove(&proof)?;
+        let solved = backtrack::replay(&proof, &steps).unwrap();
+        assert!(solved.goals.is_empty());
+        for (theorem, term) in solved.finished_terms().unwrap() {
+            assert_eq!(kernel::check_theorem(&theorem, &term), Ok(()));
+        }
+        Ok(solved)
+    }
+
+    #[test]
+    fn theorems() {
+        for statement in [
+            "a => a",
+            "((a => b) => a) => (a => b) => b",
+            "a | b => b | a",
+            "a ^ b => b ^ a",
+            "(a => b) => (b => c) => a => c",
+            "(a | b => c) => (a => c) ^ (b => c)",
+            "(a ^ b => c) => a => b => c",
+            "!!(a | !a)",
+            "!!!a => !a",
+            "(a => b) => !b => !a",
+            "!(a | b) <=> !a ^ !b",
+            "((a => b) => c) => b => c",
+            "(((a => b) => b) => b) => a => b",
+            "(a | (b ^ c)) => (a | b) ^ (a | c)",
+            "(T => a) => a",
+            "!(a <=> !a)",
+            "(a <=> b) => (b <=> c) => (a <=> c)",
+            "F => a",
+            "T",
+        ] {
+            assert!(decide(statement).is_ok(), "{}", statement);
+        }
+    }
+
+    #[test]
+    fn non_theorems() {
+        for statement in [
+            "a",
+            "a | !a",
+            "!!a => a",
+            "((a => b) => a) => a",
+            "(a => b) | (b => a)",
+            "(!a => !b) => b => a",
+            "a | b => a",
+            "!(a ^ b) => !a | !b",
+            "(!a <=> !b) => (a <=> b)",
+            "F",
+        ] {
+            assert_eq!(
+                decide(statement),
+                Err(NotProvable {
+                    hyps: vec![],
+                    goal: PropRef::new(Prop::parse_infix(statement).unwrap()),
+                }),
+                "{}",
+                statement
+            );
+        }
+    }
+
+    #[test]
+    fn hypotheses_and_goals() {
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::parse_infix("c").unwrap());
+        proof.add_hyp_from_prop(Prop::parse_infix("a ^ (a => b)").unwrap());
+        proof.add_hyp_from_prop(Prop::parse_infix("b => c").unwrap());
+        proof.add_goal_from_prop(Prop::parse_infix("a | b => b | a").unwrap());
+        let steps = prove(&proof).unwrap();
+        assert!(backtrack::replay(&proof, &steps).unwrap().goals.is_empty());
+
+        proof.add_goal_from_prop(Prop::parse_infix("a => b").unwrap());
+        let err = prove(&proof).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "not a theorem of intuitionistic logic:  |- ( a => b )"
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use std::cell::{Cell, RefCell};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::rc::{Rc, Weak};
+
+use crate::libpoulet::logic::Prop;
+
+// Hash-consing: structurally equal propositions built through `PropRef::new`
+// share one node, so equality and hashing only look at the node id. Since the
+// children of a `Prop` are themselves `PropRef`s, hashing or comparing a node
+// to intern it never goes deeper than one level.
+
+struct Node {
+    prop: Prop,
+    id: usize,
+}
+
+thread_local! {
+    // weak so that the table does not keep propositions alive, a node takes
+    // its entry out when it is dropped
+    static TABLE: RefCell<HashMap<Prop, Weak<Node>>> = RefCell::new(HashMap::new());
+    static NEXT_ID: Cell<usize> = const { Cell::new(0) };
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        // the removed key is dropped once the table is released, as it may
+        // hold the last reference to other nodes
+        let _removed = TABLE.try_with(|table| {
+            let mut table = table.borrow_mut();
+            match table.get(&self.prop) {
+                Some(weak) if weak.strong_count() == 0 => table.remove_entry(&self.prop),
+                _ => None,
+            }
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct PropRef(Rc<Node>);
+
+impl PropRef {
+    pub fn new(prop: Prop) -> PropRef {
+        TABLE.with(|table| {
+            let existing = table.borrow().get(&prop).and_then(Weak::upgrade);
+            if let Some(node) = existing {
+                return PropRef(node);
+            }
+            let id = NEXT_ID.with(|next| next.replace(next.get() + 1));
+            let node = Rc::new(Node {
+                prop: prop.clone(),
+                id,
+            });
+            table.borrow_mut().insert(prop, Rc::downgrade(&node));
+            PropRef(node)
+        })
+    }
+
+    pub fn id(&self) -> usize {
+        self.0.id
+    }
+}
+
+impl Deref for PropRef {
+    type Target = Prop;
+
+    fn deref(&self) -> &Prop {
+        &self.0.prop
+    }
+}
+
+impl AsRef<Prop> for PropRef {
+    fn as_ref(&self) -> &Prop {
+        &self.0.prop
+    }
+}
+
+impl From<Prop> for PropRef {
+    fn from(prop: Prop) -> PropRef {
+        PropRef::new(prop)
+    }
+}
+
+impl PartialEq for PropRef {
+    fn eq(&self, other: &PropRef) -> bool {
+        self.id() == other.id()
+    }
+}
+
+impl Eq for PropRef {}
+
+impl Hash for PropRef {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id().hash(state);
+    }
+}
+
+// still the structural order, so that sorted hypotheses do not depend on the
+// order the propositions were first built in, but shared subterms are skipped
+impl Ord for PropRef {
+    fn cmp(&self, other: &PropRef) -> Ordering {
+        if self == other {
+            Ordering::Equal
+        } else {
+            self.0.prop.cmp(&other.0.prop)
+        }
+    }
+}
+
+impl PartialOrd for PropRef {
+    fn partial_cmp(&self, other: &PropRef) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Debug for PropRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.prop.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Prop {
+        Prop::parse_infix(s).unwrap()
+    }
+
+    #[test]
+    fn sharing() {
+        let a = PropRef::new(parse("(a => b) ^ (a => b)"));
+        let b = PropRef::new(parse("(a => b) ^ (a => b)"));
+        assert_eq!(a.id(), b.id());
+        assert_ne!(a.id(), PropRef::new(parse("(a => b) | (a => b)")).id());
+        let Prop::And(left, right) = &*a else {
+            panic!("not a conjunction");
+        };
+        assert_eq!(left.id(), right.id());
+    }
+
+    #[test]
+    fn structural_order() {
+        let mut props: Vec<PropRef> = ["c", "a ^ b", "a", "b => a", "a"]
+            .into_iter()
+            .map(|s| PropRef::new(parse(s)))
+            .collect();
+        props.sort();
+        props.dedup();
+        let expected: Vec<Prop> = ["a", "c", "b => a", "a ^ b"]
+            .into_iter()
+            .map(parse)
+            .collect();
+        assert_eq!(
+            props.iter().map(|p| p.as_ref().clone()).collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    fn released() {
+        let id = PropRef::new(parse("released_x => released_y")).id();
+        // nothing holds the first node anymore, so a fresh one is built
+        assert_ne!(PropRef::new(parse("released_x => released_y")).id(), id);
+        TABLE.with(|table| {
+            assert!(
+                table
+                    .borrow()
+                    .keys()
+                    .all(|prop| !prop.to_string().contains("released"))
+            );
+        });
+    }
+}
@@ -0,0 +1,433 @@
+use std::{error::Error, fmt};
+
+use crate::libpoulet::fol::FoTerm;
+use crate::libpoulet::intern::PropRef;
+use crate::libpoulet::logic::{Logic, Prop};
+use crate::libpoulet::terms::{Term, Theorem};
+
+// Type checker for proof terms. It only knows about `Prop` and `Term`, so
+// whatever the strategies or the search do, a theorem is accepted only if
+// its term has the right type here.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KernelError {
+    UnboundVariable(usize),
+    OpenHole(usize),
+    TypeMismatch { expected: Prop, found: Prop },
+    ShapeMismatch { expected: &'static str, found: Prop },
+    // an individual variable meant to be arbitrary that is not
+    NotFresh(String),
+    // the term needs a stronger logic than the theorem is stated in
+    WrongLogic { needed: Logic, allowed: Logic },
+}
+
+impl fmt::Display for KernelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KernelError::UnboundVariable(x) => write!(f, "variable x{} is not bound", x),
+            KernelError::OpenHole(h) => write!(f, "proof is unfinished, hole ?{} remains", h),
+            KernelError::TypeMismatch { expected, found } => write!(
+                f,
+                "expected a proof of {}, found a proof of {}",
+                expected.to_string(),
+                found.to_string()
+            ),
+            KernelError::ShapeMismatch { expected, found } => write!(
+                f,
+                "expected a proof of {}, found a proof of {}",
+                expected,
+                found.to_string()
+            ),
+            KernelError::NotFresh(x) => write!(f, "variable {} is not fresh", x),
+            KernelError::WrongLogic { needed, allowed } => write!(
+                f,
+                "the proof needs {} logic, the theorem is stated in {} logic",
+                needed, allowed
+            ),
+        }
+    }
+}
+
+impl Error for KernelError {}
+
+// negations and biconditionals are only notations, two propositions are
+// the same when they are once unfolded, up to the names of bound variables
+fn expect_eq(expected: &Prop, found: &Prop) -> Result<(), KernelError> {
+    if expected == found || expected.unfold_all().alpha_eq(&found.unfold_all()) {
+        Ok(())
+    } else {
+        Err(KernelError::TypeMismatch {
+            expected: expected.clone(),
+            found: found.clone(),
+        })
+    }
+}
+
+fn shape_mismatch(expected: &'static str, found: &Prop) -> KernelError {
+    KernelError::ShapeMismatch {
+        expected,
+        found: found.clone(),
+    }
+}
+
+// `x` is arbitrary for `term` when no hypothesis the term uses says
+// anything about it
+fn expect_fresh(
+    ctx: &[(usize, PropRef)],
+    term: &Term,
+    x: &str,
+    others: &[&Prop],
+) -> Result<(), KernelError> {
+    let free_in = |prop: &Prop| prop.free_vars().contains(x);
+    if others.iter().any(|prop| free_in(prop))
+        || ctx
+            .iter()
+            .any(|(y, prop)| term.mentions(*y) && free_in(prop))
+    {
+        Err(KernelError::NotFresh(String::from(x)))
+    } else {
+        Ok(())
+    }
+}
+
+// context is a stack, the innermost binding of a variable is the last one
+fn infer_in(ctx: &mut Vec<(usize, PropRef)>, term: &Term) -> Result<Prop, KernelError> {
+    match term {
+        Term::Var(x) => ctx
+            .iter()
+            .rev()
+            .find(|(y, _)| y == x)
+            .map(|(_, prop)| prop.as_ref().clone())
+            .ok_or(KernelError::UnboundVariable(*x)),
+        Term::Hole(h) => Err(KernelError::OpenHole(*h)),
+        Term::Unit => Ok(Prop::True),
+        Term::Lambda(x, a, t) => {
+            ctx.push((*x, a.clone()));
+            let b = infer_in(ctx, t);
+            ctx.pop();
+            Ok(Prop::Implies(a.clone(), PropRef::new(b?)))
+        }
+        Term::App(t, u) => match infer_in(ctx, t)?.unfold_head() {
+            Prop::Implies(a, b) => {
+                expect_eq(&a, &infer_in(ctx, u)?)?;
+                Ok(b.as_ref().clone())
+            }
+            other => Err(shape_mismatch("an implication", &other)),
+        },
+        Term::Pair(t, u) => Ok(Prop::and(infer_in(ctx, t)?, infer_in(ctx, u)?)),
+        Term::Fst(t) | Term::Snd(t) => match infer_in(ctx, t)?.unfold_head() {
+            Prop::And(a, b) => {
+                if let Term::Fst(_) = term {
+                    Ok(a.as_ref().clone())
+                } else {
+                    Ok(b.as_ref().clone())
+                }
+            }
+            other => Err(shape_mismatch("a conjunction", &other)),
+        },
+        Term::Inl(t, b) => Ok(Prop::Or(PropRef::new(infer_in(ctx, t)?), b.clone())),
+        Term::Inr(a, t) => Ok(Prop::Or(a.clone(), PropRef::new(infer_in(ctx, t)?))),
+        Term::Case(t, x, u, y, v) => match infer_in(ctx, t)? {
+            Prop::Or(a, b) => {
+                ctx.push((*x, a));
+                let left = infer_in(ctx, u);
+                ctx.pop();
+                ctx.push((*y, b));
+                let right = infer_in(ctx, v);
+                ctx.pop();
+                let left = left?;
+                expect_eq(&left, &right?)?;
+                Ok(left)
+            }
+            other => Err(shape_mismatch("a disjunction", &other)),
+        },
+        Term::Let(x, u, t) => {
+            let a = infer_in(ctx, u)?;
+            ctx.push((*x, PropRef::new(a)));
+            let b = infer_in(ctx, t);
+            ctx.pop();
+            b
+        }
+        Term::Absurd(t, a) => {
+            expect_eq(&Prop::False, &infer_in(ctx, t)?)?;
+            Ok(a.as_ref().clone())
+        }
+        Term::ForallIntro(x, t) => {
+            expect_fresh(ctx, t, x, &[])?;
+            Ok(Prop::forall(x.clone(), infer_in(ctx, t)?))
+        }
+        Term::Inst(t, u) => match infer_in(ctx, t)? {
+            Prop::Forall(x, a) => Ok(a.subst(&x, u)),
+            other => Err(shape_mismatch("a universal quantification", &other)),
+        },
+        Term::Witness(u, t, a) => match a.as_ref() {
+            Prop::Exists(x, body) => {
+                expect_eq(&body.subst(x, u), &infer_in(ctx, t)?)?;
+                Ok(a.as_ref().clone())
+            }
+            other => Err(shape_mismatch("an existential quantification", other)),
+        },
+        Term::Unpack(t, x, h, u) => match infer_in(ctx, t)? {
+            Prop::Exists(y, a) => {
+                let exists = Prop::Exists(y.clone(), a.clone());
+                ctx.push((*h, PropRef::new(a.subst(&y, &FoTerm::Var(x.clone())))));
+                let b = infer_in(ctx, u);
+                ctx.pop();
+                let b = b?;
+                expect_fresh(ctx, u, x, &[&exists, &b])?;
+                Ok(b)
+            }
+            other => Err(shape_mismatch("an existential quantification", &other)),
+        },
+        Term::ExcludedMiddle(a) => Ok(Prop::Or(a.clone(), PropRef::new(Prop::Not(a.clone())))),
+    }
+}
+
+// the weakest logic the term is a proof in: ex falso is what minimal logic
+// lacks, the excluded middle what intuitionistic logic lacks
+pub fn required_logic(term: &Term) -> Logic {
+    match term {
+        Term::ExcludedMiddle(_) => Logic::Classical,
+        Term::Var(_) | Term::Unit | Term::Hole(_) => Logic::Minimal,
+        Term::Absurd(t, _) => required_logic(t).max(Logic::Intuitionistic),
+        Term::Lambda(_, _, t)
+        | Term::Fst(t)
+        | Term::Snd(t)
+        | Term::Inl(t, _)
+        | Term::Inr(_, t)
+        | Term::ForallIntro(_, t)
+        | Term::Inst(t, _)
+        | Term::Witness(_, t, _) => required_logic(t),
+        Term::App(t, u) | Term::Pair(t, u) | Term::Let(_, t, u) | Term::Unpack(t, _, _, u) => {
+            required_logic(t).max(required_logic(u))
+        }
+        Term::Case(t, _, u, _, v) => required_logic(t)
+            .max(required_logic(u))
+            .max(required_logic(v)),
+    }
+}
+
+pub fn infer(hyps: &[(usize, PropRef)], term: &Term) -> Result<Prop, KernelError> {
+    infer_in(&mut hyps.to_vec(), term)
+}
+
+pub fn check(hyps: &[(usize, PropRef)], term: &Term, prop: &Prop) -> Result<(), KernelError> {
+    expect_eq(prop, &infer(hyps, term)?)
+}
+
+pub fn check_theorem(theorem: &Theorem, term: &Term) -> Result<(), KernelError> {
+    check(&theorem.hyps, term, &theorem.goal)?;
+    let needed = required_logic(term);
+    if needed > theorem.logic {
+        return Err(KernelError::WrongLogic {
+            needed,
+            allowed: theorem.logic,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    fn var(x: usize) -> Rc<Term> {
+        Rc::new(Term::Var(x))
+    }
+
+    #[test]
+    fn accepts() {
+        let swap = Term::Lambda(
+            0,
+            PropRef::new(Prop::parse_infix("a ^ b").unwrap()),
+            Rc::new(Term::Pair(
+                Rc::new(Term::Snd(var(0))),
+                Rc::new(Term::Fst(var(0))),
+            )),
+        );
+        assert_eq!(
+            check(&[], &swap, &Prop::parse_infix("a ^ b => b ^ a").unwrap()),
+            Ok(())
+        );
+
+        let commute = Term::Lambda(
+            0,
+            PropRef::new(Prop::parse_infix("a | b").unwrap()),
+            Rc::new(Term::Case(
+                var(0),
+                1,
+                Rc::new(Term::Inr(
+                    PropRef::new(Prop::from_name(String::from("b"))),
+                    var(1),
+                )),
+                2,
+                Rc::new(Term::Inl(
+                    var(2),
+                    PropRef::new(Prop::from_name(String::from("a"))),
+                )),
+            )),
+        );
+        assert_eq!(
+            check(&[], &commute, &Prop::parse_infix("a | b => b | a").unwrap()),
+            Ok(())
+        );
+
+        let hyps = vec![
+            (0, PropRef::new(Prop::parse_infix("a => b").unwrap())),
+            (1, PropRef::new(Prop::from_name(String::from("a")))),
+            (2, PropRef::new(Prop::False)),
+        ];
+        let modus_ponens = Term::Let(3, Rc::new(Term::App(var(0), var(1))), var(3));
+        assert_eq!(
+            check(&hyps, &modus_ponens, &Prop::from_name(String::from("b"))),
+            Ok(())
+        );
+        let absurd = Term::Absurd(var(2), PropRef::new(Prop::from_name(String::from("c"))));
+        assert_eq!(
+            infer(&hyps, &absurd),
+            Ok(Prop::from_name(String::from("c")))
+        );
+        assert_eq!(infer(&hyps, &Term::Unit), Ok(Prop::True));
+    }
+
+    #[test]
+    fn notations() {
+        let prop = |s: &str| PropRef::new(Prop::parse_infix(s).unwrap());
+        // λx: !a. λy: a. x y
+        let contradiction = Term::Lambda(
+            0,
+            prop("!a"),
+            Rc::new(Term::Lambda(
+                1,
+                prop("a"),
+                Rc::new(Term::App(var(0), var(1))),
+            )),
+        );
+        assert_eq!(check(&[], &contradiction, &prop("!a => !a")), Ok(()));
+        assert_eq!(check(&[], &contradiction, &prop("!a => a => F")), Ok(()));
+        let forward = Term::Lambda(0, prop("a <=> b"), Rc::new(Term::Fst(var(0))));
+        assert_eq!(check(&[], &forward, &prop("(a <=> b) => a => b")), Ok(()));
+        assert_eq!(
+            check(&[], &forward, &prop("(a <=> b) => b => a")),
+            Err(KernelError::TypeMismatch {
+                expected: prop("(a <=> b) => b => a").as_ref().clone(),
+                found: Prop::parse_infix("(a <=> b) => (a => b)").unwrap(),
+            })
+        );
+    }
+
+    #[test]
+    fn quantifiers() {
+        let prop = |s: &str| PropRef::new(Prop::parse_infix(s).unwrap());
+        let x = || FoTerm::Var(String::from("x"));
+        let hyps = vec![(0, prop("forall y. P(y)")), (1, prop("P(x)"))];
+        // bound variables may be renamed
+        let generalize = Term::ForallIntro(String::from("x"), Rc::new(Term::Inst(var(0), x())));
+        assert_eq!(check(&hyps, &generalize, &prop("forall z. P(z)")), Ok(()));
+        assert_eq!(
+            check(
+                &hyps,
+                &Term::ForallIntro(String::from("x"), var(1)),
+                &prop("forall x. P(x)")
+            ),
+            Err(KernelError::NotFresh(String::from("x")))
+        );
+        assert_eq!(
+            infer(&hyps, &Term::Inst(var(1), x())),
+            Err(KernelError::ShapeMismatch {
+                expected: "a universal quantification",
+                found: Prop::parse_infix("P(x)").unwrap(),
+            })
+        );
+
+        let witness = Term::Witness(x(), var(1), prop("exists y. P(y)"));
+        assert_eq!(
+            infer(&hyps, &witness),
+            Ok(Prop::parse_infix("exists y. P(y)").unwrap())
+        );
+        let wrong = Term::Witness(
+            FoTerm::Var(String::from("z")),
+            var(1),
+            prop("exists y. P(y)"),
+        );
+        assert!(infer(&hyps, &wrong).is_err());
+
+        let hyps = vec![(0, prop("exists y. P(y) ^ Q(y)"))];
+        let unpack = |body: Term| Term::Unpack(var(0), String::from("x"), 1, Rc::new(body));
+        let keep = Term::Witness(x(), Rc::new(Term::Snd(var(1))), prop("exists y. Q(y)"));
+        assert_eq!(check(&hyps, &unpack(keep), &prop("exists z. Q(z)")), Ok(()));
+        // the witness cannot escape its scope
+        assert_eq!(
+            infer(&hyps, &unpack(Term::Snd(var(1)))),
+            Err(KernelError::NotFresh(String::from("x")))
+        );
+    }
+
+    #[test]
+    fn logics() {
+        let a = PropRef::new(Prop::from_name(String::from("a")));
+        let em = Term::ExcludedMiddle(a.clone());
+        assert_eq!(infer(&[], &em), Ok(Prop::parse_infix("a | !a").unwrap()));
+        let ex_falso = Term::Lambda(
+            0,
+            PropRef::new(Prop::False),
+            Rc::new(Term::Absurd(var(0), a.clone())),
+        );
+        assert_eq!(required_logic(&Term::Lambda(0, a, var(0))), Logic::Minimal);
+        assert_eq!(required_logic(&ex_falso), Logic::Intuitionistic);
+        assert_eq!(
+            required_logic(&Term::Pair(Rc::new(ex_falso), Rc::new(em))),
+            Logic::Classical
+        );
+    }
+
+    #[test]
+    fn rejects() {
+        // what the old one-sided or-split on hypotheses amounted to
+        let unsound = Term::Lambda(0, PropRef::new(Prop::parse_infix("a | b").unwrap()), var(0));
+        assert_eq!(
+            check(&[], &unsound, &Prop::parse_infix("a | b => a").unwrap()),
+            Err(KernelError::TypeMismatch {
+                expected: Prop::parse_infix("a | b => a").unwrap(),
+                found: Prop::parse_infix("a | b => a | b").unwrap(),
+            })
+        );
+
+        assert_eq!(
+            infer(&[], &Term::Var(4)),
+            Err(KernelError::UnboundVariable(4))
+        );
+        assert_eq!(
+            infer(
+                &[],
+                &Term::Pair(Rc::new(Term::Unit), Rc::new(Term::Hole(2)))
+            ),
+            Err(KernelError::OpenHole(2))
+        );
+        assert_eq!(
+            infer(&[], &Term::Fst(Rc::new(Term::Unit))),
+            Err(KernelError::ShapeMismatch {
+                expected: "a conjunction",
+                found: Prop::True,
+            })
+        );
+        assert_eq!(
+            infer(
+                &[],
+                &Term::Absurd(Rc::new(Term::Unit), PropRef::new(Prop::True))
+            ),
+            Err(KernelError::TypeMismatch {
+                expected: Prop::False,
+                found: Prop::True,
+            })
+        );
+
+        // a variable bound in one branch is out of scope afterwards
+        let escaped = Term::Pair(
+            Rc::new(Term::Lambda(0, PropRef::new(Prop::True), var(0))),
+            var(0),
+        );
+        assert_eq!(infer(&[], &escaped), Err(KernelError::UnboundVariable(0)));
+    }
+}
@@ -0,0 +1,354 @@
+use std::{collections::BTreeSet, error::Error, fmt};
+
+use crate::libpoulet::g4ip;
+use crate::libpoulet::intern::PropRef;
+use crate::libpoulet::logic::Prop;
+
+// Finite Kripke models for intuitionistic logic: worlds ordered by a
+// preorder, atoms staying true once they are true. World 0 is the root.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KripkeModel {
+    // atoms true at each world
+    pub valuation: Vec<BTreeSet<String>>,
+    // reaches[w][v] when v is accessible from w
+    pub reaches: Vec<Vec<bool>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ModelError {
+    NoWorlds,
+    // reaches is not a worlds x worlds matrix
+    ReachesSize(usize),
+    NotReflexive(usize),
+    NotTransitive(usize, usize, usize),
+    NotMonotone {
+        atom: String,
+        from: usize,
+        to: usize,
+    },
+    HypNotForced(usize),
+    GoalForced,
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModelError::NoWorlds => write!(f, "the model has no worlds"),
+            ModelError::ReachesSize(worlds) => write!(
+                f,
+                "the accessibility relation is not a {} x {} matrix",
+                worlds, worlds
+            ),
+            ModelError::NotReflexive(w) => write!(f, "w{} does not see itself", w),
+            ModelError::NotTransitive(u, v, w) => write!(
+                f,
+                "w{} sees w{} which sees w{}, but w{} does not see w{}",
+                u, v, w, u, w
+            ),
+            ModelError::NotMonotone { atom, from, to } => write!(
+                f,
+                "{} is true at w{} but not at w{} which it sees",
+                atom, from, to
+            ),
+            ModelError::HypNotForced(index) => {
+                write!(f, "hypothesis {} is not forced at the root", index)
+            }
+            ModelError::GoalForced => write!(f, "the goal is forced at the root"),
+        }
+    }
+}
+
+impl Error for ModelError {}
+
+impl KripkeModel {
+    pub fn forces(&self, world: usize, prop: &Prop) -> bool {
+        match prop {
+            Prop::True => true,
+            Prop::False => false,
+            Prop::Name(_) | Prop::Pred(_, _) | Prop::Forall(_, _) | Prop::Exists(_, _) => {
+                self.valuation[world].contains(&prop.atom_name().unwrap())
+            }
+            Prop::And(a, b) => self.forces(world, a) && self.forces(world, b),
+            Prop::Or(a, b) => self.forces(world, a) || self.forces(world, b),
+            Prop::Implies(a, b) => (0..self.valuation.len())
+                .filter(|v| self.reaches[world][*v])
+                .all(|v| !self.forces(v, a) || self.forces(v, b)),
+            Prop::Not(_) | Prop::Iff(_, _) => self.forces(world, &prop.unfold_head()),
+        }
+    }
+
+    // checks that this is a Kripke model whose root forces every hypothesis
+    // but not the goal, in which case the goal does not follow from them
+    pub fn check_countermodel(&self, hyps: &[PropRef], goal: &Prop) -> Result<(), ModelError> {
+        let worlds = self.valuation.len();
+        if worlds == 0 {
+            return Err(ModelError::NoWorlds);
+        }
+        if self.reaches.len() != worlds || self.reaches.iter().any(|row| row.len() != worlds) {
+            return Err(ModelError::ReachesSize(worlds));
+        }
+        for u in 0..worlds {
+            if !self.reaches[u][u] {
+                return Err(ModelError::NotReflexive(u));
+            }
+            for v in (0..worlds).filter(|v| self.reaches[u][*v]) {
+                if let Some(atom) = self.valuation[u].difference(&self.valuation[v]).next() {
+                    return Err(ModelError::NotMonotone {
+                        atom: atom.clone(),
+                        from: u,
+                        to: v,
+                    });
+                }
+                if let Some(w) = (0..worlds).find(|w| self.reaches[v][*w] && !self.reaches[u][*w]) {
+                    return Err(ModelError::NotTransitive(u, v, w));
+                }
+            }
+        }
+        if let Some(index) = hyps.iter().position(|hyp| !self.forces(0, hyp)) {
+            return Err(ModelError::HypNotForced(index));
+        }
+        if self.forces(0, goal) {
+            return Err(ModelError::GoalForced);
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for KripkeModel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (w, atoms) in self.valuation.iter().enumerate() {
+            let atoms: Vec<&str> = atoms.iter().map(|atom| atom.as_str()).collect();
+            let seen: Vec<String> = (0..self.valuation.len())
+                .filter(|v| *v != w && self.reaches[w][*v])
+                .map(|v| format!("w{}", v))
+                .collect();
+            write!(f, "w{} | true: ", w)?;
+            if atoms.is_empty() {
+                write!(f, "nothing")?;
+            } else {
+                write!(f, "{}", atoms.join(", "))?;
+            }
+            if !seen.is_empty() {
+                write!(f, " | sees: {}", seen.join(", "))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn subformulas(prop: &PropRef, acc: &mut Vec<PropRef>) {
+    if acc.contains(prop) {
+        return;
+    }
+    acc.push(prop.clone());
+    if let Prop::Implies(a, b) | Prop::And(a, b) | Prop::Or(a, b) = prop.as_ref() {
+        subformulas(a, acc);
+        subformulas(b, acc);
+    }
+}
+
+// every subformula that follows from `base`
+fn closure(base: &[PropRef], subs: &[PropRef]) -> Vec<PropRef> {
+    let mut world: Vec<PropRef> = subs
+        .iter()
+        .filter(|prop| g4ip::provable(base, prop))
+        .cloned()
+        .collect();
+    world.sort();
+    world
+}
+
+// a disjunction of the world neither side of which is in it
+fn undecided(world: &[PropRef]) -> Option<(PropRef, PropRef)> {
+    world.iter().find_map(|prop| match prop.as_ref() {
+        Prop::Or(a, b) if !world.contains(a) && !world.contains(b) => Some((a.clone(), b.clone())),
+        _ => None,
+    })
+}
+
+// extends `base`, which does not prove `avoid`, into a closed set that
+// still does not, and contains a side of each of its disjunctions
+fn saturate(base: &[PropRef], avoid: &PropRef, subs: &[PropRef]) -> Vec<PropRef> {
+    let mut world = closure(base, subs);
+    while let Some((a, b)) = undecided(&world) {
+        // if both sides proved `avoid`, so would the world itself
+        let mut extended = world.clone();
+        extended.push(a);
+        if g4ip::provable(&extended, avoid) {
+            extended.pop();
+            extended.push(b);
+        }
+        world = closure(&extended, subs);
+    }
+    world
+}
+
+// The canonical model over the subformulas of the sequent: its worlds are
+// saturated sets of subformulas ordered by inclusion, and a world forces a
+// subformula exactly when it contains it. None if the goal is provable.
+pub fn countermodel(hyps: &[PropRef], goal: &PropRef) -> Option<KripkeModel> {
+    if g4ip::provable(hyps, goal) {
+        return None;
+    }
+    // forcing agrees on a proposition and its unfolding
+    let hyps: Vec<PropRef> = hyps
+        .iter()
+        .map(|hyp| PropRef::new(hyp.unfold_all()))
+        .collect();
+    let goal = &PropRef::new(goal.unfold_all());
+    let mut subs = vec![];
+    for prop in hyps.iter().chain([goal]) {
+        subformulas(prop, &mut subs);
+    }
+
+    let mut worlds = vec![saturate(&hyps, goal, &subs)];
+    let mut index = 0;
+    while index < worlds.len() {
+        // every implication a => b missing from a world needs a world above
+        // it where a holds and b does not
+        for prop in subs.iter() {
+            if let Prop::Implies(a, b) = prop.as_ref()
+                && !worlds[index].contains(prop)
+            {
+                let mut base = worlds[index].clone();
+                base.push(a.clone());
+                let world = saturate(&base, b, &subs);
+                if !worlds.contains(&world) {
+                    worlds.push(world);
+                }
+            }
+        }
+        index += 1;
+    }
+
+    let valuation = worlds
+        .iter()
+        .map(|world| world.iter().filter_map(|prop| prop.atom_name()).collect())
+        .collect();
+    let reaches = worlds
+        .iter()
+        .map(|u| {
+            worlds
+                .iter()
+                .map(|v| u.iter().all(|prop| v.contains(prop)))
+                .collect()
+        })
+        .collect();
+    Some(KripkeModel { valuation, reaches })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn refute(hyps: &[&str], goal: &str) -> Option<KripkeModel> {
+        let hyps: Vec<PropRef> = hyps
+            .iter()
+            .map(|hyp| PropRef::new(Prop::parse_infix(hyp).unwrap()))
+            .collect();
+        let goal = PropRef::new(Prop::parse_infix(goal).unwrap());
+        let model = countermodel(&hyps, &goal)?;
+        assert_eq!(model.check_countermodel(&hyps, &goal), Ok(()));
+        Some(model)
+    }
+
+    #[test]
+    fn excluded_middle() {
+        let model = refute(&[], "a | !a").unwrap();
+        assert_eq!(
+            model,
+            KripkeModel {
+                valuation: vec![BTreeSet::new(), BTreeSet::from([String::from("a")])],
+                reaches: vec![vec![true, true], vec![false, true]],
+            }
+        );
+        assert_eq!(
+            model.to_string(),
+            "w0 | true: nothing | sees: w1\nw1 | true: a\n"
+        );
+    }
+
+    #[test]
+    fn non_theorems() {
+        for statement in [
+            "a",
+            "F",
+            "!!a => a",
+            "((a => b) => a) => a",
+            "(a => b) | (b => a)",
+            "!(a ^ b) => !a | !b",
+            "(!a => b | c) => (!a => b) | (!a => c)",
+            "((a <=> b) => a) => a",
+        ] {
+            assert!(refute(&[], statement).is_some(), "{}", statement);
+        }
+        assert!(refute(&["a | b", "a => c"], "c").is_some());
+    }
+
+    #[test]
+    fn theorems() {
+        assert_eq!(refute(&[], "((a => b) => a) => (a => b) => b"), None);
+        assert_eq!(refute(&["a | b", "a => c", "b => c"], "c"), None);
+    }
+
+    #[test]
+    fn checker() {
+        let a = || BTreeSet::from([String::from("a")]);
+        let goal = Prop::parse_infix("a | !a").unwrap();
+        let model = |valuation, reaches| KripkeModel { valuation, reaches };
+
+        let classical = model(vec![a()], vec![vec![true]]);
+        assert_eq!(
+            classical.check_countermodel(&[], &goal),
+            Err(ModelError::GoalForced)
+        );
+        let forgetful = model(
+            vec![a(), BTreeSet::new()],
+            vec![vec![true, true], vec![false, true]],
+        );
+        assert_eq!(
+            forgetful.check_countermodel(&[], &goal),
+            Err(ModelError::NotMonotone {
+                atom: String::from("a"),
+                from: 0,
+                to: 1,
+            })
+        );
+        let chain = model(
+            vec![BTreeSet::new(); 3],
+            vec![
+                vec![true, true, false],
+                vec![false, true, true],
+                vec![false, false, true],
+            ],
+        );
+        assert_eq!(
+            chain.check_countermodel(&[], &goal),
+            Err(ModelError::NotTransitive(0, 1, 2))
+        );
+        let empty = model(vec![BTreeSet::new()], vec![vec![false]]);
+        assert_eq!(
+            empty.check_countermodel(&[], &goal),
+            Err(ModelError::NotReflexive(0))
+        );
+        let two = model(
+            vec![BTreeSet::new(), a()],
+            vec![vec![true, true], vec![false, true]],
+        );
+        assert_eq!(
+            two.check_countermodel(&[PropRef::new(Prop::from_name(String::from("a")))], &goal),
+            Err(ModelError::HypNotForced(0))
+        );
+        let short_row = model(vec![BTreeSet::new(); 2], vec![vec![true, true], vec![true]]);
+        assert_eq!(
+            short_row.check_countermodel(&[], &goal),
+            Err(ModelError::ReachesSize(2))
+        );
+        let missing_row = model(vec![BTreeSet::new(); 2], vec![vec![true, true]]);
+        assert_eq!(
+            missing_row.check_countermodel(&[], &goal),
+            Err(ModelError::ReachesSize(2))
+        );
+    }
+}
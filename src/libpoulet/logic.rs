@@ -1,13 +1,51 @@
 use std::cmp::max;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
 
-use std::rc::Rc;
+use crate::libpoulet::fol::{self, FoTerm};
+use crate::libpoulet::intern::PropRef;
+use crate::libpoulet::sat;
 
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+// classical truth values of atoms, missing atoms being false
+pub type Valuation = BTreeMap<String, bool>;
+
+// the logics proofs can be carried out in, each one proving more than the
+// previous: minimal logic has no ex falso, classical logic adds the
+// excluded middle
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Logic {
+    Minimal,
+    #[default]
+    Intuitionistic,
+    Classical,
+}
+
+impl fmt::Display for Logic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Logic::Minimal => write!(f, "minimal"),
+            Logic::Intuitionistic => write!(f, "intuitionistic"),
+            Logic::Classical => write!(f, "classical"),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub enum Prop {
     Name(String),
-    Implies(Rc<Prop>, Rc<Prop>),
-    And(Rc<Prop>, Rc<Prop>),
-    Or(Rc<Prop>, Rc<Prop>),
+    // a predicate applied to at least one term, one without arguments being
+    // a plain name
+    Pred(String, Vec<FoTerm>),
+    Implies(PropRef, PropRef),
+    And(PropRef, PropRef),
+    Or(PropRef, PropRef),
+    // a => F and (a => b) ^ (b => a), kept apart to be printed as written
+    Not(PropRef),
+    Iff(PropRef, PropRef),
+    Forall(String, PropRef),
+    Exists(String, PropRef),
     True,
     False,
 }
@@ -18,119 +56,130 @@ impl Prop {
     }
 
     pub fn imply(a: Prop, b: Prop) -> Prop {
-        Prop::Implies(Rc::new(a), Rc::new(b))
+        Prop::Implies(PropRef::new(a), PropRef::new(b))
     }
 
     pub fn not(prop: Prop) -> Prop {
-        Prop::imply(prop, Prop::False)
+        Prop::Not(PropRef::new(prop))
     }
 
     pub fn and(a: Prop, b: Prop) -> Prop {
-        Prop::And(Rc::new(a), Rc::new(b))
+        Prop::And(PropRef::new(a), PropRef::new(b))
     }
 
     pub fn or(a: Prop, b: Prop) -> Prop {
-        Prop::Or(Rc::new(a), Rc::new(b))
+        Prop::Or(PropRef::new(a), PropRef::new(b))
     }
 
     pub fn equiv(a: Prop, b: Prop) -> Prop {
-        Prop::and(Prop::imply(a.clone(), b.clone()), Prop::imply(b, a))
-    }
-
-    pub fn parse_rpn(s: &str) -> Result<Prop, &str> {
-        let mut acc: Vec<Prop> = vec![];
-        for string in s.split_whitespace() {
-            match string {
-                "=>" => {
-                    let b = acc.pop();
-                    let a = acc.pop();
-                    match (a, b) {
-                        (Some(thing_a), Some(thing_b)) => acc.push(Prop::imply(thing_a, thing_b)),
-                        (None, Some(_)) => {
-                            return Err(
-                                "During parsing of '=>', two items expected in accumulator, found one",
-                            );
-                        }
-                        _ => {
-                            return Err(
-                                "During parsing of '=>', two items expected in accumulator, found zero",
-                            );
-                        }
-                    }
-                }
-                "^" => {
-                    let b = acc.pop();
-                    let a = acc.pop();
-                    match (a, b) {
-                        (Some(thing_a), Some(thing_b)) => acc.push(Prop::and(thing_a, thing_b)),
-                        (None, Some(_)) => {
-                            return Err(
-                                "During parsing of '^', two items expected in accumulator, found one",
-                            );
-                        }
-                        _ => {
-                            return Err(
-                                "During parsing of '^', two items expected in accumulator, found zero",
-                            );
-                        }
-                    }
-                }
-                "|" => {
-                    let b = acc.pop();
-                    let a = acc.pop();
-                    match (a, b) {
-                        (Some(thing_a), Some(thing_b)) => acc.push(Prop::or(thing_a, thing_b)),
-                        (None, Some(_)) => {
-                            return Err(
-                                "During parsing of '|', two items expected in accumulator, found one",
-                            );
-                        }
-                        _ => {
-                            return Err(
-                                "During parsing of '|', two items expected in accumulator, found zero",
-                            );
-                        }
-                    }
+        Prop::Iff(PropRef::new(a), PropRef::new(b))
+    }
+
+    pub fn parse_rpn(s: &str) -> Result<Prop, ParseError> {
+        let tokens = tokenize_whitespace(s);
+        // every accumulated item remembers the byte span it was built from
+        let mut acc: Vec<(Prop, Range<usize>)> = vec![];
+        for (index, &(start, raw)) in tokens.iter().enumerate() {
+            let span = start..start + raw.len();
+            let token = canonical(raw);
+            let arity = match token {
+                "=>" | "^" | "|" | "<=>" | "forall" | "exists" => 2,
+                "!" => 1,
+                "T" | "F" => 0,
+                name if name.chars().all(is_name_char) => 0,
+                name if name.contains('(') && name.starts_with(is_name_char) => 0,
+                _ => {
+                    return Err(ParseError {
+                        kind: ParseErrorKind::UnknownOperator,
+                        token_index: index,
+                        span,
+                        token: String::from(raw),
+                    });
                 }
-                "<=>" => {
-                    let b = acc.pop();
-                    let a = acc.pop();
-                    match (a, b) {
-                        (Some(thing_a), Some(thing_b)) => acc.push(Prop::equiv(thing_a, thing_b)),
-                        (None, Some(_)) => {
-                            return Err(
-                                "During parsing of '<=>', two items expected in accumulator, found one",
-                            );
-                        }
-                        _ => {
-                            return Err(
-                                "During parsing of '<=>', two items expected in accumulator, found zero",
-                            );
-                        }
-                    }
+            };
+            if acc.len() < arity {
+                return Err(ParseError {
+                    kind: ParseErrorKind::MissingOperands {
+                        expected: arity,
+                        found: acc.len(),
+                    },
+                    token_index: index,
+                    span,
+                    token: String::from(raw),
+                });
+            }
+            // the first operand of a quantifier is the variable it binds
+            if let "forall" | "exists" = token {
+                let (variable, variable_span) = &acc[acc.len() - 2];
+                if !matches!(variable, Prop::Name(x) if is_variable(x)) {
+                    return Err(ParseError {
+                        kind: ParseErrorKind::ExpectedVariable,
+                        token_index: index,
+                        span: variable_span.clone(),
+                        token: String::from(&s[variable_span.clone()]),
+                    });
                 }
-                "!" => {
-                    let a = acc.pop();
-                    match a {
-                        None => {
-                            return Err(
-                                "During parsing of '!', one item expected in accumulator, found zero",
-                            );
-                        }
-                        Some(thing) => acc.push(Prop::not(thing)),
+            }
+            let mut operands = acc.split_off(acc.len() - arity).into_iter();
+            let span = operands
+                .as_slice()
+                .first()
+                .map_or(span.start, |(_, sp)| sp.start)..span.end;
+            let mut pop = || operands.next().map(|(prop, _)| prop).unwrap();
+            let prop = match token {
+                "=>" => Prop::imply(pop(), pop()),
+                "^" => Prop::and(pop(), pop()),
+                "|" => Prop::or(pop(), pop()),
+                "<=>" => Prop::equiv(pop(), pop()),
+                "!" => Prop::not(pop()),
+                "forall" | "exists" => {
+                    let Prop::Name(x) = pop() else {
+                        unreachable!("the bound variable was checked above")
+                    };
+                    if token == "forall" {
+                        Prop::forall(x, pop())
+                    } else {
+                        Prop::exists(x, pop())
                     }
                 }
-                "T" => acc.push(Prop::True),
-                "F" => acc.push(Prop::False),
-                name => acc.push(Prop::from_name(String::from(name))),
-            }
+                "T" => Prop::True,
+                "F" => Prop::False,
+                name if name.contains('(') => parse_pred_token(name, start, index)?,
+                name => Prop::from_name(String::from(name)),
+            };
+            acc.push((prop, span));
         }
         if acc.len() > 1 {
-            return Err("At the end of parsing, > 1 item left unused in the accumulator");
+            let span = acc[1].1.clone();
+            let token_index = tokens
+                .iter()
+                .position(|(start, _)| *start == span.start)
+                .unwrap_or(0);
+            return Err(ParseError {
+                kind: ParseErrorKind::LeftoverOperands { found: acc.len() },
+                token_index,
+                token: String::from(&s[span.clone()]),
+                span,
+            });
         }
         match acc.pop() {
-            None => Err("At the end of parsing, accumulator is empty"),
-            Some(thing) => Ok(thing),
+            None => Err(ParseError::at_end(s, tokens.len(), ParseErrorKind::Empty)),
+            Some((thing, _)) => Ok(thing),
+        }
+    }
+
+    pub fn parse_infix(s: &str) -> Result<Prop, ParseError> {
+        let tokens = tokenize_infix(s)?;
+        let mut parser = InfixParser {
+            input: s,
+            tokens,
+            pos: 0,
+        };
+        let prop = parser.parse_equiv()?;
+        match parser.peek() {
+            None => Ok(prop),
+            Some(")") => Err(parser.error_here(ParseErrorKind::UnmatchedParen)),
+            Some(_) => Err(parser.error_here(ParseErrorKind::TrailingToken)),
         }
     }
 
@@ -154,6 +203,18 @@ impl Prop {
                 let str_b = b.as_ref().to_string();
                 format!("( {str_a} | {str_b} )")
             }
+            Prop::Not(a) => {
+                let str_a = a.as_ref().to_string();
+                format!("! {str_a}")
+            }
+            Prop::Iff(a, b) => {
+                let str_a = a.as_ref().to_string();
+                let str_b = b.as_ref().to_string();
+                format!("( {str_a} <=> {str_b} )")
+            }
+            Prop::Pred(p, args) => format!("{}({})", p, fol::join_terms(args)),
+            Prop::Forall(x, a) => format!("( forall {x}. {} )", a.as_ref().to_string()),
+            Prop::Exists(x, a) => format!("( exists {x}. {} )", a.as_ref().to_string()),
         }
     }
 
@@ -177,13 +238,29 @@ impl Prop {
                 let str_b = b.as_ref().to_string_rpn();
                 format!("{str_a} {str_b} |")
             }
+            Prop::Not(a) => {
+                let str_a = a.as_ref().to_string_rpn();
+                format!("{str_a} !")
+            }
+            Prop::Iff(a, b) => {
+                let str_a = a.as_ref().to_string_rpn();
+                let str_b = b.as_ref().to_string_rpn();
+                format!("{str_a} {str_b} <=>")
+            }
+            Prop::Pred(p, args) => {
+                let args: Vec<String> = args.iter().map(|arg| arg.to_string_rpn()).collect();
+                format!("{}({})", p, args.join(","))
+            }
+            Prop::Forall(x, a) => format!("{x} {} forall", a.as_ref().to_string_rpn()),
+            Prop::Exists(x, a) => format!("{x} {} exists", a.as_ref().to_string_rpn()),
         }
     }
 
     pub fn depth(self: &Prop) -> usize {
         match self {
-            Prop::True | Prop::False | Prop::Name(_) => 1,
-            Prop::Implies(a, b) | Prop::And(a, b) | Prop::Or(a, b) => {
+            Prop::True | Prop::False | Prop::Name(_) | Prop::Pred(_, _) => 1,
+            Prop::Not(a) | Prop::Forall(_, a) | Prop::Exists(_, a) => a.as_ref().depth() + 1,
+            Prop::Implies(a, b) | Prop::And(a, b) | Prop::Or(a, b) | Prop::Iff(a, b) => {
                 max(a.as_ref().depth(), b.as_ref().depth()) + 1
             }
         }
@@ -191,12 +268,647 @@ impl Prop {
 
     pub fn items(self: &Prop) -> usize {
         match self {
-            Prop::True | Prop::False | Prop::Name(_) => 1,
-            Prop::Implies(a, b) | Prop::And(a, b) | Prop::Or(a, b) => {
+            Prop::True | Prop::False | Prop::Name(_) | Prop::Pred(_, _) => 1,
+            Prop::Not(a) | Prop::Forall(_, a) | Prop::Exists(_, a) => a.as_ref().items() + 1,
+            Prop::Implies(a, b) | Prop::And(a, b) | Prop::Or(a, b) | Prop::Iff(a, b) => {
                 a.as_ref().items() + b.as_ref().items() + 1
             }
         }
     }
+
+    pub fn eval(self: &Prop, valuation: &Valuation) -> bool {
+        match self {
+            Prop::True => true,
+            Prop::False => false,
+            Prop::Name(_) | Prop::Pred(_, _) | Prop::Forall(_, _) | Prop::Exists(_, _) => {
+                let name = self.atom_name().unwrap();
+                valuation.get(&name).copied().unwrap_or(false)
+            }
+            Prop::Implies(a, b) => !a.eval(valuation) || b.eval(valuation),
+            Prop::And(a, b) => a.eval(valuation) && b.eval(valuation),
+            Prop::Or(a, b) => a.eval(valuation) || b.eval(valuation),
+            Prop::Not(a) => !a.eval(valuation),
+            Prop::Iff(a, b) => a.eval(valuation) == b.eval(valuation),
+        }
+    }
+
+    // propositional reasoning only sees first-order formulas as a whole,
+    // they are atoms named after how they are written
+    pub fn atom_name(self: &Prop) -> Option<String> {
+        match self {
+            Prop::Name(name) => Some(name.clone()),
+            Prop::Pred(_, _) | Prop::Forall(_, _) | Prop::Exists(_, _) => Some(self.to_string()),
+            _ => None,
+        }
+    }
+
+    pub fn atoms(self: &Prop) -> BTreeSet<String> {
+        let mut atoms = BTreeSet::new();
+        self.collect_atoms(&mut atoms);
+        atoms
+    }
+
+    fn collect_atoms(self: &Prop, atoms: &mut BTreeSet<String>) {
+        match self {
+            Prop::True | Prop::False => (),
+            Prop::Name(_) | Prop::Pred(_, _) | Prop::Forall(_, _) | Prop::Exists(_, _) => {
+                atoms.insert(self.atom_name().unwrap());
+            }
+            Prop::Not(a) => a.collect_atoms(atoms),
+            Prop::Implies(a, b) | Prop::And(a, b) | Prop::Or(a, b) | Prop::Iff(a, b) => {
+                a.collect_atoms(atoms);
+                b.collect_atoms(atoms);
+            }
+        }
+    }
+
+    // every atom named in `subst` replaced by its proposition, all at once
+    pub fn subst_atoms(self: &Prop, subst: &BTreeMap<String, PropRef>) -> Prop {
+        let go = |p: &PropRef| p.subst_atoms(subst);
+        match self {
+            Prop::Name(name) => match subst.get(name) {
+                Some(prop) => prop.as_ref().clone(),
+                None => self.clone(),
+            },
+            Prop::True | Prop::False | Prop::Pred(_, _) => self.clone(),
+            Prop::Implies(a, b) => Prop::imply(go(a), go(b)),
+            Prop::And(a, b) => Prop::and(go(a), go(b)),
+            Prop::Or(a, b) => Prop::or(go(a), go(b)),
+            Prop::Not(a) => Prop::not(go(a)),
+            Prop::Iff(a, b) => Prop::equiv(go(a), go(b)),
+            Prop::Forall(x, a) => Prop::forall(x.clone(), go(a)),
+            Prop::Exists(x, a) => Prop::exists(x.clone(), go(a)),
+        }
+    }
+
+    // every negation replaced by its definition a => F
+    pub fn unfold_not(self: &Prop) -> Prop {
+        self.unfold(true, false)
+    }
+
+    // every biconditional replaced by its definition (a => b) ^ (b => a)
+    pub fn unfold_iff(self: &Prop) -> Prop {
+        self.unfold(false, true)
+    }
+
+    // only the connectives the proof search and the kernel work with
+    pub fn unfold_all(self: &Prop) -> Prop {
+        self.unfold(true, true)
+    }
+
+    fn unfold(self: &Prop, not: bool, iff: bool) -> Prop {
+        let go = |p: &PropRef| p.unfold(not, iff);
+        match self {
+            Prop::True | Prop::False | Prop::Name(_) | Prop::Pred(_, _) => self.clone(),
+            Prop::Implies(a, b) => Prop::imply(go(a), go(b)),
+            Prop::And(a, b) => Prop::and(go(a), go(b)),
+            Prop::Or(a, b) => Prop::or(go(a), go(b)),
+            Prop::Forall(x, a) => Prop::forall(x.clone(), go(a)),
+            Prop::Exists(x, a) => Prop::exists(x.clone(), go(a)),
+            Prop::Not(a) if not => Prop::imply(go(a), Prop::False),
+            Prop::Not(a) => Prop::not(go(a)),
+            Prop::Iff(a, b) if iff => {
+                let (a, b) = (go(a), go(b));
+                Prop::and(Prop::imply(a.clone(), b.clone()), Prop::imply(b, a))
+            }
+            Prop::Iff(a, b) => Prop::equiv(go(a), go(b)),
+        }
+    }
+
+    // the definition of the outermost connective, the other ones untouched
+    pub fn unfold_head(self: &Prop) -> Prop {
+        match self {
+            Prop::Not(a) => Prop::Implies(a.clone(), PropRef::new(Prop::False)),
+            Prop::Iff(a, b) => Prop::and(
+                Prop::Implies(a.clone(), b.clone()),
+                Prop::Implies(b.clone(), a.clone()),
+            ),
+            _ => self.clone(),
+        }
+    }
+
+    // decided by the SAT solver, whatever the number of atoms
+    pub fn is_tautology(self: &Prop) -> bool {
+        sat::satisfying_valuation(&Prop::not(self.clone())).is_none()
+    }
+
+    pub fn is_satisfiable(self: &Prop) -> bool {
+        sat::satisfying_valuation(self).is_some()
+    }
+}
+
+// the most atoms a truth table is built for, already 65536 rows
+pub const MAX_TABLE_ATOMS: usize = 16;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TooManyAtoms {
+    pub found: usize,
+}
+
+impl fmt::Display for TooManyAtoms {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} atoms, truth tables are limited to {}",
+            self.found, MAX_TABLE_ATOMS
+        )
+    }
+}
+
+impl Error for TooManyAtoms {}
+
+// every valuation of the atoms, in the usual truth table order: all false
+// first, the last atom changing fastest
+pub fn valuations(atoms: &[String]) -> Result<impl Iterator<Item = Valuation> + '_, TooManyAtoms> {
+    if atoms.len() > MAX_TABLE_ATOMS {
+        return Err(TooManyAtoms { found: atoms.len() });
+    }
+    Ok((0..1u32 << atoms.len()).map(move |bits| {
+        atoms
+            .iter()
+            .enumerate()
+            .map(|(i, atom)| (atom.clone(), (bits >> (atoms.len() - 1 - i)) & 1 == 1))
+            .collect()
+    }))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TruthTable {
+    pub atoms: Vec<String>,
+    pub labels: Vec<String>,
+    // values of the atoms, then of the labelled propositions
+    pub rows: Vec<(Vec<bool>, Vec<bool>)>,
+}
+
+impl TruthTable {
+    pub fn new(props: &[(String, PropRef)]) -> Result<TruthTable, TooManyAtoms> {
+        let atoms: BTreeSet<String> = props.iter().flat_map(|(_, prop)| prop.atoms()).collect();
+        let atoms: Vec<String> = atoms.into_iter().collect();
+        let rows = valuations(&atoms)?
+            .map(|valuation| {
+                (
+                    atoms.iter().map(|atom| valuation[atom]).collect(),
+                    props
+                        .iter()
+                        .map(|(_, prop)| prop.eval(&valuation))
+                        .collect(),
+                )
+            })
+            .collect();
+        Ok(TruthTable {
+            atoms,
+            labels: props.iter().map(|(label, _)| label.clone()).collect(),
+            rows,
+        })
+    }
+}
+
+impl fmt::Display for TruthTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let headers: Vec<&String> = self.atoms.iter().chain(&self.labels).collect();
+        let line = |cells: Vec<&str>| {
+            let cells: Vec<String> = cells
+                .iter()
+                .zip(&headers)
+                .map(|(cell, header)| format!("{:>width$}", cell, width = header.len()))
+                .collect();
+            let (atoms, props) = cells.split_at(self.atoms.len());
+            format!("{} | {}", atoms.join(" "), props.join(" "))
+        };
+        writeln!(f, "{}", line(headers.iter().map(|h| h.as_str()).collect()))?;
+        for (atoms, props) in self.rows.iter() {
+            let bits = atoms.iter().chain(props);
+            writeln!(
+                f,
+                "{}",
+                line(bits.map(|b| if *b { "1" } else { "0" }).collect())
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    MissingOperands { expected: usize, found: usize },
+    LeftoverOperands { found: usize },
+    Empty,
+    UnexpectedEnd,
+    UnexpectedOperator,
+    UnexpectedChar,
+    UnknownOperator,
+    ExpectedVariable,
+    ExpectedTerm,
+    MissingDot,
+    TrailingToken,
+    UnmatchedParen,
+    MissingParen,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub token_index: usize,
+    pub span: Range<usize>,
+    pub token: String,
+}
+
+impl ParseError {
+    fn at_end(s: &str, token_index: usize, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            kind,
+            token_index,
+            span: s.len()..s.len(),
+            token: String::new(),
+        }
+    }
+
+    // the input on one line and a caret line underneath pointing at the span
+    pub fn underline(&self, input: &str) -> String {
+        let offset = input[..self.span.start].chars().count();
+        let width = input[self.span.clone()].chars().count().max(1);
+        format!(
+            "    {}\n    {}{}",
+            input,
+            " ".repeat(offset),
+            "^".repeat(width)
+        )
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            ParseErrorKind::MissingOperands { expected, found } => write!(
+                f,
+                "'{}' expects {} item(s) in accumulator, found {}",
+                self.token, expected, found
+            )?,
+            ParseErrorKind::LeftoverOperands { found } => write!(
+                f,
+                "at the end of parsing, {} items left in the accumulator instead of 1",
+                found
+            )?,
+            ParseErrorKind::Empty => write!(f, "empty proposition")?,
+            ParseErrorKind::UnexpectedEnd => {
+                write!(f, "unexpected end of expression, proposition expected")?
+            }
+            ParseErrorKind::UnexpectedOperator => {
+                write!(f, "unexpected '{}', proposition expected", self.token)?
+            }
+            ParseErrorKind::UnexpectedChar => write!(f, "unexpected character '{}'", self.token)?,
+            ParseErrorKind::UnknownOperator => write!(f, "unknown operator '{}'", self.token)?,
+            ParseErrorKind::ExpectedVariable | ParseErrorKind::ExpectedTerm => {
+                let expected = if self.kind == ParseErrorKind::ExpectedVariable {
+                    "variable"
+                } else {
+                    "term"
+                };
+                if self.token.is_empty() {
+                    write!(f, "unexpected end of expression, {} expected", expected)?
+                } else {
+                    write!(f, "unexpected '{}', {} expected", self.token, expected)?
+                }
+            }
+            ParseErrorKind::MissingDot => write!(f, "missing '.' after the quantified variables")?,
+            ParseErrorKind::TrailingToken => write!(
+                f,
+                "unexpected '{}' after the end of the expression",
+                self.token
+            )?,
+            ParseErrorKind::UnmatchedParen => write!(f, "unmatched ')'")?,
+            ParseErrorKind::MissingParen => write!(f, "missing ')' to close parenthesis")?,
+        };
+        write!(
+            f,
+            " (token {}, bytes {}..{})",
+            self.token_index, self.span.start, self.span.end
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+fn tokenize_whitespace(s: &str) -> Vec<(usize, &str)> {
+    let mut tokens: Vec<(usize, &str)> = vec![];
+    let mut start: Option<usize> = None;
+    for (i, c) in s.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(st)) => {
+                tokens.push((st, &s[st..i]));
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => (),
+        }
+    }
+    if let Some(st) = start {
+        tokens.push((st, &s[st..]));
+    }
+    tokens
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '\''
+}
+
+// names of variables, functions and predicates, the keywords left out
+fn is_variable(token: &str) -> bool {
+    !token.is_empty()
+        && token.chars().all(is_name_char)
+        && !matches!(token, "T" | "F" | "forall" | "exists")
+}
+
+// a predicate application written as a single RPN token, as in P(x,f(y))
+fn parse_pred_token(token: &str, start: usize, index: usize) -> Result<Prop, ParseError> {
+    let shift = |err: ParseError| ParseError {
+        token_index: index,
+        span: err.span.start + start..err.span.end + start,
+        ..err
+    };
+    let mut parser = InfixParser {
+        input: token,
+        tokens: tokenize_infix(token).map_err(shift)?,
+        pos: 0,
+    };
+    let prop = parser.parse_atom().map_err(shift)?;
+    match parser.peek() {
+        None => Ok(prop),
+        Some(_) => Err(shift(parser.error_here(ParseErrorKind::TrailingToken))),
+    }
+}
+
+// the Unicode connectives and the usual ASCII alternatives, as their
+// canonical spelling; any other token is returned unchanged
+fn canonical(token: &str) -> &str {
+    match token {
+        "->" | "→" => "=>",
+        "&" | "/\\" | "∧" => "^",
+        "\\/" | "∨" => "|",
+        "~" | "¬" => "!",
+        "<->" | "↔" => "<=>",
+        "⊤" => "T",
+        "⊥" => "F",
+        "∀" => "forall",
+        "∃" => "exists",
+        token => token,
+    }
+}
+
+// longest first, so that '<->' is not read as '<' and '->'
+const INFIX_OPERATORS: [&str; 8] = ["<=>", "<->", "=>", "->", "/\\", "\\/", "!", "~"];
+
+// splits an infix expression into (byte offset, token) pairs
+fn tokenize_infix(s: &str) -> Result<Vec<(usize, &str)>, ParseError> {
+    let mut tokens: Vec<(usize, &str)> = vec![];
+    let mut chars = s.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let len = if c.is_whitespace() {
+            continue;
+        } else if matches!(
+            c,
+            '(' | ')'
+                | ','
+                | '.'
+                | '^'
+                | '|'
+                | '&'
+                | '→'
+                | '∧'
+                | '∨'
+                | '¬'
+                | '↔'
+                | '⊤'
+                | '⊥'
+                | '∀'
+                | '∃'
+        ) {
+            c.len_utf8()
+        } else if let Some(op) = INFIX_OPERATORS
+            .iter()
+            .find(|op| s[start..].starts_with(*op))
+        {
+            for _ in 1..op.len() {
+                chars.next();
+            }
+            op.len()
+        } else if is_name_char(c) {
+            let mut end = start + c.len_utf8();
+            while let Some(&(i, next)) = chars.peek() {
+                if !is_name_char(next) {
+                    break;
+                }
+                end = i + next.len_utf8();
+                chars.next();
+            }
+            end - start
+        } else {
+            return Err(ParseError {
+                kind: ParseErrorKind::UnexpectedChar,
+                token_index: tokens.len(),
+                span: start..start + c.len_utf8(),
+                token: c.to_string(),
+            });
+        };
+        tokens.push((start, &s[start..start + len]));
+    }
+    Ok(tokens)
+}
+
+impl FoTerm {
+    pub fn parse(s: &str) -> Result<FoTerm, ParseError> {
+        let mut parser = InfixParser {
+            input: s,
+            tokens: tokenize_infix(s)?,
+            pos: 0,
+        };
+        let term = parser.parse_term()?;
+        match parser.peek() {
+            None => Ok(term),
+            Some(_) => Err(parser.error_here(ParseErrorKind::TrailingToken)),
+        }
+    }
+}
+
+// recursive descent, from loosest to tightest binding:
+// '<=>' (right), '=>' (right), '|' (left), '^' (left), '!' (prefix), the
+// quantifiers being read where '!' is but taking everything to their right
+struct InfixParser<'a> {
+    input: &'a str,
+    tokens: Vec<(usize, &'a str)>,
+    pos: usize,
+}
+
+impl<'a> InfixParser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).map(|(_, token)| canonical(token))
+    }
+
+    fn advance(&mut self) -> Option<&'a str> {
+        let token = self.peek();
+        self.pos += 1;
+        token
+    }
+
+    fn error_at(&self, index: usize, kind: ParseErrorKind) -> ParseError {
+        match self.tokens.get(index) {
+            Some(&(start, token)) => ParseError {
+                kind,
+                token_index: index,
+                span: start..start + token.len(),
+                token: String::from(token),
+            },
+            None => ParseError::at_end(self.input, self.tokens.len(), kind),
+        }
+    }
+
+    fn error_here(&self, kind: ParseErrorKind) -> ParseError {
+        self.error_at(self.pos, kind)
+    }
+
+    fn parse_equiv(&mut self) -> Result<Prop, ParseError> {
+        let a = self.parse_imply()?;
+        if self.peek() == Some("<=>") {
+            self.advance();
+            let b = self.parse_equiv()?;
+            return Ok(Prop::equiv(a, b));
+        }
+        Ok(a)
+    }
+
+    fn parse_imply(&mut self) -> Result<Prop, ParseError> {
+        let a = self.parse_or()?;
+        if self.peek() == Some("=>") {
+            self.advance();
+            let b = self.parse_imply()?;
+            return Ok(Prop::imply(a, b));
+        }
+        Ok(a)
+    }
+
+    fn parse_or(&mut self) -> Result<Prop, ParseError> {
+        let mut a = self.parse_and()?;
+        while self.peek() == Some("|") {
+            self.advance();
+            let b = self.parse_and()?;
+            a = Prop::or(a, b);
+        }
+        Ok(a)
+    }
+
+    fn parse_and(&mut self) -> Result<Prop, ParseError> {
+        let mut a = self.parse_not()?;
+        while self.peek() == Some("^") {
+            self.advance();
+            let b = self.parse_not()?;
+            a = Prop::and(a, b);
+        }
+        Ok(a)
+    }
+
+    fn parse_not(&mut self) -> Result<Prop, ParseError> {
+        match self.peek() {
+            Some("!") => {
+                self.advance();
+                Ok(Prop::not(self.parse_not()?))
+            }
+            Some("forall") => {
+                self.advance();
+                self.parse_quantifier(true)
+            }
+            Some("exists") => {
+                self.advance();
+                self.parse_quantifier(false)
+            }
+            _ => self.parse_atom(),
+        }
+    }
+
+    // forall x y. a, the body extending as far right as possible
+    fn parse_quantifier(&mut self, universal: bool) -> Result<Prop, ParseError> {
+        let mut vars = vec![self.parse_variable()?];
+        while self.peek().is_some_and(is_variable) {
+            vars.push(self.parse_variable()?);
+        }
+        if self.peek() != Some(".") {
+            return Err(self.error_here(ParseErrorKind::MissingDot));
+        }
+        self.advance();
+        let body = self.parse_equiv()?;
+        Ok(vars.into_iter().rev().fold(body, |body, x| {
+            if universal {
+                Prop::forall(x, body)
+            } else {
+                Prop::exists(x, body)
+            }
+        }))
+    }
+
+    fn parse_variable(&mut self) -> Result<String, ParseError> {
+        match self.peek() {
+            Some(token) if is_variable(token) => {
+                self.advance();
+                Ok(String::from(token))
+            }
+            _ => Err(self.error_here(ParseErrorKind::ExpectedVariable)),
+        }
+    }
+
+    fn parse_term(&mut self) -> Result<FoTerm, ParseError> {
+        match self.peek() {
+            Some(token) if is_variable(token) => {
+                self.advance();
+                if self.peek() == Some("(") {
+                    Ok(FoTerm::App(String::from(token), self.parse_args()?))
+                } else {
+                    Ok(FoTerm::Var(String::from(token)))
+                }
+            }
+            _ => Err(self.error_here(ParseErrorKind::ExpectedTerm)),
+        }
+    }
+
+    // (t1, ..., tn) right after a predicate or function symbol
+    fn parse_args(&mut self) -> Result<Vec<FoTerm>, ParseError> {
+        self.advance();
+        let mut args = vec![self.parse_term()?];
+        while self.peek() == Some(",") {
+            self.advance();
+            args.push(self.parse_term()?);
+        }
+        if self.peek() != Some(")") {
+            return Err(self.error_here(ParseErrorKind::MissingParen));
+        }
+        self.advance();
+        Ok(args)
+    }
+
+    fn parse_atom(&mut self) -> Result<Prop, ParseError> {
+        let index = self.pos;
+        match self.advance() {
+            None => Err(self.error_at(index, ParseErrorKind::UnexpectedEnd)),
+            Some("(") => {
+                let prop = self.parse_equiv()?;
+                match self.peek() {
+                    Some(")") => {
+                        self.advance();
+                        Ok(prop)
+                    }
+                    _ => Err(self.error_here(ParseErrorKind::MissingParen)),
+                }
+            }
+            Some("T") => Ok(Prop::True),
+            Some("F") => Ok(Prop::False),
+            Some(token) if is_variable(token) => {
+                if self.peek() == Some("(") {
+                    Ok(Prop::Pred(String::from(token), self.parse_args()?))
+                } else {
+                    Ok(Prop::from_name(String::from(token)))
+                }
+            }
+            Some(_) => Err(self.error_at(index, ParseErrorKind::UnexpectedOperator)),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -218,31 +930,37 @@ mod tests {
 
     #[test]
     fn eq_nested() {
-        let imply_a = Prop::Implies(Rc::new(Prop::True), Rc::new(Prop::True));
-        let imply_b = Prop::Implies(Rc::new(Prop::True), Rc::new(Prop::True));
+        let imply_a = Prop::Implies(PropRef::new(Prop::True), PropRef::new(Prop::True));
+        let imply_b = Prop::Implies(PropRef::new(Prop::True), PropRef::new(Prop::True));
         let and_a = Prop::And(
-            Rc::new(Prop::True),
-            Rc::new(Prop::Name(String::from("name"))),
+            PropRef::new(Prop::True),
+            PropRef::new(Prop::Name(String::from("name"))),
         );
         let and_b = Prop::And(
-            Rc::new(Prop::True),
-            Rc::new(Prop::Name(String::from("name"))),
+            PropRef::new(Prop::True),
+            PropRef::new(Prop::Name(String::from("name"))),
         );
         let or_a = Prop::Or(
-            Rc::new(Prop::False),
-            Rc::new(Prop::Name(String::from("name"))),
+            PropRef::new(Prop::False),
+            PropRef::new(Prop::Name(String::from("name"))),
         );
         let or_b = Prop::Or(
-            Rc::new(Prop::False),
-            Rc::new(Prop::Name(String::from("name"))),
+            PropRef::new(Prop::False),
+            PropRef::new(Prop::Name(String::from("name"))),
         );
         let nested_a = Prop::Implies(
-            Rc::new(Prop::Implies(Rc::new(Prop::True), Rc::new(Prop::True))),
-            Rc::new(Prop::False),
+            PropRef::new(Prop::Implies(
+                PropRef::new(Prop::True),
+                PropRef::new(Prop::True),
+            )),
+            PropRef::new(Prop::False),
         );
         let nested_b = Prop::Implies(
-            Rc::new(Prop::Implies(Rc::new(Prop::True), Rc::new(Prop::True))),
-            Rc::new(Prop::False),
+            PropRef::new(Prop::Implies(
+                PropRef::new(Prop::True),
+                PropRef::new(Prop::True),
+            )),
+            PropRef::new(Prop::False),
         );
         assert_eq!(imply_a, imply_b);
         assert_eq!(and_a, and_b);
@@ -258,22 +976,50 @@ mod tests {
         let imply = Prop::imply(Prop::True, Prop::False);
         assert_eq!(
             imply,
-            Prop::Implies(Rc::new(Prop::True), Rc::new(Prop::False))
+            Prop::Implies(PropRef::new(Prop::True), PropRef::new(Prop::False))
         );
 
         let and = Prop::and(Prop::True, Prop::False);
-        assert_eq!(and, Prop::And(Rc::new(Prop::True), Rc::new(Prop::False)));
+        assert_eq!(
+            and,
+            Prop::And(PropRef::new(Prop::True), PropRef::new(Prop::False))
+        );
 
         let or = Prop::or(Prop::True, Prop::False);
-        assert_eq!(or, Prop::Or(Rc::new(Prop::True), Rc::new(Prop::False)));
+        assert_eq!(
+            or,
+            Prop::Or(PropRef::new(Prop::True), PropRef::new(Prop::False))
+        );
 
         let not = Prop::not(Prop::True);
+        assert_eq!(not, Prop::Not(PropRef::new(Prop::True)));
+
+        let equiv = Prop::equiv(Prop::True, Prop::False);
         assert_eq!(
-            not,
-            Prop::Implies(Rc::new(Prop::True), Rc::new(Prop::False))
+            equiv,
+            Prop::Iff(PropRef::new(Prop::True), PropRef::new(Prop::False))
         );
     }
 
+    #[test]
+    fn unfold() {
+        let prop = |s: &str| Prop::parse_infix(s).unwrap();
+        let nested = prop("!(a <=> !b) | (c <=> d)");
+        assert_eq!(nested.to_string(), "( ! ( a <=> ! b ) | ( c <=> d ) )");
+        assert_eq!(
+            nested.unfold_not(),
+            prop("((a <=> (b => F)) => F) | (c <=> d)")
+        );
+        assert_eq!(
+            nested.unfold_iff(),
+            prop("!((a => !b) ^ (!b => a)) | ((c => d) ^ (d => c))")
+        );
+        assert_eq!(nested.unfold_all(), nested.unfold_not().unfold_iff());
+        assert_eq!(nested.unfold_head(), nested);
+        assert_eq!(prop("!!a").unfold_head(), prop("!a => F"));
+        assert_eq!(prop("a => F").unfold_not(), prop("a => F"));
+    }
+
     #[test]
     fn depth() {
         let p_true = Prop::True;
@@ -321,7 +1067,7 @@ mod tests {
             Prop::imply(Prop::not(Prop::False), Prop::and(Prop::True, Prop::False)),
             Prop::or(Prop::from_name(String::from("name")), Prop::False),
         );
-        assert_eq!(p_complex.items(), 11);
+        assert_eq!(p_complex.items(), 10);
     }
 
     #[test]
@@ -366,19 +1112,13 @@ mod tests {
         );
         assert_eq!(
             Prop::parse_rpn(rpn_simple_not),
-            Ok(Prop::imply(Prop::from_name(String::from("a")), Prop::False))
+            Ok(Prop::not(Prop::from_name(String::from("a"))))
         );
         assert_eq!(
             Prop::parse_rpn(rpn_simple_equiv),
-            Ok(Prop::and(
-                Prop::imply(
-                    Prop::from_name(String::from("a")),
-                    Prop::from_name(String::from("b"))
-                ),
-                Prop::imply(
-                    Prop::from_name(String::from("b")),
-                    Prop::from_name(String::from("a"))
-                )
+            Ok(Prop::equiv(
+                Prop::from_name(String::from("a")),
+                Prop::from_name(String::from("b"))
             ))
         );
 
@@ -405,52 +1145,275 @@ mod tests {
     fn parse_rpn_bad() {
         assert_eq!(
             Prop::parse_rpn(""),
-            Err("At the end of parsing, accumulator is empty")
+            Err(ParseError {
+                kind: ParseErrorKind::Empty,
+                token_index: 0,
+                span: 0..0,
+                token: String::new(),
+            })
         );
         assert_eq!(
             Prop::parse_rpn("a b"),
-            Err("At the end of parsing, > 1 item left unused in the accumulator")
+            Err(ParseError {
+                kind: ParseErrorKind::LeftoverOperands { found: 2 },
+                token_index: 1,
+                span: 2..3,
+                token: String::from("b"),
+            })
         );
+        assert_eq!(
+            Prop::parse_rpn("a b c ^ ^ d e |"),
+            Err(ParseError {
+                kind: ParseErrorKind::LeftoverOperands { found: 2 },
+                token_index: 5,
+                span: 10..15,
+                token: String::from("d e |"),
+            })
+        );
+
+        for op in ["=>", "^", "|", "<=>"] {
+            assert_eq!(
+                Prop::parse_rpn(&format!("a  {op}")),
+                Err(ParseError {
+                    kind: ParseErrorKind::MissingOperands {
+                        expected: 2,
+                        found: 1
+                    },
+                    token_index: 1,
+                    span: 3..3 + op.len(),
+                    token: String::from(op),
+                })
+            );
+            assert_eq!(
+                Prop::parse_rpn(op),
+                Err(ParseError {
+                    kind: ParseErrorKind::MissingOperands {
+                        expected: 2,
+                        found: 0
+                    },
+                    token_index: 0,
+                    span: 0..op.len(),
+                    token: String::from(op),
+                })
+            );
+        }
 
         assert_eq!(
-            Prop::parse_rpn("a =>"),
-            Err("During parsing of '=>', two items expected in accumulator, found one")
+            Prop::parse_rpn("!"),
+            Err(ParseError {
+                kind: ParseErrorKind::MissingOperands {
+                    expected: 1,
+                    found: 0
+                },
+                token_index: 0,
+                span: 0..1,
+                token: String::from("!"),
+            })
         );
         assert_eq!(
-            Prop::parse_rpn("=>"),
-            Err("During parsing of '=>', two items expected in accumulator, found zero")
+            Prop::parse_rpn("a ¬"),
+            Ok(Prop::not(Prop::from_name(String::from("a"))))
         );
+        for op in ["<-", "=>>", "&&", "⇒", "a-b"] {
+            assert_eq!(
+                Prop::parse_rpn(&format!("a b {op}")),
+                Err(ParseError {
+                    kind: ParseErrorKind::UnknownOperator,
+                    token_index: 2,
+                    span: 4..4 + op.len(),
+                    token: String::from(op),
+                })
+            );
+        }
+        assert_eq!(
+            Prop::parse_rpn("a b ->>").unwrap_err().to_string(),
+            "unknown operator '->>' (token 2, bytes 4..7)"
+        );
+    }
 
+    #[test]
+    fn parse_error_display() {
+        let err = Prop::parse_rpn("a b => =>").unwrap_err();
         assert_eq!(
-            Prop::parse_rpn("a ^"),
-            Err("During parsing of '^', two items expected in accumulator, found one")
+            err.to_string(),
+            "'=>' expects 2 item(s) in accumulator, found 1 (token 3, bytes 7..9)"
         );
+        assert_eq!(err.underline("a b => =>"), "    a b => =>\n           ^^");
+
+        let err = Prop::parse_infix("a ^").unwrap_err();
         assert_eq!(
-            Prop::parse_rpn("^"),
-            Err("During parsing of '^', two items expected in accumulator, found zero")
+            err.to_string(),
+            "unexpected end of expression, proposition expected (token 2, bytes 3..3)"
         );
+        assert_eq!(err.underline("a ^"), "    a ^\n       ^");
+    }
+
+    #[test]
+    fn parse_infix() {
+        let a = || Prop::from_name(String::from("a"));
+        let b = || Prop::from_name(String::from("b"));
+        let c = || Prop::from_name(String::from("c"));
+
+        assert_eq!(Prop::parse_infix("T"), Ok(Prop::True));
+        assert_eq!(Prop::parse_infix("F"), Ok(Prop::False));
+        assert_eq!(Prop::parse_infix("a"), Ok(a()));
+        assert_eq!(Prop::parse_infix("((a))"), Ok(a()));
+        assert_eq!(Prop::parse_infix("!a"), Ok(Prop::not(a())));
+        assert_eq!(Prop::parse_infix("!!a"), Ok(Prop::not(Prop::not(a()))));
+        assert_eq!(Prop::parse_infix("a <=> b"), Ok(Prop::equiv(a(), b())));
 
         assert_eq!(
-            Prop::parse_rpn("a |"),
-            Err("During parsing of '|', two items expected in accumulator, found one")
+            Prop::parse_infix("(a => b) ^ !c | T"),
+            Ok(Prop::or(
+                Prop::and(Prop::imply(a(), b()), Prop::not(c())),
+                Prop::True
+            ))
         );
         assert_eq!(
-            Prop::parse_rpn("|"),
-            Err("During parsing of '|', two items expected in accumulator, found zero")
+            Prop::parse_infix("a => b => c"),
+            Ok(Prop::imply(a(), Prop::imply(b(), c())))
         );
+        assert_eq!(
+            Prop::parse_infix("a ^ b ^ c"),
+            Ok(Prop::and(Prop::and(a(), b()), c()))
+        );
+        assert_eq!(
+            Prop::parse_infix("a | b ^ c"),
+            Ok(Prop::or(a(), Prop::and(b(), c())))
+        );
+        assert_eq!(
+            Prop::parse_infix("a|b=>c"),
+            Ok(Prop::imply(Prop::or(a(), b()), c()))
+        );
+        assert_eq!(
+            Prop::parse_infix("a => b <=> c"),
+            Ok(Prop::equiv(Prop::imply(a(), b()), c()))
+        );
+    }
+
+    #[test]
+    fn parse_infix_roundtrip() {
+        let props = [
+            "a b => c ^ d d => |",
+            "a ! b c | ^ T =>",
+            "a b <=> c =>",
+            "F a b ^ |",
+        ];
+        for rpn in props {
+            let prop = Prop::parse_rpn(rpn).unwrap();
+            assert_eq!(Prop::parse_infix(&prop.to_string()), Ok(prop));
+        }
+    }
+
+    #[test]
+    fn parse_infix_bad() {
+        let kind = |s: &str| Prop::parse_infix(s).map_err(|err| err.kind);
+        assert_eq!(kind(""), Err(ParseErrorKind::UnexpectedEnd));
+        assert_eq!(kind("a ^"), Err(ParseErrorKind::UnexpectedEnd));
+        assert_eq!(kind("a b"), Err(ParseErrorKind::TrailingToken));
+        assert_eq!(kind("(a ^ b"), Err(ParseErrorKind::MissingParen));
+        assert_eq!(kind("a ^ b)"), Err(ParseErrorKind::UnmatchedParen));
+        assert_eq!(kind("a ^ | b"), Err(ParseErrorKind::UnexpectedOperator));
+        assert_eq!(kind("a # b"), Err(ParseErrorKind::UnexpectedChar));
+        assert_eq!(kind("a <- b"), Err(ParseErrorKind::UnexpectedChar));
 
         assert_eq!(
-            Prop::parse_rpn("!"),
-            Err("During parsing of '!', one item expected in accumulator, found zero")
+            Prop::parse_infix("(a ^ b) c"),
+            Err(ParseError {
+                kind: ParseErrorKind::TrailingToken,
+                token_index: 5,
+                span: 8..9,
+                token: String::from("c"),
+            })
         );
+        assert_eq!(
+            Prop::parse_infix("a => & b"),
+            Err(ParseError {
+                kind: ParseErrorKind::UnexpectedOperator,
+                token_index: 2,
+                span: 5..6,
+                token: String::from("&"),
+            })
+        );
+        assert_eq!(
+            Prop::parse_infix("a → ∧ b"),
+            Err(ParseError {
+                kind: ParseErrorKind::UnexpectedOperator,
+                token_index: 2,
+                span: 6..9,
+                token: String::from("∧"),
+            })
+        );
+    }
 
+    #[test]
+    fn alternative_syntax() {
+        let expected = Prop::parse_infix("(a => b) ^ !c | T <=> F").unwrap();
+        for infix in [
+            "(a → b) ∧ ¬c ∨ ⊤ ↔ ⊥",
+            "(a -> b) & ~c \\/ T <-> F",
+            "(a->b)/\\~c\\/T<->F",
+        ] {
+            assert_eq!(Prop::parse_infix(infix), Ok(expected.clone()), "{}", infix);
+        }
+        for rpn in ["a b → c ¬ ∧ ⊤ ∨ ⊥ ↔", "a b -> c ~ /\\ T \\/ F <->"] {
+            assert_eq!(Prop::parse_rpn(rpn), Ok(expected.clone()), "{}", rpn);
+        }
+    }
+
+    #[test]
+    fn first_order() {
+        let var = |x: &str| FoTerm::Var(String::from(x));
+        let p_x = Prop::Pred(String::from("P"), vec![var("x")]);
+        let r = Prop::Pred(
+            String::from("R"),
+            vec![var("x"), FoTerm::App(String::from("f"), vec![var("y")])],
+        );
+        let expected = Prop::forall(
+            String::from("x"),
+            Prop::exists(String::from("y"), Prop::imply(p_x, r)),
+        );
+        for infix in [
+            "forall x. exists y. P(x) => R(x, f(y))",
+            "forall x. exists y. (P(x) => R(x,f(y)))",
+            "∀x. ∃y. P(x) → R(x, f(y))",
+        ] {
+            assert_eq!(Prop::parse_infix(infix), Ok(expected.clone()), "{}", infix);
+        }
+        assert_eq!(
+            Prop::parse_infix("forall x y. R(x, y)"),
+            Prop::parse_infix("forall x. forall y. R(x, y)")
+        );
+        assert_eq!(
+            expected.to_string(),
+            "( forall x. ( exists y. ( P(x) => R(x, f(y)) ) ) )"
+        );
         assert_eq!(
-            Prop::parse_rpn("a <=>"),
-            Err("During parsing of '<=>', two items expected in accumulator, found one")
+            expected.to_string_rpn(),
+            "x y P(x) R(x,f(y)) => exists forall"
         );
         assert_eq!(
-            Prop::parse_rpn("<=>"),
-            Err("During parsing of '<=>', two items expected in accumulator, found zero")
+            Prop::parse_rpn(&expected.to_string_rpn()),
+            Ok(expected.clone())
+        );
+        assert_eq!(Prop::parse_infix(&expected.to_string()), Ok(expected));
+        assert_eq!(
+            FoTerm::parse("g(a, h(b))").map(|t| t.to_string()),
+            Ok(String::from("g(a, h(b))"))
+        );
+
+        let kind = |s: &str| Prop::parse_infix(s).map_err(|err| err.kind);
+        assert_eq!(kind("forall . P(x)"), Err(ParseErrorKind::ExpectedVariable));
+        assert_eq!(kind("forall x P(x)"), Err(ParseErrorKind::MissingDot));
+        assert_eq!(kind("P(x, )"), Err(ParseErrorKind::ExpectedTerm));
+        assert_eq!(kind("P(x"), Err(ParseErrorKind::MissingParen));
+        assert_eq!(
+            Prop::parse_rpn("a b ^ P(x) forall").map_err(|err| err.kind),
+            Err(ParseErrorKind::ExpectedVariable)
+        );
+        assert_eq!(
+            FoTerm::parse("f(x) y").map_err(|err| err.kind),
+            Err(ParseErrorKind::TrailingToken)
         );
     }
 
@@ -494,4 +1457,89 @@ mod tests {
             "a b |"
         );
     }
+
+    #[test]
+    fn eval() {
+        let prop = Prop::parse_infix("(a => b) ^ !c | T => b").unwrap();
+        let valuation = Valuation::from([(String::from("a"), true), (String::from("c"), true)]);
+        assert!(!prop.eval(&valuation));
+        let valuation = Valuation::from([(String::from("b"), true)]);
+        assert!(prop.eval(&valuation));
+        assert!(!Prop::False.eval(&Valuation::new()));
+        assert_eq!(
+            prop.atoms().into_iter().collect::<Vec<String>>(),
+            vec!["a", "b", "c"]
+        );
+        assert!(Prop::True.atoms().is_empty());
+    }
+
+    #[test]
+    fn tautologies() {
+        for statement in ["a | !a", "!!a => a", "((a => b) => a) => a", "T", "a => a"] {
+            assert!(
+                Prop::parse_infix(statement).unwrap().is_tautology(),
+                "{}",
+                statement
+            );
+        }
+        let contingent = Prop::parse_infix("a => b").unwrap();
+        assert!(!contingent.is_tautology());
+        assert!(contingent.is_satisfiable());
+        assert!(!Prop::parse_infix("a ^ !a").unwrap().is_satisfiable());
+        assert!(!Prop::False.is_satisfiable());
+
+        // far too many atoms for a truth table
+        let many = (1..70).fold(Prop::from_name(String::from("a0")), |acc, i| {
+            Prop::and(acc, Prop::from_name(format!("a{}", i)))
+        });
+        assert!(Prop::imply(many.clone(), Prop::from_name(String::from("a42"))).is_tautology());
+        assert!(!Prop::imply(Prop::from_name(String::from("b")), many.clone()).is_tautology());
+        assert!(Prop::and(many, Prop::not(Prop::from_name(String::from("b")))).is_satisfiable());
+
+        let atoms = vec![String::from("x"), String::from("y")];
+        let rows: Vec<Vec<bool>> = valuations(&atoms)
+            .unwrap()
+            .map(|valuation| atoms.iter().map(|atom| valuation[atom]).collect())
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec![false, false],
+                vec![false, true],
+                vec![true, false],
+                vec![true, true]
+            ]
+        );
+        let atoms: Vec<String> = (0..=MAX_TABLE_ATOMS).map(|i| format!("a{}", i)).collect();
+        assert_eq!(
+            valuations(&atoms).err(),
+            Some(TooManyAtoms {
+                found: MAX_TABLE_ATOMS + 1
+            })
+        );
+    }
+
+    #[test]
+    fn truth_table() {
+        let table = TruthTable::new(&[
+            (
+                String::from("H0"),
+                PropRef::new(Prop::parse_infix("a => b").unwrap()),
+            ),
+            (
+                String::from("Goal"),
+                PropRef::new(Prop::parse_infix("b").unwrap()),
+            ),
+        ])
+        .unwrap();
+        assert_eq!(table.atoms, vec!["a", "b"]);
+        assert_eq!(table.rows[2], (vec![true, false], vec![false, false]));
+        assert_eq!(
+            table.to_string(),
+            "a b | H0 Goal\n0 0 |  1    0\n0 1 |  1    1\n1 0 |  0    0\n1 1 |  1    1\n"
+        );
+        let constant =
+            TruthTable::new(&[(String::from("Goal"), PropRef::new(Prop::True))]).unwrap();
+        assert_eq!(constant.to_string(), " | Goal\n |    1\n");
+    }
 }
@@ -0,0 +1,360 @@
+use std::{collections::BTreeMap, fmt};
+
+use crate::libpoulet::intern::PropRef;
+use crate::libpoulet::logic::Prop;
+use crate::libpoulet::sat;
+
+// Classical normal forms. Implications are rewritten as disjunctions, `a => F`
+// as a negation, and biconditionals are unfolded, so the results are only
+// classically equivalent to the original proposition.
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Literal {
+    // a name, a predicate or a quantified formula
+    pub atom: PropRef,
+    pub positive: bool,
+}
+
+// conjunction of clauses, each the disjunction of its literals
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClauseSet {
+    pub clauses: Vec<Vec<Literal>>,
+}
+
+// disjunction of cubes, each the conjunction of its literals
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CubeSet {
+    pub cubes: Vec<Vec<Literal>>,
+}
+
+impl Literal {
+    fn to_prop(&self) -> Prop {
+        let atom = self.atom.as_ref().clone();
+        if self.positive { atom } else { Prop::not(atom) }
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let atom = self.atom.atom_name().unwrap();
+        if self.positive {
+            write!(f, "{}", atom)
+        } else {
+            write!(f, "!{}", atom)
+        }
+    }
+}
+
+// sorted, without repeated literals, and None if it contains a literal
+// along with its negation
+fn normalize(mut lits: Vec<Literal>) -> Option<Vec<Literal>> {
+    lits.sort();
+    lits.dedup();
+    let complementary = lits
+        .windows(2)
+        .any(|pair| pair[0].atom == pair[1].atom && pair[0].positive != pair[1].positive);
+    if complementary { None } else { Some(lits) }
+}
+
+// every way of picking one set from each side, as for (a ^ b) | (c ^ d)
+fn distribute(left: Vec<Vec<Literal>>, right: Vec<Vec<Literal>>) -> Vec<Vec<Literal>> {
+    let mut result = vec![];
+    for a in left.iter() {
+        for b in right.iter() {
+            if let Some(lits) = normalize(a.iter().chain(b).cloned().collect()) {
+                result.push(lits);
+            }
+        }
+    }
+    simplify(result)
+}
+
+// drops the sets containing another one, they add nothing to the formula
+fn simplify(mut sets: Vec<Vec<Literal>>) -> Vec<Vec<Literal>> {
+    sets.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+    sets.dedup();
+    let mut kept: Vec<Vec<Literal>> = vec![];
+    for set in sets {
+        if !kept
+            .iter()
+            .any(|smaller| smaller.iter().all(|lit| set.contains(lit)))
+        {
+            kept.push(set);
+        }
+    }
+    kept.sort();
+    kept
+}
+
+fn join(sets: &[Vec<Literal>], inner: &str, outer: &str, empty: &str) -> String {
+    sets.iter()
+        .map(|lits| {
+            let lits: Vec<String> = lits.iter().map(|lit| lit.to_string()).collect();
+            if lits.is_empty() {
+                String::from(empty)
+            } else {
+                format!("({})", lits.join(inner))
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(outer)
+}
+
+fn fold(props: impl Iterator<Item = Prop>, empty: Prop, op: fn(Prop, Prop) -> Prop) -> Prop {
+    props.reduce(op).unwrap_or(empty)
+}
+
+impl ClauseSet {
+    pub fn to_prop(&self) -> Prop {
+        let clauses = self
+            .clauses
+            .iter()
+            .map(|clause| fold(clause.iter().map(Literal::to_prop), Prop::False, Prop::or));
+        fold(clauses, Prop::True, Prop::and)
+    }
+}
+
+impl CubeSet {
+    pub fn to_prop(&self) -> Prop {
+        let cubes = self
+            .cubes
+            .iter()
+            .map(|cube| fold(cube.iter().map(Literal::to_prop), Prop::True, Prop::and));
+        fold(cubes, Prop::False, Prop::or)
+    }
+}
+
+impl fmt::Display for ClauseSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.clauses.is_empty() {
+            return write!(f, "T");
+        }
+        write!(f, "{}", join(&self.clauses, " | ", " ^ ", "F"))
+    }
+}
+
+impl fmt::Display for CubeSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.cubes.is_empty() {
+            return write!(f, "F");
+        }
+        write!(f, "{}", join(&self.cubes, " ^ ", " | ", "T"))
+    }
+}
+
+impl Prop {
+    // negation normal form: only conjunctions, disjunctions, atoms and
+    // negated atoms
+    pub fn nnf(&self) -> Prop {
+        self.nnf_signed(true)
+    }
+
+    fn nnf_signed(&self, positive: bool) -> Prop {
+        let both = |a: &PropRef, sign_a: bool, b: &PropRef, sign_b: bool, and: bool| {
+            let (a, b) = (a.nnf_signed(sign_a), b.nnf_signed(sign_b));
+            if and { Prop::and(a, b) } else { Prop::or(a, b) }
+        };
+        match self {
+            Prop::Name(_) | Prop::Pred(_, _) | Prop::Forall(_, _) | Prop::Exists(_, _) => {
+                if positive {
+                    self.clone()
+                } else {
+                    Prop::not(self.clone())
+                }
+            }
+            Prop::True | Prop::False => {
+                if (*self == Prop::True) == positive {
+                    Prop::True
+                } else {
+                    Prop::False
+                }
+            }
+            Prop::Implies(a, b) if **b == Prop::False => a.nnf_signed(!positive),
+            Prop::Not(a) => a.nnf_signed(!positive),
+            Prop::Iff(_, _) => self.unfold_head().nnf_signed(positive),
+            Prop::Implies(a, b) => both(a, !positive, b, positive, !positive),
+            Prop::And(a, b) => both(a, positive, b, positive, positive),
+            Prop::Or(a, b) => both(a, positive, b, positive, !positive),
+        }
+    }
+
+    // conjunctive normal form by distributing disjunctions over
+    // conjunctions, which can be exponentially larger than the proposition
+    pub fn cnf(&self) -> ClauseSet {
+        ClauseSet {
+            clauses: self.nnf().sets(true),
+        }
+    }
+
+    // disjunctive normal form, the dual of `cnf`
+    pub fn dnf(&self) -> CubeSet {
+        CubeSet {
+            cubes: self.nnf().sets(false),
+        }
+    }
+
+    // clauses (or cubes) of a proposition in negation normal form
+    fn sets(&self, clauses: bool) -> Vec<Vec<Literal>> {
+        let literal = |atom: &Prop, positive: bool| match atom.atom_name() {
+            Some(_) => vec![vec![Literal {
+                atom: PropRef::new(atom.clone()),
+                positive,
+            }]],
+            None => unreachable!("not in negation normal form"),
+        };
+        match self {
+            Prop::Name(_) | Prop::Pred(_, _) | Prop::Forall(_, _) | Prop::Exists(_, _) => {
+                literal(self, true)
+            }
+            Prop::Not(a) => literal(a, false),
+            Prop::Implies(_, _) | Prop::Iff(_, _) => unreachable!("not in negation normal form"),
+            // T is the empty conjunction, F the empty disjunction
+            Prop::True if clauses => vec![],
+            Prop::False if !clauses => vec![],
+            Prop::True | Prop::False => vec![vec![]],
+            Prop::And(a, b) | Prop::Or(a, b) => {
+                let (a, b) = (a.sets(clauses), b.sets(clauses));
+                if matches!(self, Prop::And(_, _)) == clauses {
+                    simplify(a.into_iter().chain(b).collect())
+                } else {
+                    distribute(a, b)
+                }
+            }
+        }
+    }
+
+    // conjunctive normal form of linear size, satisfiable exactly when the
+    // proposition is; fresh atoms stand for its subformulas and are
+    // returned along with the clauses
+    pub fn tseitin_cnf(&self) -> (ClauseSet, Vec<String>) {
+        let (cnf, atoms) = sat::tseitin(self);
+        let mut names: BTreeMap<usize, PropRef> = atoms
+            .into_iter()
+            .map(|(atom, lit)| (lit.unsigned_abs() as usize, atom))
+            .collect();
+        let taken = self.atoms();
+        let mut prefix = String::from("_t");
+        while taken.iter().any(|atom| atom.starts_with(&prefix)) {
+            prefix.push('_');
+        }
+        let mut fresh = vec![];
+        for v in 1..=cnf.num_vars {
+            names.entry(v).or_insert_with(|| {
+                let name = format!("{}{}", prefix, v);
+                fresh.push(name.clone());
+                PropRef::new(Prop::Name(name))
+            });
+        }
+        let clauses = cnf
+            .clauses
+            .iter()
+            .map(|clause| {
+                clause
+                    .iter()
+                    .map(|lit| Literal {
+                        atom: names[&(lit.unsigned_abs() as usize)].clone(),
+                        positive: *lit > 0,
+                    })
+                    .collect()
+            })
+            .collect();
+        (ClauseSet { clauses }, fresh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prop(s: &str) -> Prop {
+        Prop::parse_infix(s).unwrap()
+    }
+
+    const FORMULAS: [&str; 7] = [
+        "(a => b) => !a | b",
+        "!(a ^ (b | !c))",
+        "(a <=> b) ^ (c | T)",
+        "((a => b) => a) => a",
+        "a ^ !a",
+        "F | (a => F => b)",
+        "(a | b) ^ (c | d) => !(a ^ e)",
+    ];
+
+    #[test]
+    fn nnf() {
+        assert_eq!(prop("!(a => b)").nnf(), prop("a ^ !b"));
+        assert_eq!(prop("!(a | !b)").nnf(), prop("!a ^ b"));
+        assert_eq!(prop("!!a").nnf(), prop("a"));
+        assert_eq!(prop("!T | a").nnf(), prop("F | a"));
+        assert_eq!(prop("!(a <=> b)").nnf(), prop("(a ^ !b) | (b ^ !a)"));
+        assert_eq!(prop("a => F").nnf(), prop("!a"));
+        for formula in FORMULAS {
+            let p = prop(formula);
+            assert!(
+                Prop::equiv(p.clone(), p.nnf()).is_tautology(),
+                "{}",
+                formula
+            );
+        }
+    }
+
+    #[test]
+    fn cnf_and_dnf() {
+        let cnf = prop("a | (b ^ !c)").cnf();
+        assert_eq!(cnf.to_string(), "(a | b) ^ (a | !c)");
+        assert_eq!(
+            cnf.clauses[1][1],
+            Literal {
+                atom: PropRef::new(prop("c")),
+                positive: false
+            }
+        );
+        // the cube with both a and !a is dropped
+        assert_eq!(prop("(a => b) ^ a").dnf().to_string(), "(a ^ b)");
+        assert_eq!(prop("a | !a").cnf().to_string(), "T");
+        assert_eq!(prop("a ^ !a").dnf().to_string(), "F");
+        assert_eq!(prop("F").cnf().to_string(), "F");
+        assert_eq!(prop("!a ^ (!a | b)").cnf().to_string(), "(!a)");
+        assert_eq!(prop("a | (a ^ b)").dnf().to_string(), "(a)");
+        assert_eq!(prop("T").dnf().to_string(), "T");
+        // predicates and quantified formulas stay what they are
+        let first_order = prop("P(a) | Q(a) ^ (forall x. R(x))");
+        let cnf = first_order.cnf();
+        assert_eq!(
+            cnf.to_string(),
+            "(P(a) | Q(a)) ^ (P(a) | ( forall x. R(x) ))"
+        );
+        assert_eq!(
+            cnf.to_prop(),
+            prop("(P(a) | Q(a)) ^ (P(a) | (forall x. R(x)))")
+        );
+        assert!(Prop::equiv(first_order.clone(), cnf.to_prop()).is_tautology());
+        assert!(Prop::equiv(first_order.clone(), first_order.dnf().to_prop()).is_tautology());
+        assert_eq!(first_order.tseitin_cnf().1.len(), 2);
+        for formula in FORMULAS {
+            let p = prop(formula);
+            let cnf = p.cnf().to_prop();
+            let dnf = p.dnf().to_prop();
+            assert!(Prop::equiv(p.clone(), cnf).is_tautology(), "{}", formula);
+            assert!(Prop::equiv(p.clone(), dnf).is_tautology(), "{}", formula);
+        }
+    }
+
+    #[test]
+    fn tseitin() {
+        let (clauses, fresh) = prop("_t1 ^ (b | c)").tseitin_cnf();
+        assert!(fresh.iter().all(|name| name.starts_with("_t_")));
+        assert_eq!(fresh.len(), 2);
+        assert_eq!(clauses.clauses.len(), 7);
+        for formula in FORMULAS {
+            let p = prop(formula);
+            let (clauses, _) = p.tseitin_cnf();
+            assert_eq!(
+                clauses.to_prop().is_satisfiable(),
+                p.is_satisfiable(),
+                "{}",
+                formula
+            );
+        }
+    }
+}
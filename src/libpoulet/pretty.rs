@@ -0,0 +1,360 @@
+use crate::libpoulet::fol::{self, FoTerm};
+use crate::libpoulet::intern::PropRef;
+use crate::libpoulet::logic::Prop;
+
+// Printing with only the parentheses `Prop::parse_infix` needs to read the
+// proposition back, from loosest to tightest binding: '<=>' (right), '=>'
+// (right), '|' (left), '^' (left), '!' (prefix). Quantifiers take everything
+// to their right, so they are put in parentheses below any connective.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PrettyOptions {
+    // → ∧ ∨ ¬ ↔ ⊤ ⊥ instead of => ^ | ! <=> T F
+    pub unicode: bool,
+    // `a => F` shown as the negation it stands for
+    pub negation: bool,
+    // longer propositions are broken before their loosest connectives
+    pub width: Option<usize>,
+}
+
+impl Default for PrettyOptions {
+    fn default() -> PrettyOptions {
+        PrettyOptions {
+            unicode: false,
+            negation: true,
+            width: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Iff,
+    Implies,
+    Or,
+    And,
+}
+
+impl Op {
+    fn precedence(self) -> u8 {
+        match self {
+            Op::Iff => 1,
+            Op::Implies => 2,
+            Op::Or => 3,
+            Op::And => 4,
+        }
+    }
+
+    fn right_assoc(self) -> bool {
+        matches!(self, Op::Iff | Op::Implies)
+    }
+}
+
+const QUANTIFIER_PRECEDENCE: u8 = 0;
+const NOT_PRECEDENCE: u8 = 5;
+const LEAF_PRECEDENCE: u8 = 6;
+
+// a proposition as far as printing goes
+enum View<'a> {
+    Leaf(&'static str),
+    Name(&'a str),
+    Pred(&'a str, &'a [FoTerm]),
+    Not(&'a PropRef),
+    // universal or not, bound variable, body
+    Quantifier(bool, &'a str, &'a PropRef),
+    Binary(Op, &'a PropRef, &'a PropRef),
+}
+
+impl View<'_> {
+    fn precedence(&self) -> u8 {
+        match self {
+            View::Leaf(_) | View::Name(_) | View::Pred(_, _) => LEAF_PRECEDENCE,
+            View::Not(_) => NOT_PRECEDENCE,
+            View::Quantifier(_, _, _) => QUANTIFIER_PRECEDENCE,
+            View::Binary(op, _, _) => op.precedence(),
+        }
+    }
+}
+
+struct Printer<'o> {
+    options: &'o PrettyOptions,
+}
+
+impl Printer<'_> {
+    fn view<'a>(&self, prop: &'a Prop) -> View<'a> {
+        let unicode = self.options.unicode;
+        match prop {
+            Prop::Name(name) => View::Name(name),
+            Prop::True => View::Leaf(if unicode { "⊤" } else { "T" }),
+            Prop::False => View::Leaf(if unicode { "⊥" } else { "F" }),
+            Prop::Implies(a, b) if self.options.negation && **b == Prop::False => View::Not(a),
+            Prop::Not(a) => View::Not(a),
+            Prop::Implies(a, b) => View::Binary(Op::Implies, a, b),
+            Prop::And(a, b) => View::Binary(Op::And, a, b),
+            Prop::Or(a, b) => View::Binary(Op::Or, a, b),
+            Prop::Iff(a, b) => View::Binary(Op::Iff, a, b),
+            Prop::Pred(p, args) => View::Pred(p, args),
+            Prop::Forall(x, a) => View::Quantifier(true, x, a),
+            Prop::Exists(x, a) => View::Quantifier(false, x, a),
+        }
+    }
+
+    fn symbol(&self, op: Op) -> &'static str {
+        match (op, self.options.unicode) {
+            (Op::Iff, false) => "<=>",
+            (Op::Iff, true) => "↔",
+            (Op::Implies, false) => "=>",
+            (Op::Implies, true) => "→",
+            (Op::Or, false) => "|",
+            (Op::Or, true) => "∨",
+            (Op::And, false) => "^",
+            (Op::And, true) => "∧",
+        }
+    }
+
+    fn not_symbol(&self) -> &'static str {
+        if self.options.unicode { "¬" } else { "!" }
+    }
+
+    fn quantifier(&self, universal: bool, x: &str) -> String {
+        match (universal, self.options.unicode) {
+            (true, false) => format!("forall {}. ", x),
+            (true, true) => format!("∀{}. ", x),
+            (false, false) => format!("exists {}. ", x),
+            (false, true) => format!("∃{}. ", x),
+        }
+    }
+
+    // an operand of `op` needs parentheses when it binds more loosely, or as
+    // tightly but on the side the parser would not group it
+    fn needs_parens(&self, child: &Prop, op: Op, right: bool) -> bool {
+        let precedence = self.view(child).precedence();
+        precedence < op.precedence() || (precedence == op.precedence() && right != op.right_assoc())
+    }
+
+    fn flat(&self, prop: &Prop) -> String {
+        match self.view(prop) {
+            View::Leaf(symbol) => String::from(symbol),
+            View::Name(name) => String::from(name),
+            View::Pred(p, args) => format!("{}({})", p, fol::join_terms(args)),
+            View::Quantifier(universal, x, a) => {
+                format!("{}{}", self.quantifier(universal, x), self.flat(a))
+            }
+            View::Not(a) => {
+                let inner = self.flat(a);
+                if self.view(a).precedence() < NOT_PRECEDENCE {
+                    format!("{}({})", self.not_symbol(), inner)
+                } else {
+                    format!("{}{}", self.not_symbol(), inner)
+                }
+            }
+            View::Binary(op, a, b) => {
+                let side = |child: &Prop, right: bool| {
+                    let inner = self.flat(child);
+                    if self.needs_parens(child, op, right) {
+                        format!("({})", inner)
+                    } else {
+                        inner
+                    }
+                };
+                format!("{} {} {}", side(a, false), self.symbol(op), side(b, true))
+            }
+        }
+    }
+
+    // the operands of a chain of the same connective, as in a ^ b ^ c,
+    // along with whether each one needs parentheses
+    fn chain<'a>(&self, op: Op, a: &'a PropRef, b: &'a PropRef) -> Vec<(&'a PropRef, bool)> {
+        let continued = if op.right_assoc() { b } else { a };
+        let mut operands = match self.view(continued) {
+            View::Binary(inner, c, d) if inner == op => self.chain(op, c, d),
+            _ => vec![(
+                continued,
+                self.needs_parens(continued, op, op.right_assoc()),
+            )],
+        };
+        if op.right_assoc() {
+            operands.insert(0, (a, self.needs_parens(a, op, false)));
+        } else {
+            operands.push((b, self.needs_parens(b, op, true)));
+        }
+        operands
+    }
+
+    fn lines(&self, prop: &Prop, width: usize) -> Vec<String> {
+        let flat = self.flat(prop);
+        if flat.chars().count() <= width {
+            return vec![flat];
+        }
+        match self.view(prop) {
+            View::Leaf(_) | View::Name(_) | View::Pred(_, _) => vec![flat],
+            View::Quantifier(universal, x, a) => {
+                let lead = self.quantifier(universal, x);
+                let pad = lead.chars().count();
+                indent(&lead, pad, self.lines(a, width.saturating_sub(pad)))
+            }
+            View::Not(a) => {
+                let symbol = self.not_symbol();
+                let parens = self.view(a).precedence() < NOT_PRECEDENCE;
+                let inner = wrap(self.lines(a, width.saturating_sub(3)), parens);
+                indent(symbol, 1, inner)
+            }
+            // one operand per line, each but the first after its connective
+            View::Binary(op, a, b) => {
+                let symbol = self.symbol(op);
+                let pad = symbol.chars().count() + 1;
+                let mut result = vec![];
+                for (index, (operand, parens)) in self.chain(op, a, b).into_iter().enumerate() {
+                    let inner = wrap(self.lines(operand, width.saturating_sub(pad + 2)), parens);
+                    let lead = if index == 0 {
+                        String::from(" ").repeat(pad)
+                    } else {
+                        format!("{} ", symbol)
+                    };
+                    result.extend(indent(&lead, pad, inner));
+                }
+                result
+            }
+        }
+    }
+}
+
+fn wrap(mut lines: Vec<String>, parens: bool) -> Vec<String> {
+    if parens {
+        for (index, line) in lines.iter_mut().enumerate() {
+            *line = format!("{}{}", if index == 0 { "(" } else { " " }, line);
+        }
+        if let Some(last) = lines.last_mut() {
+            last.push(')');
+        }
+    }
+    lines
+}
+
+// the first line after `lead`, the others after `pad` spaces
+fn indent(lead: &str, pad: usize, lines: Vec<String>) -> Vec<String> {
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(index, line)| {
+            if index == 0 {
+                format!("{}{}", lead, line)
+            } else {
+                format!("{}{}", " ".repeat(pad), line)
+            }
+        })
+        .collect()
+}
+
+impl Prop {
+    pub fn pretty(&self, options: &PrettyOptions) -> String {
+        let printer = Printer { options };
+        match options.width {
+            None => printer.flat(self),
+            Some(width) => printer.lines(self, width).join("\n"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prop(s: &str) -> Prop {
+        Prop::parse_infix(s).unwrap()
+    }
+
+    const ASCII: PrettyOptions = PrettyOptions {
+        unicode: false,
+        negation: false,
+        width: None,
+    };
+
+    #[test]
+    fn minimal_parentheses() {
+        for (input, expected) in [
+            ("a => b => c", "a => b => c"),
+            ("(a => b) => c", "(a => b) => c"),
+            ("a ^ b ^ c", "a ^ b ^ c"),
+            ("a ^ (b ^ c)", "a ^ (b ^ c)"),
+            ("a | b ^ c", "a | b ^ c"),
+            ("(a | b) ^ c", "(a | b) ^ c"),
+            ("!(a ^ b) | !!c", "!(a ^ b) | !!c"),
+            ("(a <=> b) <=> c", "(a <=> b) <=> c"),
+            ("a <=> b => c | T", "a <=> b => c | T"),
+            ("a => F", "a => F"),
+        ] {
+            let printed = prop(input).pretty(&ASCII);
+            assert_eq!(printed, expected, "{}", input);
+            assert_eq!(prop(&printed), prop(input), "{}", input);
+        }
+    }
+
+    #[test]
+    fn unicode_and_negation() {
+        let options = PrettyOptions {
+            unicode: true,
+            ..PrettyOptions::default()
+        };
+        assert_eq!(
+            prop("(a => F) ^ (b <=> T) | !c => F").pretty(&options),
+            "¬(¬a ∧ (b ↔ ⊤) ∨ ¬c)"
+        );
+        assert_eq!(
+            prop("(a => F) => b").pretty(&PrettyOptions::default()),
+            "!a => b"
+        );
+    }
+
+    #[test]
+    fn quantifiers() {
+        for (input, expected) in [
+            (
+                "forall x. P(x) => Q(x, f(y))",
+                "forall x. P(x) => Q(x, f(y))",
+            ),
+            ("(forall x. P(x)) => Q", "(forall x. P(x)) => Q"),
+            ("a ^ (exists x. P(x)) ^ b", "a ^ (exists x. P(x)) ^ b"),
+            ("!(forall x y. R(x, y))", "!(forall x. forall y. R(x, y))"),
+        ] {
+            let printed = prop(input).pretty(&ASCII);
+            assert_eq!(printed, expected, "{}", input);
+            assert_eq!(prop(&printed), prop(input), "{}", input);
+        }
+        let options = PrettyOptions {
+            unicode: true,
+            width: Some(16),
+            ..PrettyOptions::default()
+        };
+        assert_eq!(
+            prop("forall x. exists y. R(x, y) ^ S(y)").pretty(&options),
+            "∀x. ∃y.   R(x, y)\n        ∧ S(y)"
+        );
+    }
+
+    #[test]
+    fn line_breaking() {
+        let long = prop("(alpha | beta) ^ (gamma => delta) ^ !(epsilon | zeta)");
+        let options = |width| PrettyOptions {
+            width: Some(width),
+            ..PrettyOptions::default()
+        };
+        assert_eq!(
+            long.pretty(&options(80)),
+            "(alpha | beta) ^ (gamma => delta) ^ !(epsilon | zeta)"
+        );
+        assert_eq!(
+            long.pretty(&options(30)),
+            "  (alpha | beta)\n^ (gamma => delta)\n^ !(epsilon | zeta)"
+        );
+        assert_eq!(
+            long.pretty(&options(14)),
+            "  (  alpha\n   | beta)\n^ (   gamma\n   => delta)\n^ !(  epsilon\n    | zeta)"
+        );
+        let implications = prop("alpha => beta => gamma");
+        assert_eq!(
+            implications.pretty(&options(10)),
+            "   alpha\n=> beta\n=> gamma"
+        );
+    }
+}
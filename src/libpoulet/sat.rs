@@ -0,0 +1,789 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    error::Error,
+    fmt,
+};
+
+use crate::libpoulet::fol;
+use crate::libpoulet::intern::PropRef;
+use crate::libpoulet::logic::{Prop, Valuation};
+
+// Clauses use the DIMACS convention: variables are numbered from 1, the
+// literal v is the variable and -v its negation.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Cnf {
+    pub num_vars: usize,
+    pub clauses: Vec<Vec<i32>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DimacsError {
+    MissingHeader,
+    BadHeader { line: usize },
+    BadLiteral { line: usize, token: String },
+    VariableOutOfRange { line: usize, var: usize },
+    // a 'c atom' line naming a variable that is not declared
+    AtomOutOfRange { line: usize, var: i32 },
+    DuplicateAtomVar { line: usize, var: i32 },
+    DuplicateAtomName { line: usize, name: String },
+    UnterminatedClause,
+    ClauseCountMismatch { expected: usize, found: usize },
+}
+
+impl fmt::Display for DimacsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DimacsError::MissingHeader => write!(f, "missing 'p cnf <vars> <clauses>' line"),
+            DimacsError::BadHeader { line } => write!(f, "line {}: malformed header", line),
+            DimacsError::BadLiteral { line, token } => {
+                write!(f, "line {}: '{}' is not a literal", line, token)
+            }
+            DimacsError::VariableOutOfRange { line, var } => write!(
+                f,
+                "line {}: variable {} is beyond the declared count",
+                line, var
+            ),
+            DimacsError::AtomOutOfRange { line, var } => write!(
+                f,
+                "line {}: atom given to {}, which is not a declared variable",
+                line, var
+            ),
+            DimacsError::DuplicateAtomVar { line, var } => {
+                write!(f, "line {}: variable {} already has an atom", line, var)
+            }
+            DimacsError::DuplicateAtomName { line, name } => {
+                write!(
+                    f,
+                    "line {}: atom {} is already given to a variable",
+                    line, name
+                )
+            }
+            DimacsError::UnterminatedClause => write!(f, "last clause is not ended by 0"),
+            DimacsError::ClauseCountMismatch { expected, found } => {
+                write!(f, "header declares {} clauses, found {}", expected, found)
+            }
+        }
+    }
+}
+
+impl Error for DimacsError {}
+
+impl Cnf {
+    pub fn fresh(&mut self) -> i32 {
+        self.num_vars += 1;
+        self.num_vars as i32
+    }
+
+    // atom names are kept in comment lines 'c atom <var> <name>'
+    pub fn to_dimacs(&self, atoms: &BTreeMap<String, i32>) -> String {
+        let mut out = String::new();
+        for (atom, v) in atoms.iter() {
+            out.push_str(&format!("c atom {} {}\n", v, atom));
+        }
+        out.push_str(&format!("p cnf {} {}\n", self.num_vars, self.clauses.len()));
+        for clause in self.clauses.iter() {
+            for lit in clause.iter() {
+                out.push_str(&format!("{} ", lit));
+            }
+            out.push_str("0\n");
+        }
+        out
+    }
+
+    pub fn parse_dimacs(input: &str) -> Result<(Cnf, BTreeMap<String, i32>), DimacsError> {
+        // checked once the header gives the number of variables
+        let mut atom_lines = vec![];
+        let mut header: Option<(usize, usize)> = None;
+        let mut cnf = Cnf::default();
+        let mut clause = vec![];
+        for (index, line) in input.lines().enumerate() {
+            let line_num = index + 1;
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                None => continue,
+                Some("c") => {
+                    if let (Some("atom"), Some(v), Some(name)) =
+                        (tokens.next(), tokens.next(), tokens.next())
+                        && let Ok(v) = v.parse::<i32>()
+                    {
+                        atom_lines.push((line_num, v, name));
+                    }
+                    continue;
+                }
+                // end marker of the SATLIB benchmarks
+                Some("%") => break,
+                Some("p") => {
+                    let counts = match (tokens.next(), tokens.next(), tokens.next()) {
+                        (Some("cnf"), Some(vars), Some(clauses)) => vars
+                            .parse::<usize>()
+                            .ok()
+                            .zip(clauses.parse::<usize>().ok()),
+                        _ => None,
+                    };
+                    match counts {
+                        Some(counts) if header.is_none() => header = Some(counts),
+                        _ => return Err(DimacsError::BadHeader { line: line_num }),
+                    }
+                    cnf.num_vars = header.unwrap().0;
+                    continue;
+                }
+                Some(_) => (),
+            }
+            if header.is_none() {
+                return Err(DimacsError::MissingHeader);
+            }
+            for token in line.split_whitespace() {
+                let lit = token.parse::<i32>().map_err(|_| DimacsError::BadLiteral {
+                    line: line_num,
+                    token: String::from(token),
+                })?;
+                if lit == 0 {
+                    cnf.clauses.push(std::mem::take(&mut clause));
+                } else if var(lit) > cnf.num_vars {
+                    return Err(DimacsError::VariableOutOfRange {
+                        line: line_num,
+                        var: var(lit),
+                    });
+                } else {
+                    clause.push(lit);
+                }
+            }
+        }
+        let Some((_, expected)) = header else {
+            return Err(DimacsError::MissingHeader);
+        };
+        if !clause.is_empty() {
+            return Err(DimacsError::UnterminatedClause);
+        }
+        if cnf.clauses.len() != expected {
+            return Err(DimacsError::ClauseCountMismatch {
+                expected,
+                found: cnf.clauses.len(),
+            });
+        }
+        let mut atoms = BTreeMap::new();
+        let mut named = BTreeSet::new();
+        for (line, var, name) in atom_lines {
+            if var <= 0 || var as usize > cnf.num_vars {
+                return Err(DimacsError::AtomOutOfRange { line, var });
+            }
+            if !named.insert(var) {
+                return Err(DimacsError::DuplicateAtomVar { line, var });
+            }
+            if atoms.insert(String::from(name), var).is_some() {
+                return Err(DimacsError::DuplicateAtomName {
+                    line,
+                    name: String::from(name),
+                });
+            }
+        }
+        Ok((cnf, atoms))
+    }
+
+    // conjunction of the clauses, variables without a name becoming x<var>
+    // with primes added until it is not one of the atoms
+    pub fn to_prop(&self, atoms: &BTreeMap<String, i32>) -> Prop {
+        let names: BTreeMap<usize, &String> =
+            atoms.iter().map(|(atom, v)| (var(*v), atom)).collect();
+        let taken: BTreeSet<String> = atoms.keys().cloned().collect();
+        let literal = |lit: &i32| {
+            let atom = match names.get(&var(*lit)) {
+                Some(name) => Prop::Name((*name).clone()),
+                None => Prop::Name(fol::fresh_var(&format!("x{}", var(*lit)), &taken)),
+            };
+            if *lit > 0 { atom } else { Prop::not(atom) }
+        };
+        let clauses = self.clauses.iter().map(|clause| {
+            clause
+                .iter()
+                .map(literal)
+                .reduce(Prop::or)
+                .unwrap_or(Prop::False)
+        });
+        clauses.reduce(Prop::and).unwrap_or(Prop::True)
+    }
+}
+
+fn var(lit: i32) -> usize {
+    lit.unsigned_abs() as usize
+}
+
+// position of a literal in the watch lists
+fn watch_index(lit: i32) -> usize {
+    2 * var(lit) + (lit < 0) as usize
+}
+
+fn lit_value(values: &[Option<bool>], lit: i32) -> Option<bool> {
+    values[var(lit)].map(|value| value == (lit > 0))
+}
+
+// 1, 1, 2, 1, 1, 2, 4, 1, 1, 2, ... scaled to get the restart intervals
+fn luby(mut i: u64) -> u64 {
+    let mut size = 1;
+    while size < i + 1 {
+        size = 2 * size + 1;
+    }
+    while size - 1 != i {
+        size = (size - 1) / 2;
+        i %= size;
+    }
+    size.div_ceil(2)
+}
+
+const RESTART_BASE: u64 = 64;
+const ACTIVITY_DECAY: f64 = 0.95;
+
+// conflict-driven clause learning with two watched literals per clause,
+// first-UIP learning, activity-based branching with saved phases, and
+// restarts following the Luby sequence
+struct Solver {
+    clauses: Vec<Vec<i32>>,
+    watches: Vec<Vec<usize>>,
+    values: Vec<Option<bool>>,
+    levels: Vec<usize>,
+    reasons: Vec<Option<usize>>,
+    trail: Vec<i32>,
+    // start of each decision level in the trail
+    trail_lim: Vec<usize>,
+    head: usize,
+    activity: Vec<f64>,
+    bump: f64,
+    phase: Vec<bool>,
+}
+
+impl Solver {
+    fn new(num_vars: usize) -> Solver {
+        Solver {
+            clauses: vec![],
+            watches: vec![vec![]; 2 * num_vars + 2],
+            values: vec![None; num_vars + 1],
+            levels: vec![0; num_vars + 1],
+            reasons: vec![None; num_vars + 1],
+            trail: vec![],
+            trail_lim: vec![],
+            head: 0,
+            activity: vec![0.0; num_vars + 1],
+            bump: 1.0,
+            phase: vec![false; num_vars + 1],
+        }
+    }
+
+    fn assign(&mut self, lit: i32, reason: Option<usize>) {
+        self.values[var(lit)] = Some(lit > 0);
+        self.levels[var(lit)] = self.trail_lim.len();
+        self.reasons[var(lit)] = reason;
+        self.trail.push(lit);
+    }
+
+    // the first two literals of a clause are the watched ones
+    fn attach(&mut self, clause: Vec<i32>) -> usize {
+        let index = self.clauses.len();
+        self.watches[watch_index(clause[0])].push(index);
+        self.watches[watch_index(clause[1])].push(index);
+        self.clauses.push(clause);
+        index
+    }
+
+    // only called before the search starts, false if the clauses are
+    // already contradictory
+    fn add_clause(&mut self, clause: &[i32]) -> bool {
+        let mut clause = clause.to_vec();
+        clause.sort();
+        clause.dedup();
+        if clause.iter().any(|lit| clause.contains(&-lit))
+            || clause
+                .iter()
+                .any(|lit| lit_value(&self.values, *lit) == Some(true))
+        {
+            return true;
+        }
+        clause.retain(|lit| lit_value(&self.values, *lit).is_none());
+        match clause.len() {
+            0 => false,
+            1 => {
+                self.assign(clause[0], None);
+                self.propagate().is_none()
+            }
+            _ => {
+                self.attach(clause);
+                true
+            }
+        }
+    }
+
+    // unit propagation, returns a clause with every literal false if any
+    fn propagate(&mut self) -> Option<usize> {
+        while self.head < self.trail.len() {
+            let false_lit = -self.trail[self.head];
+            self.head += 1;
+            let mut watching = std::mem::take(&mut self.watches[watch_index(false_lit)]);
+            let mut i = 0;
+            while i < watching.len() {
+                let index = watching[i];
+                let clause = &mut self.clauses[index];
+                if clause[0] == false_lit {
+                    clause.swap(0, 1);
+                }
+                let first = clause[0];
+                if lit_value(&self.values, first) == Some(true) {
+                    i += 1;
+                    continue;
+                }
+                let replacement =
+                    (2..clause.len()).find(|k| lit_value(&self.values, clause[*k]) != Some(false));
+                if let Some(k) = replacement {
+                    clause.swap(1, k);
+                    self.watches[watch_index(clause[1])].push(index);
+                    watching.swap_remove(i);
+                } else if lit_value(&self.values, first) == Some(false) {
+                    self.watches[watch_index(false_lit)] = watching;
+                    self.head = self.trail.len();
+                    return Some(index);
+                } else {
+                    self.assign(first, Some(index));
+                    i += 1;
+                }
+            }
+            self.watches[watch_index(false_lit)] = watching;
+        }
+        None
+    }
+
+    fn bump_activity(&mut self, v: usize) {
+        self.activity[v] += self.bump;
+        if self.activity[v] > 1e100 {
+            for activity in self.activity.iter_mut() {
+                *activity *= 1e-100;
+            }
+            self.bump *= 1e-100;
+        }
+    }
+
+    // learns the first-UIP clause of a conflict, its first literal being
+    // the one asserted after backjumping to the returned level
+    fn analyze(&mut self, conflict: usize) -> (Vec<i32>, usize) {
+        let level = self.trail_lim.len();
+        let mut seen = vec![false; self.values.len()];
+        let mut learnt = vec![0];
+        let mut pending = 0;
+        let mut index = self.trail.len();
+        let mut clause = conflict;
+        let mut implied: Option<i32> = None;
+        loop {
+            for lit in self.clauses[clause].clone() {
+                let v = var(lit);
+                if Some(lit) == implied || seen[v] || self.levels[v] == 0 {
+                    continue;
+                }
+                seen[v] = true;
+                self.bump_activity(v);
+                if self.levels[v] == level {
+                    pending += 1;
+                } else {
+                    learnt.push(lit);
+                }
+            }
+            // latest literal of the current level involved in the conflict
+            loop {
+                index -= 1;
+                if seen[var(self.trail[index])] {
+                    break;
+                }
+            }
+            let lit = self.trail[index];
+            seen[var(lit)] = false;
+            pending -= 1;
+            if pending == 0 {
+                learnt[0] = -lit;
+                break;
+            }
+            implied = Some(lit);
+            clause = self.reasons[var(lit)].expect("only decisions have no reason");
+        }
+
+        let mut backjump = 0;
+        if learnt.len() > 1 {
+            let (k, _) = learnt
+                .iter()
+                .enumerate()
+                .skip(1)
+                .max_by_key(|(_, lit)| self.levels[var(**lit)])
+                .unwrap();
+            learnt.swap(1, k);
+            backjump = self.levels[var(learnt[1])];
+        }
+        (learnt, backjump)
+    }
+
+    fn backtrack(&mut self, level: usize) {
+        if self.trail_lim.len() <= level {
+            return;
+        }
+        for lit in self.trail.drain(self.trail_lim[level]..) {
+            self.values[var(lit)] = None;
+            self.reasons[var(lit)] = None;
+            self.phase[var(lit)] = lit > 0;
+        }
+        self.trail_lim.truncate(level);
+        self.head = self.trail.len();
+    }
+
+    fn pick_branch(&self) -> Option<i32> {
+        let v = (1..self.values.len())
+            .filter(|v| self.values[*v].is_none())
+            .max_by(|a, b| self.activity[*a].total_cmp(&self.activity[*b]))?;
+        Some(if self.phase[v] { v as i32 } else { -(v as i32) })
+    }
+
+    fn search(&mut self) -> Option<Vec<bool>> {
+        let mut restarts = 0;
+        let mut conflicts = 0;
+        loop {
+            if let Some(conflict) = self.propagate() {
+                if self.trail_lim.is_empty() {
+                    return None;
+                }
+                conflicts += 1;
+                let (learnt, backjump) = self.analyze(conflict);
+                self.backtrack(backjump);
+                if learnt.len() == 1 {
+                    self.assign(learnt[0], None);
+                } else {
+                    let asserted = learnt[0];
+                    let index = self.attach(learnt);
+                    self.assign(asserted, Some(index));
+                }
+                self.bump /= ACTIVITY_DECAY;
+            } else if conflicts >= RESTART_BASE * luby(restarts) {
+                restarts += 1;
+                conflicts = 0;
+                self.backtrack(0);
+            } else {
+                match self.pick_branch() {
+                    Some(lit) => {
+                        self.trail_lim.push(self.trail.len());
+                        self.assign(lit, None);
+                    }
+                    None => {
+                        return Some(self.values[1..].iter().map(|v| v.unwrap()).collect());
+                    }
+                }
+            }
+        }
+    }
+}
+
+// a model gives the value of variable v at index v - 1, None means UNSAT
+pub fn solve(cnf: &Cnf) -> Option<Vec<bool>> {
+    let mut solver = Solver::new(cnf.num_vars);
+    for clause in cnf.clauses.iter() {
+        if !solver.add_clause(clause) {
+            return None;
+        }
+    }
+    solver.search()
+}
+
+struct Tseitin {
+    cnf: Cnf,
+    atoms: BTreeMap<PropRef, i32>,
+    // literals already standing for a subformula
+    cache: HashMap<Prop, i32>,
+}
+
+impl Tseitin {
+    fn encode(&mut self, prop: &Prop) -> i32 {
+        if let Some(lit) = self.cache.get(prop) {
+            return *lit;
+        }
+        let lit = match prop {
+            Prop::Name(_) | Prop::Pred(_, _) | Prop::Forall(_, _) | Prop::Exists(_, _) => {
+                let lit = self.cnf.fresh();
+                self.atoms.insert(PropRef::new(prop.clone()), lit);
+                lit
+            }
+            Prop::True => {
+                let lit = self.cnf.fresh();
+                self.cnf.clauses.push(vec![lit]);
+                lit
+            }
+            Prop::False => -self.encode(&Prop::True),
+            Prop::Implies(a, b) if **b == Prop::False => -self.encode(a),
+            Prop::Not(a) => -self.encode(a),
+            Prop::Iff(a, b) => {
+                let (a, b) = (self.encode(a), self.encode(b));
+                let x = self.cnf.fresh();
+                self.cnf.clauses.extend([
+                    vec![-x, -a, b],
+                    vec![-x, a, -b],
+                    vec![x, a, b],
+                    vec![x, -a, -b],
+                ]);
+                x
+            }
+            Prop::And(a, b) | Prop::Or(a, b) | Prop::Implies(a, b) => {
+                let (a, b) = (self.encode(a), self.encode(b));
+                let x = self.cnf.fresh();
+                let clauses = match prop {
+                    Prop::And(_, _) => [vec![-x, a], vec![-x, b], vec![x, -a, -b]],
+                    Prop::Or(_, _) => [vec![-x, a, b], vec![x, -a], vec![x, -b]],
+                    _ => [vec![-x, -a, b], vec![x, a], vec![x, -b]],
+                };
+                self.cnf.clauses.extend(clauses);
+                x
+            }
+        };
+        self.cache.insert(prop.clone(), lit);
+        lit
+    }
+}
+
+// clauses satisfiable exactly when the proposition is, along with the
+// variable standing for each atom
+pub fn tseitin(prop: &Prop) -> (Cnf, BTreeMap<PropRef, i32>) {
+    let mut encoding = Tseitin {
+        cnf: Cnf::default(),
+        atoms: BTreeMap::new(),
+        cache: HashMap::new(),
+    };
+    let root = encoding.encode(prop);
+    encoding.cnf.clauses.push(vec![root]);
+    (encoding.cnf, encoding.atoms)
+}
+
+pub fn satisfying_valuation(prop: &Prop) -> Option<Valuation> {
+    let (cnf, atoms) = tseitin(prop);
+    let model = solve(&cnf)?;
+    Some(
+        atoms
+            .into_iter()
+            .map(|(atom, v)| (atom.atom_name().unwrap(), model[var(v) - 1]))
+            .collect(),
+    )
+}
+
+// the hypotheses along with the negated goal, satisfiable exactly when the
+// goal is not a classical consequence of the hypotheses
+pub fn refutation(hyps: &[PropRef], goal: &Prop) -> Prop {
+    hyps.iter().rev().fold(Prop::not(goal.clone()), |acc, hyp| {
+        Prop::And(hyp.clone(), PropRef::new(acc))
+    })
+}
+
+// a valuation making every hypothesis true and the goal false, if any
+pub fn classical_counterexample(hyps: &[PropRef], goal: &Prop) -> Option<Valuation> {
+    satisfying_valuation(&refutation(hyps, goal))
+}
+
+// intuitionistic validity implies classical validity, so a sequent for
+// which this is false can never be proven
+pub fn classically_valid(hyps: &[PropRef], goal: &Prop) -> bool {
+    classical_counterexample(hyps, goal).is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cnf(num_vars: usize, clauses: &[&[i32]]) -> Cnf {
+        Cnf {
+            num_vars,
+            clauses: clauses.iter().map(|clause| clause.to_vec()).collect(),
+        }
+    }
+
+    fn satisfies(model: &[bool], cnf: &Cnf) -> bool {
+        cnf.clauses
+            .iter()
+            .all(|clause| clause.iter().any(|lit| model[var(*lit) - 1] == (*lit > 0)))
+    }
+
+    fn brute_force(cnf: &Cnf) -> bool {
+        (0..1u32 << cnf.num_vars).any(|bits| {
+            let model: Vec<bool> = (0..cnf.num_vars).map(|v| bits >> v & 1 == 1).collect();
+            satisfies(&model, cnf)
+        })
+    }
+
+    #[test]
+    fn small() {
+        assert_eq!(solve(&cnf(0, &[])), Some(vec![]));
+        assert_eq!(solve(&cnf(1, &[&[]])), None);
+        assert_eq!(solve(&cnf(1, &[&[1], &[-1]])), None);
+        assert_eq!(
+            solve(&cnf(2, &[&[1, 2], &[-1], &[1, -1]])),
+            Some(vec![false, true])
+        );
+        let unsat = cnf(2, &[&[1, 2], &[-1, 2], &[1, -2], &[-1, -2]]);
+        assert_eq!(solve(&unsat), None);
+        assert_eq!(
+            luby_prefix(),
+            vec![1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8]
+        );
+    }
+
+    fn luby_prefix() -> Vec<u64> {
+        (0..15).map(luby).collect()
+    }
+
+    #[test]
+    fn pigeonhole() {
+        // n + 1 pigeons in n holes, hard enough to need learning and restarts
+        let n = 6;
+        let p = |pigeon: usize, hole: usize| (pigeon * n + hole + 1) as i32;
+        let mut problem = Cnf {
+            num_vars: (n + 1) * n,
+            clauses: vec![],
+        };
+        for pigeon in 0..=n {
+            problem
+                .clauses
+                .push((0..n).map(|hole| p(pigeon, hole)).collect());
+        }
+        for hole in 0..n {
+            for a in 0..=n {
+                for b in a + 1..=n {
+                    problem.clauses.push(vec![-p(a, hole), -p(b, hole)]);
+                }
+            }
+        }
+        assert_eq!(solve(&problem), None);
+        problem
+            .clauses
+            .retain(|clause| clause != &(0..n).map(|hole| p(n, hole)).collect::<Vec<i32>>());
+        assert!(satisfies(&solve(&problem).unwrap(), &problem));
+    }
+
+    #[test]
+    fn random_3sat() {
+        let mut seed: u64 = 42;
+        let mut next = |bound: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % bound
+        };
+        for _ in 0..200 {
+            let num_vars = 8;
+            let clauses: Vec<Vec<i32>> = (0..34)
+                .map(|_| {
+                    (0..3)
+                        .map(|_| {
+                            let v = next(num_vars) as i32 + 1;
+                            if next(2) == 0 { v } else { -v }
+                        })
+                        .collect()
+                })
+                .collect();
+            let problem = Cnf {
+                num_vars: num_vars as usize,
+                clauses,
+            };
+            match solve(&problem) {
+                Some(model) => assert!(satisfies(&model, &problem)),
+                None => assert!(!brute_force(&problem)),
+            }
+        }
+    }
+
+    #[test]
+    fn classical_checks() {
+        for statement in [
+            "a | !a",
+            "((a => b) => a) => a",
+            "!!a => a",
+            "(a ^ T) | F => a",
+        ] {
+            let prop = Prop::parse_infix(statement).unwrap();
+            assert!(classically_valid(&[], &prop), "{}", statement);
+            assert_eq!(satisfying_valuation(&Prop::not(prop)), None);
+        }
+        let goal = Prop::parse_infix("a ^ b").unwrap();
+        let hyps = vec![PropRef::new(Prop::parse_infix("a | c").unwrap())];
+        let valuation = classical_counterexample(&hyps, &goal).unwrap();
+        assert!(hyps[0].eval(&valuation) && !goal.eval(&valuation));
+        assert!(classically_valid(&[PropRef::new(Prop::False)], &goal));
+        let contraposition = Prop::parse_infix("(a <=> b) <=> (!a <=> !b)").unwrap();
+        assert!(classically_valid(&[], &contraposition));
+        assert!(!classically_valid(
+            &[],
+            &Prop::parse_infix("a <=> !a | b").unwrap()
+        ));
+
+        let (cnf, atoms) = tseitin(&Prop::parse_infix("(a ^ b) | (a ^ b)").unwrap());
+        // shared subformulas are encoded once
+        assert_eq!(cnf.num_vars, 4);
+        assert_eq!(atoms.len(), 2);
+    }
+
+    #[test]
+    fn dimacs() {
+        let input = "c a comment\nc atom 2 b\np cnf 3 3\n1 -2\n 0 3\n0\n-1 -3 0\n%\n0\n";
+        let (cnf, atoms) = Cnf::parse_dimacs(input).unwrap();
+        assert_eq!(cnf.num_vars, 3);
+        assert_eq!(cnf.clauses, vec![vec![1, -2], vec![3], vec![-1, -3]]);
+        assert_eq!(atoms, BTreeMap::from([(String::from("b"), 2)]));
+        assert_eq!(
+            cnf.to_prop(&atoms).to_string(),
+            "( ( ( x1 | ! b ) ^ x3 ) ^ ( ! x1 | ! x3 ) )"
+        );
+        let output = cnf.to_dimacs(&atoms);
+        assert_eq!(output, "c atom 2 b\np cnf 3 3\n1 -2 0\n3 0\n-1 -3 0\n");
+        assert_eq!(Cnf::parse_dimacs(&output), Ok((cnf, atoms)));
+
+        // an unnamed variable does not take the name of another one
+        let (clash, atoms) = Cnf::parse_dimacs("c atom 1 x2\np cnf 2 1\n1 -2 0\n").unwrap();
+        assert_eq!(clash.to_prop(&atoms).to_string(), "( x2 | ! x2' )");
+
+        let (empty, _) = Cnf::parse_dimacs("p cnf 0 1\n0\n").unwrap();
+        assert_eq!(empty.to_prop(&BTreeMap::new()), Prop::False);
+        assert_eq!(solve(&empty), None);
+
+        for (input, err) in [
+            ("1 2 0\n", DimacsError::MissingHeader),
+            ("", DimacsError::MissingHeader),
+            ("p cnf 2\n", DimacsError::BadHeader { line: 1 }),
+            ("p cnf 1 1\np cnf 1 1\n", DimacsError::BadHeader { line: 2 }),
+            (
+                "p cnf 2 1\n1 a 0\n",
+                DimacsError::BadLiteral {
+                    line: 2,
+                    token: String::from("a"),
+                },
+            ),
+            (
+                "p cnf 2 1\n\n1 -3 0\n",
+                DimacsError::VariableOutOfRange { line: 3, var: 3 },
+            ),
+            ("p cnf 2 1\n1 2\n", DimacsError::UnterminatedClause),
+            (
+                "c atom 0 a\np cnf 2 1\n1 2 0\n",
+                DimacsError::AtomOutOfRange { line: 1, var: 0 },
+            ),
+            (
+                "p cnf 2 1\nc atom 3 a\n1 2 0\n",
+                DimacsError::AtomOutOfRange { line: 2, var: 3 },
+            ),
+            (
+                "c atom 1 a\nc atom 1 b\np cnf 2 1\n1 2 0\n",
+                DimacsError::DuplicateAtomVar { line: 2, var: 1 },
+            ),
+            (
+                "c atom 1 a\nc atom 2 a\np cnf 2 1\n1 2 0\n",
+                DimacsError::DuplicateAtomName {
+                    line: 2,
+                    name: String::from("a"),
+                },
+            ),
+            (
+                "p cnf 2 2\n1 2 0\n",
+                DimacsError::ClauseCountMismatch {
+                    expected: 2,
+                    found: 1,
+                },
+            ),
+        ] {
+            assert_eq!(Cnf::parse_dimacs(input), Err(err), "{}", input);
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use std::fmt;
+
+use crate::libpoulet::backtrack;
+use crate::libpoulet::kernel;
+use crate::libpoulet::logic;
+use crate::libpoulet::strategies::Proof;
+use crate::libpoulet::tactics;
+
+// A script is a list of theorems, each proven by a block of tactics, one
+// per line, written as they would be typed in the REPL:
+//
+//   Theorem and_comm: a ^ b => b ^ a
+//   Proof.
+//     intro h; hyp_split h
+//     split; first [exact H0 | exact h]
+//   Qed.
+//
+// Empty lines and lines starting with '#' are ignored. Besides the tactics,
+// a line can be `logic <name>` to change the logic, or `auto [mode]` with
+// the modes of the REPL: the steps found are replayed as if they had been
+// typed, so the kernel still checks the resulting terms.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScriptError {
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TheoremResult {
+    pub name: String,
+    pub line: usize,
+    pub result: Result<(), ScriptError>,
+}
+
+struct TheoremBlock<'a> {
+    name: &'a str,
+    line: usize,
+    statement: &'a str,
+    steps: Vec<(usize, &'a str)>,
+    qed: usize,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+fn error(line: usize, message: String) -> ScriptError {
+    ScriptError { line, message }
+}
+
+// the theorem blocks of the script, any line outside of them being an error
+fn blocks(input: &str) -> Result<Vec<TheoremBlock<'_>>, ScriptError> {
+    let mut lines = input
+        .lines()
+        .enumerate()
+        .map(|(index, l)| (index + 1, l.trim()))
+        .filter(|(_, l)| !l.is_empty() && !l.starts_with('#'));
+    let mut blocks = vec![];
+    while let Some((line, l)) = lines.next() {
+        let Some((name, statement)) = l
+            .strip_prefix("Theorem ")
+            .and_then(|theorem| theorem.split_once(':'))
+        else {
+            return Err(error(
+                line,
+                String::from("expected 'Theorem <name>: <statement>'"),
+            ));
+        };
+        match lines.next() {
+            Some((_, "Proof.")) => (),
+            Some((line, _)) => return Err(error(line, String::from("expected 'Proof.'"))),
+            None => return Err(error(line, String::from("missing 'Proof.'"))),
+        }
+        let mut steps = vec![];
+        let qed = loop {
+            match lines.next() {
+                Some((qed, "Qed.")) => break qed,
+                Some(step) => steps.push(step),
+                None => return Err(error(line, format!("missing 'Qed.' for {}", name.trim()))),
+            }
+        };
+        blocks.push(TheoremBlock {
+            name: name.trim(),
+            line,
+            statement: statement.trim(),
+            steps,
+            qed,
+        });
+    }
+    Ok(blocks)
+}
+
+// `library` holds the theorems proven before as lemmas, this one being
+// added when it passes
+fn check_block(block: &TheoremBlock, library: &mut Proof) -> Result<(), ScriptError> {
+    let statement = logic::Prop::parse_infix(block.statement).map_err(|err| {
+        let message = format!("{}\n{}", err, err.underline(block.statement));
+        error(block.line, message)
+    })?;
+    let mut proof = Proof::new();
+    proof.inherit_lemmas(library);
+    proof.add_goal_from_prop(statement);
+    for &(line, step) in block.steps.iter() {
+        if let Some(("logic", logic)) = step.split_once(char::is_whitespace) {
+            match logic.trim() {
+                "minimal" => proof.set_logic(logic::Logic::Minimal),
+                "intuitionistic" => proof.set_logic(logic::Logic::Intuitionistic),
+                "classical" => proof.set_logic(logic::Logic::Classical),
+                _ => return Err(error(line, format!("unknown logic '{}'", logic.trim()))),
+            }
+            continue;
+        }
+        if step == "auto" || step.starts_with("auto ") {
+            let args = step.strip_prefix("auto").unwrap_or_default();
+            let steps = backtrack::search(&proof, args).map_err(|err| error(line, err))?;
+            proof = backtrack::replay(&proof, &steps).map_err(|err| {
+                error(
+                    line,
+                    format!("the steps found could not be replayed: {}", err),
+                )
+            })?;
+            continue;
+        }
+        let tactic = tactics::parse(step).map_err(|err| error(line, err))?;
+        tactic
+            .run(&mut proof)
+            .map_err(|err| error(line, err.to_string()))?;
+    }
+    let Some(terms) = proof.finished_terms() else {
+        return Err(error(block.qed, String::from("some goals are still open")));
+    };
+    for (theorem, term) in terms.iter() {
+        kernel::check_theorem(theorem, term)
+            .map_err(|err| error(block.qed, format!("rejected by kernel: {}", err)))?;
+    }
+    proof
+        .save_lemma(block.name)
+        .map_err(|err| error(block.line, err))?;
+    library.inherit_lemmas(&proof);
+    Ok(())
+}
+
+// every theorem replayed from scratch, a failing one not stopping the
+// others, and the ones passing usable by the following ones with `use`
+pub fn check(input: &str) -> Result<Vec<TheoremResult>, ScriptError> {
+    let mut library = Proof::new();
+    Ok(blocks(input)?
+        .iter()
+        .map(|block| TheoremResult {
+            name: String::from(block.name),
+            line: block.line,
+            result: check_block(block, &mut library),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_script() {
+        let script = "# propositional
+Theorem and_comm: a ^ b => b ^ a
+Proof.
+  intro h; hyp_split h
+  split; first [exact H0 | exact h]
+Qed.
+
+Theorem wrong: a => b
+Proof.
+  intro
+  exact H0
+Qed.
+
+Theorem open: a ^ b => a
+Proof.
+  intro
+Qed.
+
+Theorem peirce: ((p => q) => p) => p
+Proof.
+  logic classical
+  intro h; excluded_middle p; destruct H0
+  exact H0
+  apply h; intro hp; unfold_not H0; apply_in hp H0; false
+Qed.
+";
+        let results = check(script).unwrap();
+        let summary: Vec<(&str, usize, Result<(), String>)> = results
+            .iter()
+            .map(|theorem| {
+                let result = theorem.result.clone().map_err(|err| err.to_string());
+                (theorem.name.as_str(), theorem.line, result)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("and_comm", 2, Ok(())),
+                (
+                    "wrong",
+                    8,
+                    Err(String::from("line 11: expected b, found a"))
+                ),
+                (
+                    "open",
+                    14,
+                    Err(String::from("line 17: some goals are still open"))
+                ),
+                ("peirce", 19, Ok(())),
+            ]
+        );
+    }
+
+    #[test]
+    fn earlier_theorems_as_lemmas() {
+        let script = "Theorem and_comm: a ^ b => b ^ a
+Proof.
+  intro h; hyp_split h
+  split; first [exact H0 | exact h]
+Qed.
+
+Theorem and_comm_or: (p | q) ^ r => r ^ (p | q)
+Proof.
+  use and_comm with a := p | q, b := r
+  intro h; apply H0; exact h
+Qed.
+
+Theorem and_comm: b ^ a => a ^ b
+Proof.
+  use and_comm with a := b, b := a
+  exact H0
+Qed.
+";
+        let results = check(script).unwrap();
+        assert_eq!(results[0].result, Ok(()));
+        assert_eq!(results[1].result, Ok(()));
+        assert_eq!(
+            results[2].result,
+            Err(error(
+                13,
+                String::from("a lemma named and_comm already exists")
+            ))
+        );
+    }
+
+    #[test]
+    fn auto_steps() {
+        let script = "Theorem and_comm: a ^ b => b ^ a
+Proof.
+  auto
+Qed.
+
+Theorem excluded: a | ~a
+Proof.
+  logic classical
+  auto best 1000
+Qed.
+
+Theorem half: (a => b) => a
+Proof.
+  intro
+  auto dfs
+Qed.
+
+Theorem typo: a => a
+Proof.
+  auto fast
+Qed.
+";
+        let results = check(script).unwrap();
+        assert_eq!(results[0].result, Ok(()));
+        assert_eq!(results[1].result, Ok(()));
+        assert_eq!(
+            results[2].result,
+            Err(error(15, String::from("Could not solve using auto")))
+        );
+        assert_eq!(
+            results[3].result,
+            Err(error(
+                20,
+                String::from(
+                    "arguments incorrect: [g4ip | dfs | id <max depth> | best <max states>]"
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(check("# nothing\n"), Ok(vec![]));
+        assert_eq!(
+            check("intro\n"),
+            Err(error(
+                1,
+                String::from("expected 'Theorem <name>: <statement>'")
+            ))
+        );
+        assert_eq!(
+            check("Theorem t: a\nintro\n"),
+            Err(error(2, String::from("expected 'Proof.'")))
+        );
+        assert_eq!(
+            check("Theorem t: a\nProof.\nintro\n"),
+            Err(error(1, String::from("missing 'Qed.' for t")))
+        );
+        let results =
+            check("Theorem t: a ^\nProof.\nQed.\nTheorem u: a\nProof.\nfoo\nQed.\n").unwrap();
+        assert_eq!(results[0].result.as_ref().unwrap_err().line, 1);
+        assert_eq!(
+            results[1].result,
+            Err(error(6, String::from("Unknown command")))
+        );
+    }
+}
@@ -1,29 +1,206 @@
 use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    error::Error,
     fmt,
     fs::File,
+    hash::{Hash, Hasher},
     io::{BufRead, BufReader, BufWriter, Write},
     rc::Rc,
 };
 
+use crate::libpoulet::derivation::Derivation;
+use crate::libpoulet::fol::{self, FoTerm};
+use crate::libpoulet::intern::PropRef;
+use crate::libpoulet::kernel;
 use crate::libpoulet::logic;
+use crate::libpoulet::sat;
+use crate::libpoulet::terms::{self, GoalContext, ProofRecord, Term};
+use crate::libpoulet::tptp;
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug)]
 pub struct Proof {
-    pub goals: Vec<(Rc<logic::Prop>, Vec<Rc<logic::Prop>>)>,
+    pub goals: Vec<(PropRef, Vec<PropRef>)>,
     active_goal: usize,
+    // proof terms built so far, contexts kept parallel to `goals`
+    record: Option<ProofRecord>,
+    logic: logic::Logic,
+    // names of the hypotheses, parallel to `goals`, missing ones being
+    // generated on demand
+    hyp_names: Vec<Vec<String>>,
+    // names of the hypotheses `clean` dropped as duplicates, each standing
+    // for the name of the identical one it kept, parallel to `goals`
+    hyp_aliases: Vec<BTreeMap<String, String>>,
+    // proofs saved to be used in this one, shared with its copies
+    lemmas: Rc<BTreeMap<String, Lemma>>,
+}
+
+// a finished proof, its hypotheses turned into premises of the statement
+#[derive(Clone, Debug)]
+struct Lemma {
+    statement: PropRef,
+    term: Term,
+    logic: logic::Logic,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum StrategyArg {
-    Intro,
+    // the introduced hypothesis or variable named after the argument, if any
+    Intro(Option<String>),
     Split,
-    HypSplit(usize),
+    HypSplit(String),
     OrSplit(bool),
-    HypOrSplit(usize, bool),
+    Destruct(String),
     FalseIsHyp,
-    Exact(usize),
-    Apply(usize),
-    ApplyIn(usize, usize, bool),
+    Exact(String),
+    Apply(String),
+    ApplyIn(String, String, bool),
+    ImplyLeft(String),
+    // in the goal when None, else in that hypothesis
+    UnfoldNot(Option<String>),
+    UnfoldIff(Option<String>),
+    // that universal hypothesis instantiated with the term
+    Instantiate(String, FoTerm),
+    // the existential goal proven for the term
+    Witness(FoTerm),
+    // classical only: the goal replaced by F, its negation assumed
+    ByContradiction,
+    // classical only: p | !p added to the hypotheses
+    ExcludedMiddle(PropRef),
+    // that lemma added to the hypotheses, its atoms substituted
+    Use(String, Vec<(String, PropRef)>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PropShape {
+    Implies,
+    CompoundImplies,
+    And,
+    Or,
+    WithNot,
+    WithIff,
+    Forall,
+    Exists,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StrategyError {
+    NoGoals,
+    GoalOutOfBounds {
+        index: usize,
+        len: usize,
+    },
+    UnknownHyp(String),
+    NameTaken(String),
+    InvalidName(String),
+    GoalShapeMismatch {
+        expected: PropShape,
+        found: logic::Prop,
+    },
+    HypShapeMismatch {
+        name: String,
+        expected: PropShape,
+        found: logic::Prop,
+    },
+    PremiseMismatch {
+        expected: logic::Prop,
+        found: logic::Prop,
+    },
+    NoFalseHyp,
+    NotInLogic(logic::Logic),
+    UnknownLemma(String),
+    // the atom would be replaced by a proposition with free variables in
+    // a lemma binding variables
+    CapturingSubstitution(String),
+}
+
+// `H` followed by the smallest number not already used as a name or alias
+fn fresh_name(names: &[String], aliases: &BTreeMap<String, String>) -> String {
+    let taken: HashSet<&str> = names
+        .iter()
+        .chain(aliases.keys())
+        .map(String::as_str)
+        .collect();
+    (0..)
+        .map(|n| format!("H{}", n))
+        .find(|name| !taken.contains(name.as_str()))
+        .unwrap()
+}
+
+// `dropped` now stands for `kept`, and so do the names standing for it
+fn add_alias(aliases: &mut BTreeMap<String, String>, dropped: String, kept: &str) {
+    for target in aliases.values_mut().filter(|target| **target == dropped) {
+        *target = String::from(kept);
+    }
+    aliases.insert(dropped, String::from(kept));
+}
+
+// names given to hypotheses and variables are single identifiers, so that
+// they can be written back in commands and proof files
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && !matches!(name, "T" | "F" | "forall" | "exists")
+}
+
+pub fn is_propositional(goal: &PropRef, hyps: &[PropRef]) -> bool {
+    goal.is_propositional() && hyps.iter().all(|hyp| hyp.is_propositional())
+}
+
+fn sequent_vars(goal: &PropRef, hyps: &[PropRef]) -> BTreeSet<String> {
+    let mut vars = goal.free_vars();
+    for hyp in hyps.iter() {
+        vars.extend(hyp.free_vars());
+    }
+    vars
+}
+
+// a variable named after `x` that the sequent says nothing about, standing
+// for an arbitrary individual
+fn eigenvariable(x: &str, goal: &PropRef, hyps: &[PropRef]) -> String {
+    fol::fresh_var(x, &sequent_vars(goal, hyps))
+}
+
+fn instance(x: &str, body: &logic::Prop, t: &FoTerm) -> PropRef {
+    if *t == FoTerm::Var(String::from(x)) {
+        PropRef::new(body.clone())
+    } else {
+        PropRef::new(body.subst(x, t))
+    }
+}
+
+// the atoms of the sequent whose excluded middle is not a hypothesis yet,
+// quantified formulas being left whole
+fn excluded_middle_candidates(goal: &PropRef, hyps: &[PropRef]) -> Vec<PropRef> {
+    fn collect(prop: &PropRef, atoms: &mut BTreeSet<PropRef>) {
+        match prop.as_ref() {
+            logic::Prop::True | logic::Prop::False => (),
+            logic::Prop::Not(a) => collect(a, atoms),
+            logic::Prop::Implies(a, b)
+            | logic::Prop::And(a, b)
+            | logic::Prop::Or(a, b)
+            | logic::Prop::Iff(a, b) => {
+                collect(a, atoms);
+                collect(b, atoms);
+            }
+            logic::Prop::Name(_)
+            | logic::Prop::Pred(_, _)
+            | logic::Prop::Forall(_, _)
+            | logic::Prop::Exists(_, _) => {
+                atoms.insert(prop.clone());
+            }
+        }
+    }
+    let mut atoms = BTreeSet::new();
+    collect(goal, &mut atoms);
+    hyps.iter().for_each(|hyp| collect(hyp, &mut atoms));
+    atoms
+        .into_iter()
+        .filter(|p| {
+            let excluded_middle =
+                logic::Prop::Or(p.clone(), PropRef::new(logic::Prop::Not(p.clone())));
+            !hyps.contains(&PropRef::new(excluded_middle))
+        })
+        .collect()
 }
 
 impl Proof {
@@ -31,6 +208,11 @@ impl Proof {
         Proof {
             goals: vec![],
             active_goal: 0,
+            record: Some(ProofRecord::default()),
+            logic: logic::Logic::Intuitionistic,
+            hyp_names: vec![],
+            hyp_aliases: vec![],
+            lemmas: Rc::default(),
         }
     }
 
@@ -38,22 +220,55 @@ impl Proof {
         if let Ok(file) = File::open(path) {
             let mut proof = Proof::new();
             let reader = BufReader::new(file);
-            for l in reader.lines().map_while(Result::ok) {
+            for (line_index, l) in reader.lines().map_while(Result::ok).enumerate() {
                 if let Some((verb, param)) = l.split_once(':') {
+                    // hypotheses are named as H:name:prop, or left unnamed
+                    let (name, param) = match (verb, param.split_once(':')) {
+                        ("H", Some((name, param))) => (Some(String::from(name)), param),
+                        _ => (None, param),
+                    };
+                    let parse_error = |err: logic::ParseError| {
+                        format!(
+                            "Error while parsing file '{}', line {}: {}\n{}",
+                            path,
+                            line_index + 1,
+                            err,
+                            err.underline(param)
+                        )
+                    };
                     if verb == "G" {
                         match logic::Prop::parse_rpn(param) {
                             Ok(prop) => {
                                 proof.add_goal_from_prop(prop);
                                 let _ = proof.set_active_goal(proof.number_of_goals() - 1);
                             }
-                            Err(msg) => return Err(format!("Error while parsing file: {}", msg)),
+                            Err(err) => return Err(parse_error(err)),
                         }
                     } else if verb == "H" {
                         match logic::Prop::parse_rpn(param) {
                             Ok(prop) => {
-                                proof.add_hyp_from_prop(prop);
+                                let err = match name.as_ref() {
+                                    Some(name) if !is_valid_name(name) => {
+                                        Some(StrategyError::InvalidName(name.clone()))
+                                    }
+                                    Some(name)
+                                        if proof.hyp_names(proof.active_goal).contains(name) =>
+                                    {
+                                        Some(StrategyError::NameTaken(name.clone()))
+                                    }
+                                    _ => None,
+                                };
+                                if let Some(err) = err {
+                                    return Err(format!(
+                                        "Error while reading file '{}', line {}: {}",
+                                        path,
+                                        line_index + 1,
+                                        err
+                                    ));
+                                }
+                                proof.add_named_hyp(name, prop);
                             }
-                            Err(msg) => return Err(format!("Error while parsing file: {}", msg)),
+                            Err(err) => return Err(parse_error(err)),
                         }
                     }
                 }
@@ -68,13 +283,13 @@ impl Proof {
     pub fn to_file(&self, path: &str) -> Result<(), String> {
         if let Ok(file) = File::create(path) {
             let mut file = BufWriter::new(file);
-            for goal in self.goals.iter() {
-                match file.write_fmt(format_args!("G:{}", goal.0.to_string_rpn())) {
+            for (index, goal) in self.goals.iter().enumerate() {
+                match writeln!(file, "G:{}", goal.0.to_string_rpn()) {
                     Ok(_) => (),
                     Err(_) => return Err(format!("failed to write to file '{}'", path)),
                 }
-                for hyp in goal.1.iter() {
-                    match file.write_fmt(format_args!("H:{}", hyp.to_string_rpn())) {
+                for (name, hyp) in self.hyp_names(index).iter().zip(goal.1.iter()) {
+                    match writeln!(file, "H:{}:{}", name, hyp.to_string_rpn()) {
                         Ok(_) => (),
                         Err(_) => return Err(format!("failed to write to file '{}'", path)),
                     }
@@ -90,6 +305,137 @@ impl Proof {
         }
     }
 
+    // the active goal negated along with its hypotheses, satisfiable exactly
+    // when the goal is not a classical consequence of them
+    pub fn to_dimacs(&self, path: &str) -> Result<(), String> {
+        let Some((goal, hyps)) = self.goals.get(self.active_goal) else {
+            return Err(String::from("no goal to export"));
+        };
+        if !is_propositional(goal, hyps) {
+            return Err(String::from("only propositional goals can be exported"));
+        }
+        let (cnf, atoms) = sat::tseitin(&sat::refutation(hyps, goal));
+        let atoms = atoms
+            .into_iter()
+            .map(|(atom, v)| (atom.atom_name().unwrap(), v))
+            .collect();
+        let dimacs = format!(
+            "c poulet: unsatisfiable iff the goal follows classically from its hypotheses\n{}",
+            cnf.to_dimacs(&atoms)
+        );
+        std::fs::write(path, dimacs).map_err(|_| format!("failed to write to file '{}'", path))
+    }
+
+    // the goal is the negation of the clauses, so it is provable exactly
+    // when they are unsatisfiable (classically, which for a negation is the
+    // same as intuitionistically)
+    pub fn from_dimacs(path: &str) -> Result<Proof, String> {
+        let Ok(input) = std::fs::read_to_string(path) else {
+            return Err(format!("failed to open file '{}'", path));
+        };
+        match sat::Cnf::parse_dimacs(&input) {
+            Ok((cnf, atoms)) => {
+                let mut proof = Proof::new();
+                proof.add_goal_from_prop(logic::Prop::not(cnf.to_prop(&atoms)));
+                Ok(proof)
+            }
+            Err(err) => Err(format!("Error while parsing file '{}': {}", path, err)),
+        }
+    }
+
+    // each conjecture becomes a goal whose hypotheses are all the axioms,
+    // and without a conjecture the axioms must be shown contradictory
+    pub fn from_tptp(path: &str) -> Result<Proof, String> {
+        let Ok(input) = std::fs::read_to_string(path) else {
+            return Err(format!("failed to open file '{}'", path));
+        };
+        match tptp::parse(&input) {
+            Ok(mut problem) => {
+                if problem.conjectures.is_empty() {
+                    problem.conjectures.push(logic::Prop::False);
+                }
+                let mut proof = Proof::new();
+                for conjecture in problem.conjectures {
+                    proof.add_goal_from_prop(conjecture);
+                    let _ = proof.set_active_goal(proof.number_of_goals() - 1);
+                    for axiom in problem.axioms.iter() {
+                        proof.add_hyp_from_prop(axiom.clone());
+                    }
+                }
+                Ok(proof)
+            }
+            Err(err) => Err(format!("Error while parsing file '{}', {}", path, err)),
+        }
+    }
+
+    // a TPTP problem has a single conjecture, the active goal
+    pub fn to_tptp(&self, path: &str) -> Result<(), String> {
+        let Some((goal, hyps)) = self.goals.get(self.active_goal) else {
+            return Err(String::from("no goal to export"));
+        };
+        if !is_propositional(goal, hyps) {
+            return Err(String::from("only propositional goals can be exported"));
+        }
+        std::fs::write(path, tptp::problem_to_string(hyps, goal))
+            .map_err(|_| format!("failed to write to file '{}'", path))
+    }
+
+    pub fn logic(&self) -> logic::Logic {
+        self.logic
+    }
+
+    pub fn set_logic(&mut self, logic: logic::Logic) {
+        self.logic = logic;
+        if let Some(record) = self.record.as_mut() {
+            record.set_logic(logic);
+        }
+    }
+
+    // the lemmas of `other`, replacing the ones this proof had
+    pub fn inherit_lemmas(&mut self, other: &Proof) {
+        self.lemmas = other.lemmas.clone();
+    }
+
+    pub fn lemmas(&self) -> Vec<(String, PropRef)> {
+        self.lemmas
+            .iter()
+            .map(|(name, lemma)| (name.clone(), lemma.statement.clone()))
+            .collect()
+    }
+
+    // the proof, once finished and checked by the kernel, kept as a lemma
+    // for this proof and the ones inheriting its lemmas
+    pub fn save_lemma(&mut self, name: &str) -> Result<(), String> {
+        if self.lemmas.contains_key(name) {
+            return Err(format!("a lemma named {} already exists", name));
+        }
+        let Some(terms) = self.finished_terms() else {
+            return Err(String::from("the proof is not finished"));
+        };
+        let [(theorem, term)] = terms.as_slice() else {
+            return Err(String::from("only the proof of a single goal can be saved"));
+        };
+        if let Err(err) = kernel::check_theorem(theorem, term) {
+            return Err(format!("rejected by kernel: {}", err));
+        }
+        let mut statement = theorem.goal.clone();
+        let mut term = term.clone();
+        for (x, hyp) in theorem.hyps.iter().rev() {
+            statement = PropRef::new(logic::Prop::Implies(hyp.clone(), statement));
+            term = Term::Lambda(*x, hyp.clone(), Rc::new(term));
+        }
+        let logic = kernel::required_logic(&term);
+        Rc::make_mut(&mut self.lemmas).insert(
+            String::from(name),
+            Lemma {
+                statement,
+                term,
+                logic,
+            },
+        );
+        Ok(())
+    }
+
     pub fn number_of_goals(&self) -> usize {
         self.goals.len()
     }
@@ -98,9 +444,12 @@ impl Proof {
         self.active_goal
     }
 
-    pub fn set_active_goal(&mut self, i: usize) -> Result<(), &str> {
+    pub fn set_active_goal(&mut self, i: usize) -> Result<(), StrategyError> {
         if i >= self.goals.len() {
-            Err("Out of bounds")
+            Err(StrategyError::GoalOutOfBounds {
+                index: i,
+                len: self.goals.len(),
+            })
         } else {
             self.active_goal = i;
             Ok(())
@@ -108,61 +457,282 @@ impl Proof {
     }
 
     pub fn add_goal_from_prop(&mut self, goal: logic::Prop) {
-        self.goals.push((Rc::new(goal), vec![]))
+        let goal = PropRef::new(goal);
+        if let Some(record) = self.record.as_mut() {
+            record.add_theorem(goal.clone());
+        }
+        self.sync_names();
+        self.goals.push((goal, vec![]));
+        self.hyp_names.push(vec![]);
+        self.hyp_aliases.push(BTreeMap::new());
     }
 
     pub fn add_hyp_from_prop(&mut self, hyp: logic::Prop) {
-        self.goals[self.active_goal].1.push(Rc::new(hyp))
+        self.add_named_hyp(None, hyp)
+    }
+
+    fn add_named_hyp(&mut self, name: Option<String>, hyp: logic::Prop) {
+        let hyp = PropRef::new(hyp);
+        if let Some(record) = self.record.as_mut() {
+            record.add_hyp(self.active_goal, hyp.clone());
+        }
+        self.sync_names();
+        let aliases = &self.hyp_aliases[self.active_goal];
+        let names = &mut self.hyp_names[self.active_goal];
+        let name = name.unwrap_or_else(|| fresh_name(names, aliases));
+        names.push(name);
+        self.goals[self.active_goal].1.push(hyp)
+    }
+
+    // the names of the hypotheses of that goal, in order
+    pub fn hyp_names(&self, goal: usize) -> Vec<String> {
+        let len = self.goals[goal].1.len();
+        let mut names = self.hyp_names.get(goal).cloned().unwrap_or_default();
+        let aliases = self.aliases(goal);
+        names.truncate(len);
+        while names.len() < len {
+            names.push(fresh_name(&names, &aliases));
+        }
+        names
+    }
+
+    fn aliases(&self, goal: usize) -> BTreeMap<String, String> {
+        self.hyp_aliases.get(goal).cloned().unwrap_or_default()
+    }
+
+    fn sync_names(&mut self) {
+        self.hyp_names = (0..self.goals.len()).map(|i| self.hyp_names(i)).collect();
+        self.hyp_aliases.resize(self.goals.len(), BTreeMap::new());
+    }
+
+    // a name dropped by `clean` still finds the hypothesis that was kept
+    fn hyp_index(&self, name: &str) -> Result<usize, StrategyError> {
+        let aliases = self.aliases(self.active_goal);
+        let name_or_alias = aliases.get(name).map_or(name, String::as_str);
+        self.hyp_names(self.active_goal)
+            .iter()
+            .position(|n| n == name_or_alias)
+            .ok_or_else(|| StrategyError::UnknownHyp(String::from(name)))
     }
 
     pub fn clean(&mut self) {
-        for elt in self.goals.iter_mut() {
-            elt.1.sort();
-            elt.1.dedup();
-            elt.1.retain(|x| *(x.as_ref()) != logic::Prop::True);
+        self.active_goal = 0;
+        self.sync_names();
+        let Some(record) = self.record.as_mut() else {
+            // names follow their hypotheses, the first one being kept and the
+            // names of its duplicates becoming aliases of it
+            let mut entries: Vec<_> = self
+                .goals
+                .drain(..)
+                .zip(self.hyp_names.drain(..))
+                .zip(self.hyp_aliases.drain(..))
+                .map(|(((goal, hyps), names), aliases)| {
+                    (goal, hyps.into_iter().zip(names).collect(), aliases)
+                })
+                .collect();
+            for (_, hyps, aliases) in entries.iter_mut() {
+                let hyps: &mut Vec<(PropRef, String)> = hyps;
+                hyps.sort_by(|x, y| x.0.cmp(&y.0));
+                hyps.dedup_by(|x, y| {
+                    let duplicate = x.0 == y.0;
+                    if duplicate {
+                        add_alias(aliases, std::mem::take(&mut x.1), &y.1);
+                    }
+                    duplicate
+                });
+                hyps.retain(|x| *(x.0.as_ref()) != logic::Prop::True);
+            }
+            entries.sort_by(|x, y| {
+                x.0.cmp(&y.0)
+                    .then_with(|| x.1.iter().map(|h| &h.0).cmp(y.1.iter().map(|h| &h.0)))
+            });
+            for (goal, hyps, aliases) in entries {
+                let (hyps, names): (Vec<PropRef>, Vec<String>) = hyps.into_iter().unzip();
+                if let Some(last) = self.goals.last()
+                    && last.0 == goal
+                    && last.1 == hyps
+                {
+                    continue;
+                }
+                if *goal != logic::Prop::True {
+                    self.goals.push((goal, hyps));
+                    self.hyp_names.push(names);
+                    self.hyp_aliases.push(aliases);
+                }
+            }
+            return;
+        };
+
+        // same cleaning, but hypotheses carry their term variable along and
+        // dropped goals get their hole filled from the goal that replaces them
+        let mut entries: Vec<_> = self
+            .goals
+            .drain(..)
+            .zip(record.contexts.drain(..))
+            .zip(self.hyp_names.drain(..))
+            .zip(self.hyp_aliases.drain(..))
+            .map(|((((goal, hyps), ctx), names), aliases)| {
+                let hyps: Vec<(PropRef, usize, String)> = hyps
+                    .into_iter()
+                    .zip(ctx.vars)
+                    .zip(names)
+                    .map(|((hyp, var), name)| (hyp, var, name))
+                    .collect();
+                (goal, hyps, ctx.hole, aliases)
+            })
+            .collect();
+        for (_, hyps, _, aliases) in entries.iter_mut() {
+            hyps.sort_by(|x, y| x.0.cmp(&y.0));
+            hyps.dedup_by(|x, y| {
+                let duplicate = x.0 == y.0;
+                if duplicate {
+                    add_alias(aliases, std::mem::take(&mut x.2), &y.2);
+                }
+                duplicate
+            });
+            hyps.retain(|x| *(x.0.as_ref()) != logic::Prop::True);
+        }
+        entries.sort_by(|x, y| {
+            x.0.cmp(&y.0)
+                .then_with(|| x.1.iter().map(|h| &h.0).cmp(y.1.iter().map(|h| &h.0)))
+        });
+        for (goal, hyps, hole, aliases) in entries {
+            let mut vars = vec![];
+            let mut names = vec![];
+            let hyps: Vec<PropRef> = hyps
+                .into_iter()
+                .map(|(hyp, var, name)| {
+                    vars.push(var);
+                    names.push(name);
+                    hyp
+                })
+                .collect();
+            if let Some(last) = self.goals.last()
+                && last.0 == goal
+                && last.1 == hyps
+            {
+                let kept = record.contexts.last().unwrap().clone();
+                record.alias(hole, &kept, &vars);
+            } else if *goal == logic::Prop::True {
+                if !record.is_filled(hole) {
+                    record.fill(hole, Term::Unit);
+                }
+            } else {
+                self.goals.push((goal, hyps));
+                self.hyp_names.push(names);
+                self.hyp_aliases.push(aliases);
+                record.contexts.push(GoalContext { hole, vars });
+            }
         }
-        self.goals.sort();
-        self.goals.dedup();
-        self.goals.retain(|x| *(x.0.as_ref()) != logic::Prop::True);
-        self.active_goal = 0
     }
 
-    pub fn execute(&mut self, strat: &StrategyArg) -> Result<(), &str> {
+    pub fn execute(&mut self, strat: &StrategyArg) -> Result<(), StrategyError> {
         if self.goals.is_empty() {
-            return Err("No goal to execute strategy on.");
+            return Err(StrategyError::NoGoals);
+        }
+        self.sync_names();
+        let before = self.goals[self.active_goal].clone();
+        let before_names = self.hyp_names[self.active_goal].clone();
+        let num_goals = self.goals.len();
+        self.apply_strategy(strat)?;
+        self.name_new_hyps(strat, &before_names, num_goals);
+        if self.record.is_some() {
+            self.record_step(strat, before, &before_names);
+        }
+        Ok(())
+    }
+
+    // hypotheses keep their position when rewritten, so the active goal
+    // and the goals split from it keep the names they had and get fresh
+    // ones for the hypotheses that were added
+    fn name_new_hyps(&mut self, strat: &StrategyArg, before_names: &[String], num_goals: usize) {
+        let aliases = self.hyp_aliases[self.active_goal].clone();
+        for index in std::iter::once(self.active_goal).chain(num_goals..self.goals.len()) {
+            let len = self.goals[index].1.len();
+            let mut names = before_names.to_vec();
+            names.truncate(len);
+            if let StrategyArg::Intro(Some(name)) = strat
+                && names.len() < len
+            {
+                names.push(name.clone());
+            }
+            while names.len() < len {
+                names.push(fresh_name(&names, &aliases));
+            }
+            if index < num_goals {
+                self.hyp_names[index] = names;
+            } else {
+                self.hyp_names.push(names);
+                self.hyp_aliases.push(aliases.clone());
+            }
         }
+    }
+
+    fn apply_strategy(&mut self, strat: &StrategyArg) -> Result<(), StrategyError> {
+        let goal = self.goals[self.active_goal].0.clone();
+        let goal_mismatch = |expected: PropShape| StrategyError::GoalShapeMismatch {
+            expected,
+            found: goal.as_ref().clone(),
+        };
+        let names = self.hyp_names(self.active_goal);
+        let hyp_mismatch =
+            |index: usize, expected: PropShape, hyp: &PropRef| StrategyError::HypShapeMismatch {
+                name: names[index].clone(),
+                expected,
+                found: hyp.as_ref().clone(),
+            };
         match strat {
-            StrategyArg::Intro => {
-                if let logic::Prop::Implies(a, b) = self.goals[self.active_goal].0.as_ref().clone()
-                {
+            StrategyArg::Intro(Some(name)) if !is_valid_name(name) => {
+                Err(StrategyError::InvalidName(name.clone()))
+            }
+            StrategyArg::Intro(name) => match goal.as_ref() {
+                logic::Prop::Implies(a, b) => {
+                    if let Some(name) = name
+                        && (names.contains(name)
+                            || self.hyp_aliases[self.active_goal].contains_key(name))
+                    {
+                        return Err(StrategyError::NameTaken(name.clone()));
+                    }
                     self.goals[self.active_goal].0 = b.clone();
                     self.goals[self.active_goal].1.push(a.clone());
-                    return Ok(());
+                    Ok(())
                 }
-            }
+                logic::Prop::Forall(x, a) => {
+                    let hyps = &self.goals[self.active_goal].1;
+                    let y = match name {
+                        Some(y) if sequent_vars(&goal, hyps).contains(y) => {
+                            return Err(StrategyError::NameTaken(y.clone()));
+                        }
+                        Some(y) => y.clone(),
+                        None => eigenvariable(x, &goal, hyps),
+                    };
+                    self.goals[self.active_goal].0 = instance(x, a, &FoTerm::Var(y));
+                    Ok(())
+                }
+                _ => Err(goal_mismatch(PropShape::Implies)),
+            },
             StrategyArg::Split => {
-                if let logic::Prop::And(a, b) = self.goals[self.active_goal].0.as_ref() {
+                if let logic::Prop::And(a, b) = goal.as_ref() {
                     let new_goal_a = (a.clone(), self.goals[self.active_goal].1.clone());
                     let new_goal_b = (b.clone(), self.goals[self.active_goal].1.clone());
                     self.goals[self.active_goal] = new_goal_a;
                     self.goals.push(new_goal_b);
                     return Ok(());
                 }
+                Err(goal_mismatch(PropShape::And))
             }
             StrategyArg::HypSplit(arg1) => {
-                if *arg1 >= self.goals[self.active_goal].1.len() {
-                    return Err("Out of bounds");
-                }
-                if let logic::Prop::And(a, b) =
-                    self.goals[self.active_goal].1[*arg1].as_ref().clone()
-                {
+                let arg1 = &self.hyp_index(arg1)?;
+                let hyp = self.goals[self.active_goal].1[*arg1].clone();
+                if let logic::Prop::And(a, b) = hyp.as_ref() {
                     self.goals[self.active_goal].1[*arg1] = a.clone();
                     self.goals[self.active_goal].1.push(b.clone());
                     return Ok(());
                 }
+                Err(hyp_mismatch(*arg1, PropShape::And, &hyp))
             }
             StrategyArg::OrSplit(left) => {
-                if let logic::Prop::Or(a, b) = self.goals[self.active_goal].0.as_ref() {
+                if let logic::Prop::Or(a, b) = goal.as_ref() {
                     let new_goal = if *left {
                         (a.clone(), self.goals[self.active_goal].1.clone())
                     } else {
@@ -172,82 +742,630 @@ impl Proof {
                     self.goals[self.active_goal] = new_goal;
                     return Ok(());
                 }
+                Err(goal_mismatch(PropShape::Or))
             }
-            StrategyArg::HypOrSplit(arg1, left) => {
-                if *arg1 >= self.goals[self.active_goal].1.len() {
-                    return Err("Out of bounds");
-                }
-                if let logic::Prop::Or(a, b) = self.goals[self.active_goal].1[*arg1].as_ref() {
-                    if *left {
+            StrategyArg::Destruct(arg1) => {
+                let arg1 = &self.hyp_index(arg1)?;
+                let hyp = self.goals[self.active_goal].1[*arg1].clone();
+                match hyp.as_ref() {
+                    logic::Prop::Or(a, b) => {
+                        let mut hyps_b = self.goals[self.active_goal].1.clone();
+                        hyps_b[*arg1] = b.clone();
                         self.goals[self.active_goal].1[*arg1] = a.clone();
-                    } else {
-                        self.goals[self.active_goal].1[*arg1] = b.clone();
+                        self.goals.push((goal.clone(), hyps_b));
+                        Ok(())
                     }
-                    return Ok(());
+                    logic::Prop::Exists(x, a) => {
+                        let y = eigenvariable(x, &goal, &self.goals[self.active_goal].1);
+                        self.goals[self.active_goal].1[*arg1] = instance(x, a, &FoTerm::Var(y));
+                        Ok(())
+                    }
+                    _ => Err(hyp_mismatch(*arg1, PropShape::Or, &hyp)),
                 }
             }
             StrategyArg::FalseIsHyp => {
+                if self.logic == logic::Logic::Minimal {
+                    return Err(StrategyError::NotInLogic(self.logic));
+                }
                 for hyp in self.goals[self.active_goal].1.iter() {
                     if *(*hyp) == logic::Prop::False {
-                        self.goals[self.active_goal].0 = Rc::new(logic::Prop::True);
+                        self.goals[self.active_goal].0 = PropRef::new(logic::Prop::True);
                         return Ok(());
                     }
                 }
+                Err(StrategyError::NoFalseHyp)
             }
             StrategyArg::Exact(arg1) => {
-                if *arg1 >= self.goals[self.active_goal].1.len() {
-                    return Err("Out of bounds");
-                }
-                if self.goals[self.active_goal].1[*arg1] == self.goals[self.active_goal].0 {
-                    self.goals[self.active_goal].0 = Rc::new(logic::Prop::True);
+                let arg1 = &self.hyp_index(arg1)?;
+                let hyp = self.goals[self.active_goal].1[*arg1].clone();
+                if hyp.alpha_eq(&goal) {
+                    self.goals[self.active_goal].0 = PropRef::new(logic::Prop::True);
                     return Ok(());
                 }
+                Err(StrategyError::PremiseMismatch {
+                    expected: goal.as_ref().clone(),
+                    found: hyp.as_ref().clone(),
+                })
             }
             StrategyArg::Apply(arg1) => {
-                if *arg1 >= self.goals[self.active_goal].1.len() {
-                    return Err("Out of bounds");
-                }
-                if let logic::Prop::Implies(a, b) = self.goals[self.active_goal].1[*arg1].as_ref() {
-                    if b.as_ref() == self.goals[self.active_goal].0.as_ref() {
+                let arg1 = &self.hyp_index(arg1)?;
+                let hyp = self.goals[self.active_goal].1[*arg1].clone();
+                match hyp.as_ref() {
+                    logic::Prop::Implies(a, b) if b.alpha_eq(&goal) => {
                         self.goals[self.active_goal].0 = a.clone();
-                        return Ok(());
+                        Ok(())
                     }
+                    logic::Prop::Implies(_, b) => Err(StrategyError::PremiseMismatch {
+                        expected: goal.as_ref().clone(),
+                        found: b.as_ref().clone(),
+                    }),
+                    _ => Err(hyp_mismatch(*arg1, PropShape::Implies, &hyp)),
                 }
             }
             StrategyArg::ApplyIn(arg1, arg2, keep_old) => {
-                if *arg1 >= self.goals[self.active_goal].1.len() {
-                    return Err("Out of bounds");
-                }
-                if *arg2 >= self.goals[self.active_goal].1.len() {
-                    return Err("Out of bounds");
-                }
-                if let logic::Prop::Implies(a, b) =
-                    self.goals[self.active_goal].1[*arg2].as_ref().clone()
-                {
-                    let target_prop = &self.goals[self.active_goal].1[*arg1];
-                    if a.as_ref() == target_prop.as_ref() {
+                let arg1 = &self.hyp_index(arg1)?;
+                let arg2 = &self.hyp_index(arg2)?;
+                let hyp = self.goals[self.active_goal].1[*arg2].clone();
+                let target_prop = self.goals[self.active_goal].1[*arg1].clone();
+                match hyp.as_ref() {
+                    logic::Prop::Implies(a, b) if a.alpha_eq(&target_prop) => {
                         if *keep_old {
                             self.goals[self.active_goal].1.push(b.clone());
                         } else {
                             self.goals[self.active_goal].1[*arg1] = b.clone();
                         }
-                        return Ok(());
+                        Ok(())
+                    }
+                    logic::Prop::Implies(a, _) => Err(StrategyError::PremiseMismatch {
+                        expected: a.as_ref().clone(),
+                        found: target_prop.as_ref().clone(),
+                    }),
+                    _ => Err(hyp_mismatch(*arg2, PropShape::Implies, &hyp)),
+                }
+            }
+            StrategyArg::ImplyLeft(arg1) => {
+                // rewrites an implication hypothesis according to the shape
+                // of its premise, as in the left rules of G4ip
+                let arg1 = &self.hyp_index(arg1)?;
+                let hyp = self.goals[self.active_goal].1[*arg1].clone();
+                if let logic::Prop::Implies(a, c) = hyp.as_ref() {
+                    let hyps = &mut self.goals[self.active_goal].1;
+                    match a.as_ref() {
+                        logic::Prop::True => {
+                            hyps[*arg1] = c.clone();
+                            return Ok(());
+                        }
+                        logic::Prop::False => {
+                            hyps[*arg1] = PropRef::new(logic::Prop::True);
+                            return Ok(());
+                        }
+                        logic::Prop::And(p, q) => {
+                            hyps[*arg1] = PropRef::new(logic::Prop::Implies(
+                                p.clone(),
+                                PropRef::new(logic::Prop::Implies(q.clone(), c.clone())),
+                            ));
+                            return Ok(());
+                        }
+                        logic::Prop::Or(p, q) => {
+                            hyps[*arg1] = PropRef::new(logic::Prop::Implies(p.clone(), c.clone()));
+                            hyps.push(PropRef::new(logic::Prop::Implies(q.clone(), c.clone())));
+                            return Ok(());
+                        }
+                        logic::Prop::Implies(p, q) => {
+                            // first prove q from p with q => c, then the goal with c
+                            let mut hyps_c = hyps.clone();
+                            hyps_c[*arg1] = c.clone();
+                            hyps[*arg1] = PropRef::new(logic::Prop::Implies(q.clone(), c.clone()));
+                            hyps.push(p.clone());
+                            self.goals[self.active_goal].0 = q.clone();
+                            self.goals.push((goal.clone(), hyps_c));
+                            return Ok(());
+                        }
+                        logic::Prop::Name(_)
+                        | logic::Prop::Pred(_, _)
+                        | logic::Prop::Not(_)
+                        | logic::Prop::Iff(_, _)
+                        | logic::Prop::Forall(_, _)
+                        | logic::Prop::Exists(_, _) => (),
+                    }
+                }
+                Err(hyp_mismatch(*arg1, PropShape::CompoundImplies, &hyp))
+            }
+            StrategyArg::Instantiate(arg1, t) => {
+                let arg1 = &self.hyp_index(arg1)?;
+                let hyp = self.goals[self.active_goal].1[*arg1].clone();
+                if let logic::Prop::Forall(x, a) = hyp.as_ref() {
+                    self.goals[self.active_goal].1.push(instance(x, a, t));
+                    return Ok(());
+                }
+                Err(hyp_mismatch(*arg1, PropShape::Forall, &hyp))
+            }
+            StrategyArg::Witness(t) => {
+                if let logic::Prop::Exists(x, a) = goal.as_ref() {
+                    self.goals[self.active_goal].0 = instance(x, a, t);
+                    return Ok(());
+                }
+                Err(goal_mismatch(PropShape::Exists))
+            }
+            StrategyArg::ByContradiction | StrategyArg::ExcludedMiddle(_)
+                if self.logic != logic::Logic::Classical =>
+            {
+                Err(StrategyError::NotInLogic(self.logic))
+            }
+            StrategyArg::ByContradiction => {
+                self.goals[self.active_goal].0 = PropRef::new(logic::Prop::False);
+                self.goals[self.active_goal]
+                    .1
+                    .push(PropRef::new(logic::Prop::Not(goal)));
+                Ok(())
+            }
+            StrategyArg::ExcludedMiddle(p) => {
+                let excluded_middle =
+                    logic::Prop::Or(p.clone(), PropRef::new(logic::Prop::Not(p.clone())));
+                self.goals[self.active_goal]
+                    .1
+                    .push(PropRef::new(excluded_middle));
+                Ok(())
+            }
+            StrategyArg::Use(name, subst) => {
+                let Some(lemma) = self.lemmas.get(name) else {
+                    return Err(StrategyError::UnknownLemma(name.clone()));
+                };
+                if lemma.logic > self.logic {
+                    return Err(StrategyError::NotInLogic(self.logic));
+                }
+                if !lemma.statement.is_propositional()
+                    && let Some((atom, _)) = subst.iter().find(|(_, p)| !p.free_vars().is_empty())
+                {
+                    return Err(StrategyError::CapturingSubstitution(atom.clone()));
+                }
+                let subst = subst.iter().cloned().collect();
+                let hyp = PropRef::new(lemma.statement.subst_atoms(&subst));
+                self.goals[self.active_goal].1.push(hyp);
+                Ok(())
+            }
+            StrategyArg::UnfoldNot(target) | StrategyArg::UnfoldIff(target) => {
+                let (unfold, shape): (fn(&logic::Prop) -> logic::Prop, PropShape) = match strat {
+                    StrategyArg::UnfoldNot(_) => (logic::Prop::unfold_not, PropShape::WithNot),
+                    _ => (logic::Prop::unfold_iff, PropShape::WithIff),
+                };
+                match target {
+                    None => {
+                        let unfolded = unfold(&goal);
+                        if unfolded == *goal {
+                            return Err(goal_mismatch(shape));
+                        }
+                        self.goals[self.active_goal].0 = PropRef::new(unfolded);
+                    }
+                    Some(index) => {
+                        let index = &self.hyp_index(index)?;
+                        let hyp = self.goals[self.active_goal].1[*index].clone();
+                        let unfolded = unfold(&hyp);
+                        if unfolded == *hyp {
+                            return Err(hyp_mismatch(*index, shape, &hyp));
+                        }
+                        self.goals[self.active_goal].1[*index] = PropRef::new(unfolded);
                     }
                 }
+                Ok(())
+            }
+        }
+    }
+
+    // extends the proof term of the active goal, `before` being that goal
+    // as it was before `strat` was successfully applied
+    fn record_step(
+        &mut self,
+        strat: &StrategyArg,
+        before: (PropRef, Vec<PropRef>),
+        before_names: &[String],
+    ) {
+        let Some(record) = self.record.as_mut() else {
+            return;
+        };
+        // the kernel does not tell a notation from its definition, the
+        // term stays the same
+        if let StrategyArg::UnfoldNot(_) | StrategyArg::UnfoldIff(_) = strat {
+            return;
+        }
+        let active = self.active_goal;
+        let hole = record.contexts[active].hole;
+        let var = |ctx: &GoalContext, i: usize| Rc::new(Term::Var(ctx.vars[i]));
+        let aliases = &self.hyp_aliases[active];
+        let index = |name: &String| {
+            let name = aliases.get(name).unwrap_or(name);
+            before_names.iter().position(|n| n == name).unwrap()
+        };
+        let ctx = record.contexts[active].clone();
+        let (goal, hyps) = before;
+        let (term, new_ctx) = match (strat, goal.as_ref()) {
+            (StrategyArg::Intro(_), logic::Prop::Implies(a, _)) => {
+                let (x, next) = (record.fresh(), record.fresh());
+                let mut vars = ctx.vars.clone();
+                vars.push(x);
+                (
+                    Term::Lambda(x, a.clone(), Rc::new(Term::Hole(next))),
+                    GoalContext { hole: next, vars },
+                )
+            }
+            (StrategyArg::Intro(name), logic::Prop::Forall(x, _)) => {
+                let next = record.fresh();
+                let y = match name {
+                    Some(y) => y.clone(),
+                    None => eigenvariable(x, &goal, &hyps),
+                };
+                (
+                    Term::ForallIntro(y, Rc::new(Term::Hole(next))),
+                    GoalContext {
+                        hole: next,
+                        vars: ctx.vars.clone(),
+                    },
+                )
+            }
+            (StrategyArg::Split, _) => {
+                let (h1, h2) = (record.fresh(), record.fresh());
+                record.contexts.push(GoalContext {
+                    hole: h2,
+                    vars: ctx.vars.clone(),
+                });
+                (
+                    Term::Pair(Rc::new(Term::Hole(h1)), Rc::new(Term::Hole(h2))),
+                    GoalContext {
+                        hole: h1,
+                        vars: ctx.vars.clone(),
+                    },
+                )
+            }
+            (StrategyArg::HypSplit(i), _) => {
+                let i = &index(i);
+                let (y, z, next) = (record.fresh(), record.fresh(), record.fresh());
+                let mut vars = ctx.vars.clone();
+                vars[*i] = y;
+                vars.push(z);
+                let inner = Term::Let(
+                    z,
+                    Rc::new(Term::Snd(var(&ctx, *i))),
+                    Rc::new(Term::Hole(next)),
+                );
+                (
+                    Term::Let(y, Rc::new(Term::Fst(var(&ctx, *i))), Rc::new(inner)),
+                    GoalContext { hole: next, vars },
+                )
+            }
+            (StrategyArg::OrSplit(left), logic::Prop::Or(a, b)) => {
+                let next = record.fresh();
+                let hole_term = Rc::new(Term::Hole(next));
+                let term = if *left {
+                    Term::Inl(hole_term, b.clone())
+                } else {
+                    Term::Inr(a.clone(), hole_term)
+                };
+                (
+                    term,
+                    GoalContext {
+                        hole: next,
+                        vars: ctx.vars.clone(),
+                    },
+                )
+            }
+            (StrategyArg::Destruct(i), _)
+                if matches!(hyps[index(i)].as_ref(), logic::Prop::Exists(_, _)) =>
+            {
+                let i = &index(i);
+                let logic::Prop::Exists(x, _) = hyps[*i].as_ref() else {
+                    unreachable!()
+                };
+                let (y, next) = (record.fresh(), record.fresh());
+                let mut vars = ctx.vars.clone();
+                vars[*i] = y;
+                (
+                    Term::Unpack(
+                        var(&ctx, *i),
+                        eigenvariable(x, &goal, &hyps),
+                        y,
+                        Rc::new(Term::Hole(next)),
+                    ),
+                    GoalContext { hole: next, vars },
+                )
+            }
+            (StrategyArg::Destruct(i), _) => {
+                let i = &index(i);
+                let (y, z) = (record.fresh(), record.fresh());
+                let (h1, h2) = (record.fresh(), record.fresh());
+                let mut vars_a = ctx.vars.clone();
+                let mut vars_b = ctx.vars.clone();
+                vars_a[*i] = y;
+                vars_b[*i] = z;
+                record.contexts.push(GoalContext {
+                    hole: h2,
+                    vars: vars_b,
+                });
+                (
+                    Term::Case(
+                        var(&ctx, *i),
+                        y,
+                        Rc::new(Term::Hole(h1)),
+                        z,
+                        Rc::new(Term::Hole(h2)),
+                    ),
+                    GoalContext {
+                        hole: h1,
+                        vars: vars_a,
+                    },
+                )
+            }
+            (StrategyArg::FalseIsHyp, _) => {
+                let i = hyps
+                    .iter()
+                    .position(|hyp| *hyp.as_ref() == logic::Prop::False)
+                    .unwrap();
+                // the goal is now T, its new hole is never read
+                (
+                    Term::Absurd(var(&ctx, i), goal.clone()),
+                    GoalContext {
+                        hole: record.fresh(),
+                        vars: ctx.vars.clone(),
+                    },
+                )
+            }
+            (StrategyArg::Exact(i), _) => (
+                Term::Var(ctx.vars[index(i)]),
+                GoalContext {
+                    hole: record.fresh(),
+                    vars: ctx.vars.clone(),
+                },
+            ),
+            (StrategyArg::Apply(i), _) => {
+                let i = &index(i);
+                let next = record.fresh();
+                (
+                    Term::App(var(&ctx, *i), Rc::new(Term::Hole(next))),
+                    GoalContext {
+                        hole: next,
+                        vars: ctx.vars.clone(),
+                    },
+                )
+            }
+            (StrategyArg::ApplyIn(i, j, keep_old), _) => {
+                let (i, j) = (&index(i), &index(j));
+                let (y, next) = (record.fresh(), record.fresh());
+                let mut vars = ctx.vars.clone();
+                if *keep_old {
+                    vars.push(y);
+                } else {
+                    vars[*i] = y;
+                }
+                (
+                    Term::Let(
+                        y,
+                        Rc::new(Term::App(var(&ctx, *j), var(&ctx, *i))),
+                        Rc::new(Term::Hole(next)),
+                    ),
+                    GoalContext { hole: next, vars },
+                )
+            }
+            (StrategyArg::ImplyLeft(i), _) => {
+                let i = &index(i);
+                let x = var(&ctx, *i);
+                let (y, next) = (record.fresh(), record.fresh());
+                let logic::Prop::Implies(a, _) = hyps[*i].as_ref() else {
+                    unreachable!("strategy was applied to a hypothesis of the wrong shape")
+                };
+                let mut vars = ctx.vars.clone();
+                vars[*i] = y;
+                let lambda =
+                    |v: usize, p: &PropRef, t: Term| Term::Lambda(v, p.clone(), Rc::new(t));
+                let app = |t: Rc<Term>, u: Term| Term::App(t, Rc::new(u));
+                let let_in = |v: usize, u: Term, t: Term| Term::Let(v, Rc::new(u), Rc::new(t));
+                let term = match a.as_ref() {
+                    logic::Prop::True => let_in(y, app(x, Term::Unit), Term::Hole(next)),
+                    logic::Prop::False => let_in(y, Term::Unit, Term::Hole(next)),
+                    logic::Prop::And(p, q) => {
+                        // y = λu: p. λv: q. x <u, v>
+                        let (u, v) = (record.fresh(), record.fresh());
+                        let pair = Term::Pair(Rc::new(Term::Var(u)), Rc::new(Term::Var(v)));
+                        let curried = lambda(u, p, lambda(v, q, app(x, pair)));
+                        let_in(y, curried, Term::Hole(next))
+                    }
+                    logic::Prop::Or(p, q) => {
+                        // y = λu: p. x (inl u), z = λv: q. x (inr v)
+                        let (z, u, v) = (record.fresh(), record.fresh(), record.fresh());
+                        vars.push(z);
+                        let inl = Term::Inl(Rc::new(Term::Var(u)), q.clone());
+                        let inr = Term::Inr(p.clone(), Rc::new(Term::Var(v)));
+                        let_in(
+                            y,
+                            lambda(u, p, app(x.clone(), inl)),
+                            let_in(z, lambda(v, q, app(x, inr)), Term::Hole(next)),
+                        )
+                    }
+                    logic::Prop::Implies(p, q) => {
+                        // k = x (λw: p. let y = λb: q. x (λ_: p. b) in ?next), and
+                        // the second goal is proven with k: c in place of x
+                        let (k, w, b, unused) = (
+                            record.fresh(),
+                            record.fresh(),
+                            record.fresh(),
+                            record.fresh(),
+                        );
+                        let h2 = record.fresh();
+                        let mut vars_c = ctx.vars.clone();
+                        vars_c[*i] = k;
+                        record.contexts.push(GoalContext {
+                            hole: h2,
+                            vars: vars_c,
+                        });
+                        vars.push(w);
+                        let constant = lambda(unused, p, Term::Var(b));
+                        let y_term = lambda(b, q, app(x.clone(), constant));
+                        let body = let_in(y, y_term, Term::Hole(next));
+                        let_in(k, app(x, lambda(w, p, body)), Term::Hole(h2))
+                    }
+                    logic::Prop::Name(_)
+                    | logic::Prop::Pred(_, _)
+                    | logic::Prop::Not(_)
+                    | logic::Prop::Iff(_, _)
+                    | logic::Prop::Forall(_, _)
+                    | logic::Prop::Exists(_, _) => {
+                        unreachable!("strategy was applied to a hypothesis of the wrong shape")
+                    }
+                };
+                (term, GoalContext { hole: next, vars })
+            }
+            (StrategyArg::Instantiate(i, t), _) => {
+                let i = &index(i);
+                let (y, next) = (record.fresh(), record.fresh());
+                let mut vars = ctx.vars.clone();
+                vars.push(y);
+                (
+                    Term::Let(
+                        y,
+                        Rc::new(Term::Inst(var(&ctx, *i), t.clone())),
+                        Rc::new(Term::Hole(next)),
+                    ),
+                    GoalContext { hole: next, vars },
+                )
+            }
+            (StrategyArg::ByContradiction, _) => {
+                // case em goal of inl x -> x | inr y -> absurd ?next
+                let (x, y, next) = (record.fresh(), record.fresh(), record.fresh());
+                let mut vars = ctx.vars.clone();
+                vars.push(y);
+                (
+                    Term::Case(
+                        Rc::new(Term::ExcludedMiddle(goal.clone())),
+                        x,
+                        Rc::new(Term::Var(x)),
+                        y,
+                        Rc::new(Term::Absurd(Rc::new(Term::Hole(next)), goal.clone())),
+                    ),
+                    GoalContext { hole: next, vars },
+                )
             }
+            (StrategyArg::ExcludedMiddle(p), _) => {
+                let (y, next) = (record.fresh(), record.fresh());
+                let mut vars = ctx.vars.clone();
+                vars.push(y);
+                (
+                    Term::Let(
+                        y,
+                        Rc::new(Term::ExcludedMiddle(p.clone())),
+                        Rc::new(Term::Hole(next)),
+                    ),
+                    GoalContext { hole: next, vars },
+                )
+            }
+            (StrategyArg::Use(name, subst), _) => {
+                let subst = subst.iter().cloned().collect();
+                let lemma = self.lemmas[name].term.instance(&subst, record);
+                let (y, next) = (record.fresh(), record.fresh());
+                let mut vars = ctx.vars.clone();
+                vars.push(y);
+                (
+                    Term::Let(y, Rc::new(lemma), Rc::new(Term::Hole(next))),
+                    GoalContext { hole: next, vars },
+                )
+            }
+            (StrategyArg::Witness(t), logic::Prop::Exists(_, _)) => {
+                let next = record.fresh();
+                (
+                    Term::Witness(t.clone(), Rc::new(Term::Hole(next)), goal.clone()),
+                    GoalContext {
+                        hole: next,
+                        vars: ctx.vars.clone(),
+                    },
+                )
+            }
+            _ => unreachable!("strategy was applied to a goal of the wrong shape"),
+        };
+        record.fill(hole, term);
+        record.contexts[active] = new_ctx;
+    }
+
+    // proof terms of every goal added to the proof, with '?n' holes
+    // standing for the parts that are still to be proven
+    pub fn proof_terms(&self) -> Vec<(terms::Theorem, Term)> {
+        let Some(record) = self.record.as_ref() else {
+            return vec![];
+        };
+        let mut record = record.clone();
+        for (goal, ctx) in self.goals.iter().zip(record.contexts.clone()) {
+            if *goal.0.as_ref() == logic::Prop::True && !record.is_filled(ctx.hole) {
+                record.fill(ctx.hole, Term::Unit);
+            }
+        }
+        record.theorem_terms()
+    }
+
+    // only available once every goal has been closed
+    pub fn finished_terms(&self) -> Option<Vec<(terms::Theorem, Term)>> {
+        let terms = self.proof_terms();
+        if terms.iter().all(|(_, term)| term.is_complete()) {
+            Some(terms)
+        } else {
+            None
+        }
+    }
+
+    // one natural deduction tree per goal added to the proof, goals still
+    // open appearing as unproven leaves
+    pub fn derivations(&self) -> Vec<Derivation> {
+        let Some(record) = self.record.as_ref() else {
+            return vec![];
         };
-        Err("Strategy could not be applied")
+        let holes: HashMap<usize, PropRef> = record
+            .contexts
+            .iter()
+            .zip(self.goals.iter())
+            .map(|(ctx, goal)| (ctx.hole, goal.0.clone()))
+            .collect();
+        self.proof_terms()
+            .iter()
+            .filter_map(|(theorem, term)| Derivation::from_term(theorem, term, &holes).ok())
+            .collect()
     }
 
     pub fn get_applicable_strategies(&self) -> Vec<(usize, usize, StrategyArg)> {
         let mut result: Vec<(usize, usize, StrategyArg)> = vec![];
         // elts in list with syntax (prio: usize, goalnum: usize, cmd: string, arg1: usize, arg2: usize])
         for (index_goal, goal) in self.goals.iter().enumerate() {
+            let names = self.hyp_names(index_goal);
+            // instances are only tried for the individuals the sequent talks
+            // about, or for an arbitrary one when it talks about none
+            let vars = sequent_vars(&goal.0, &goal.1);
+            let candidates = |x: &str| -> Vec<FoTerm> {
+                if vars.is_empty() {
+                    vec![FoTerm::Var(String::from(x))]
+                } else {
+                    vars.iter().map(|y| FoTerm::Var(y.clone())).collect()
+                }
+            };
+            // unfolding loses nothing, the other strategies then apply
+            let unfoldings = |prop: &logic::Prop, target: Option<String>| {
+                let mut result = vec![];
+                if prop.unfold_not() != *prop {
+                    result.push((2, index_goal, StrategyArg::UnfoldNot(target.clone())));
+                }
+                if prop.unfold_iff() != *prop {
+                    result.push((2, index_goal, StrategyArg::UnfoldIff(target)));
+                }
+                result
+            };
             match goal.0.as_ref() {
                 logic::Prop::True => continue,
                 logic::Prop::False => (),
-                logic::Prop::Name(_) => (),
-                logic::Prop::Implies(_, _) => result.push((3, index_goal, StrategyArg::Intro)),
+                logic::Prop::Name(_)
+                | logic::Prop::Pred(_, _)
+                | logic::Prop::Not(_)
+                | logic::Prop::Iff(_, _) => (),
+                logic::Prop::Implies(_, _) | logic::Prop::Forall(_, _) => {
+                    result.push((3, index_goal, StrategyArg::Intro(None)))
+                }
+                logic::Prop::Exists(x, _) => {
+                    for t in candidates(x) {
+                        result.push((4, index_goal, StrategyArg::Witness(t)));
+                    }
+                }
                 logic::Prop::And(_, _) => result.push((3, index_goal, StrategyArg::Split)),
                 logic::Prop::Or(a, b) => {
                     if *a.as_ref() == logic::Prop::False {
@@ -262,47 +1380,86 @@ impl Proof {
                     }
                 }
             };
-            let num_hyps = goal.1.len();
+            result.extend(unfoldings(&goal.0, None));
+            if self.logic == logic::Logic::Classical {
+                if *goal.0.as_ref() != logic::Prop::False {
+                    result.push((4, index_goal, StrategyArg::ByContradiction));
+                }
+                for p in excluded_middle_candidates(&goal.0, &goal.1) {
+                    result.push((4, index_goal, StrategyArg::ExcludedMiddle(p)));
+                }
+            }
             for (index, hyp) in goal.1.iter().enumerate() {
+                let name = || names[index].clone();
+                result.extend(unfoldings(hyp, Some(name())));
                 match hyp.as_ref() {
                     logic::Prop::True => {}
-                    logic::Prop::False => result.push((0, index_goal, StrategyArg::FalseIsHyp)),
-                    logic::Prop::Name(_) => {}
+                    logic::Prop::False if self.logic != logic::Logic::Minimal => {
+                        result.push((0, index_goal, StrategyArg::FalseIsHyp))
+                    }
+                    logic::Prop::False => {}
+                    logic::Prop::Name(_)
+                    | logic::Prop::Pred(_, _)
+                    | logic::Prop::Not(_)
+                    | logic::Prop::Iff(_, _) => {}
+                    logic::Prop::Forall(x, a) => {
+                        for t in candidates(x) {
+                            if !goal.1.contains(&instance(x, a, &t)) {
+                                result.push((4, index_goal, StrategyArg::Instantiate(name(), t)));
+                            }
+                        }
+                    }
+                    logic::Prop::Exists(_, _) => {
+                        result.push((2, index_goal, StrategyArg::Destruct(name())))
+                    }
                     logic::Prop::Implies(a, b) => {
-                        if b.as_ref() == goal.0.as_ref() {
+                        match a.as_ref() {
+                            logic::Prop::True | logic::Prop::And(_, _) | logic::Prop::Or(_, _) => {
+                                result.push((3, index_goal, StrategyArg::ImplyLeft(name())))
+                            }
+                            logic::Prop::Implies(_, _) => {
+                                result.push((4, index_goal, StrategyArg::ImplyLeft(name())))
+                            }
+                            logic::Prop::False
+                            | logic::Prop::Name(_)
+                            | logic::Prop::Pred(_, _)
+                            | logic::Prop::Not(_)
+                            | logic::Prop::Iff(_, _)
+                            | logic::Prop::Forall(_, _)
+                            | logic::Prop::Exists(_, _) => (),
+                        }
+                        if b.alpha_eq(&goal.0) {
                             if goal.1.contains(a) {
-                                result.push((2, index_goal, StrategyArg::Apply(index)));
+                                result.push((2, index_goal, StrategyArg::Apply(name())));
                             } else {
-                                result.push((4, index_goal, StrategyArg::Apply(index)));
+                                result.push((4, index_goal, StrategyArg::Apply(name())));
                             }
                         }
                     }
                     logic::Prop::And(_, _) => {
-                        result.push((4, index_goal, StrategyArg::HypSplit(index)));
+                        result.push((4, index_goal, StrategyArg::HypSplit(name())));
                     }
                     logic::Prop::Or(a, b) => {
-                        if *a.as_ref() == logic::Prop::False {
-                            result.push((2, index_goal, StrategyArg::HypOrSplit(index, true)));
-                            result.push((4, index_goal, StrategyArg::HypOrSplit(index, false)));
-                        } else if *b.as_ref() == logic::Prop::False {
-                            result.push((4, index_goal, StrategyArg::HypOrSplit(index, true)));
-                            result.push((2, index_goal, StrategyArg::HypOrSplit(index, false)));
+                        if *a.as_ref() == logic::Prop::False || *b.as_ref() == logic::Prop::False {
+                            result.push((2, index_goal, StrategyArg::Destruct(name())));
                         } else {
-                            result.push((4, index_goal, StrategyArg::HypOrSplit(index, true)));
-                            result.push((4, index_goal, StrategyArg::HypOrSplit(index, false)));
+                            result.push((4, index_goal, StrategyArg::Destruct(name())));
                         }
                     }
                 };
-                if hyp.as_ref() == goal.0.as_ref() {
-                    result.push((1, index_goal, StrategyArg::Exact(index)));
-                }
-                for i in 0..num_hyps {
-                    if i != index {
-                        if let logic::Prop::Implies(a, _) = goal.1[i].as_ref() {
-                            if a.as_ref() == hyp.as_ref() {
-                                result.push((4, index_goal, StrategyArg::ApplyIn(index, i, true)))
-                            }
-                        }
+                if hyp.alpha_eq(&goal.0) {
+                    result.push((1, index_goal, StrategyArg::Exact(name())));
+                }
+                for (i, other) in goal.1.iter().enumerate() {
+                    if i != index
+                        && let logic::Prop::Implies(a, _) = other.as_ref()
+                        && a.alpha_eq(hyp)
+                    {
+                        result.push((
+                            4,
+                            index_goal,
+                            StrategyArg::ApplyIn(name(), names[i].clone(), true),
+                        ))
                     }
                 }
             }
@@ -315,33 +1472,142 @@ impl Proof {
 impl fmt::Display for StrategyArg {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            StrategyArg::Intro => write!(f, "intro"),
+            StrategyArg::Intro(None) => write!(f, "intro"),
+            StrategyArg::Intro(Some(name)) => write!(f, "intro {}", name),
             StrategyArg::Split => write!(f, "split"),
             StrategyArg::HypSplit(arg1) => {
                 write!(f, "hyp_split {}", arg1)
             }
             StrategyArg::OrSplit(true) => write!(f, "left"),
             StrategyArg::OrSplit(false) => write!(f, "right"),
-            StrategyArg::HypOrSplit(arg1, true) => {
-                write!(f, "hyp_left {}", arg1)
+            StrategyArg::Destruct(arg1) => write!(f, "destruct {}", arg1),
+            StrategyArg::FalseIsHyp => write!(f, "false"),
+            StrategyArg::Exact(arg1) => write!(f, "exact {}", arg1),
+            StrategyArg::Apply(arg1) => write!(f, "apply {}", arg1),
+            StrategyArg::ApplyIn(arg1, arg2, true) => write!(f, "apply_in_keep {} {}", arg1, arg2),
+            StrategyArg::ApplyIn(arg1, arg2, false) => write!(f, "apply_in {} {}", arg1, arg2),
+            StrategyArg::ImplyLeft(arg1) => write!(f, "imply_left {}", arg1),
+            StrategyArg::UnfoldNot(None) => write!(f, "unfold_not"),
+            StrategyArg::UnfoldNot(Some(arg1)) => write!(f, "unfold_not {}", arg1),
+            StrategyArg::UnfoldIff(None) => write!(f, "unfold_iff"),
+            StrategyArg::UnfoldIff(Some(arg1)) => write!(f, "unfold_iff {}", arg1),
+            StrategyArg::Instantiate(arg1, t) => write!(f, "instantiate {} {}", arg1, t),
+            StrategyArg::Witness(t) => write!(f, "exists {}", t),
+            StrategyArg::ByContradiction => write!(f, "by_contradiction"),
+            StrategyArg::ExcludedMiddle(p) => write!(f, "excluded_middle {}", p.to_string()),
+            StrategyArg::Use(name, subst) if subst.is_empty() => write!(f, "use {}", name),
+            StrategyArg::Use(name, subst) => {
+                let subst: Vec<String> = subst
+                    .iter()
+                    .map(|(atom, p)| format!("{} := {}", atom, p.to_string()))
+                    .collect();
+                write!(f, "use {} with {}", name, subst.join(", "))
             }
-            StrategyArg::HypOrSplit(arg1, false) => {
-                write!(f, "hyp_right {}", arg1)
+        }
+    }
+}
+
+// recorded terms are history, two proofs in the same state are equal
+impl PartialEq for Proof {
+    fn eq(&self, other: &Proof) -> bool {
+        self.goals == other.goals
+            && self.active_goal == other.active_goal
+            && self.logic == other.logic
+    }
+}
+
+impl Eq for Proof {}
+
+impl Hash for Proof {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.goals.hash(state);
+        self.active_goal.hash(state);
+        self.logic.hash(state);
+    }
+}
+
+impl fmt::Display for PropShape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PropShape::Implies => write!(f, "an implication"),
+            PropShape::CompoundImplies => write!(f, "an implication with a compound premise"),
+            PropShape::And => write!(f, "a conjunction"),
+            PropShape::Or => write!(f, "a disjunction"),
+            PropShape::WithNot => write!(f, "a proposition containing a negation"),
+            PropShape::WithIff => write!(f, "a proposition containing a biconditional"),
+            PropShape::Forall => write!(f, "a universal quantification"),
+            PropShape::Exists => write!(f, "an existential quantification"),
+        }
+    }
+}
+
+impl fmt::Display for StrategyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StrategyError::NoGoals => write!(f, "no goal to execute strategy on"),
+            StrategyError::GoalOutOfBounds { index, len } => write!(
+                f,
+                "goal {} does not exist, there are {} goal(s)",
+                index + 1,
+                len
+            ),
+            StrategyError::UnknownHyp(name) => {
+                write!(f, "the active goal has no hypothesis named {}", name)
             }
-            StrategyArg::FalseIsHyp => {
-                write!(f, "false_is_hyp")
+            StrategyError::NameTaken(name) => write!(f, "the name {} is already taken", name),
+            StrategyError::InvalidName(name) => write!(
+                f,
+                "'{}' is not a name, use letters, digits and _ other than T, F, forall and exists",
+                name
+            ),
+            StrategyError::GoalShapeMismatch { expected, found } => write!(
+                f,
+                "goal should be {}, found {}",
+                expected,
+                found.to_string()
+            ),
+            StrategyError::HypShapeMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "hypothesis {} should be {}, found {}",
+                name,
+                expected,
+                found.to_string()
+            ),
+            StrategyError::PremiseMismatch { expected, found } => write!(
+                f,
+                "expected {}, found {}",
+                expected.to_string(),
+                found.to_string()
+            ),
+            StrategyError::NoFalseHyp => write!(f, "no hypothesis is F"),
+            StrategyError::UnknownLemma(name) => write!(f, "no lemma named {}", name),
+            StrategyError::CapturingSubstitution(atom) => write!(
+                f,
+                "the proposition substituted for {} cannot have free variables",
+                atom
+            ),
+            StrategyError::NotInLogic(logic) => {
+                write!(f, "strategy not available in {} logic", logic)
             }
-            StrategyArg::Exact(arg1) => write!(f, "exact {}", arg1),
-            StrategyArg::Apply(arg1) => write!(f, "apply {}", arg1),
-            StrategyArg::ApplyIn(arg1, arg2, _) => write!(f, "apply_in_hyp_keep {} {}", arg1, arg2),
         }
     }
 }
 
+impl Error for StrategyError {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use logic::Prop;
+    use crate::libpoulet::kernel;
+    use logic::{Logic, Prop};
+
+    fn h(n: usize) -> String {
+        format!("H{}", n)
+    }
 
     #[test]
     fn new() {
@@ -350,17 +1616,100 @@ mod tests {
             new_proof,
             Proof {
                 goals: vec![],
-                active_goal: 0
+                active_goal: 0,
+                record: None,
+                logic: Logic::Intuitionistic,
+                hyp_names: vec![],
+                hyp_aliases: vec![],
+                lemmas: Rc::default(),
             }
         );
         assert_eq!(new_proof.number_of_goals(), 0);
         assert_eq!(new_proof.active_goal_index(), 0);
-        assert_eq!(new_proof.set_active_goal(0), Err("Out of bounds"));
-        assert_eq!(new_proof.set_active_goal(1), Err("Out of bounds"));
         assert_eq!(
-            new_proof.execute(&StrategyArg::Intro),
-            Err("No goal to execute strategy on.")
+            new_proof.set_active_goal(0),
+            Err(StrategyError::GoalOutOfBounds { index: 0, len: 0 })
+        );
+        assert_eq!(
+            new_proof.set_active_goal(1),
+            Err(StrategyError::GoalOutOfBounds { index: 1, len: 0 })
+        );
+        assert_eq!(
+            new_proof.execute(&StrategyArg::Intro(None)),
+            Err(StrategyError::NoGoals)
+        );
+    }
+
+    #[test]
+    fn dimacs() {
+        let path = std::env::temp_dir().join(format!("poulet_dimacs_{}.cnf", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut proof = Proof::new();
+        assert_eq!(
+            proof.to_dimacs(path),
+            Err(String::from("no goal to export"))
+        );
+        proof.add_goal_from_prop(Prop::parse_infix("b").unwrap());
+        proof.add_hyp_from_prop(Prop::parse_infix("a ^ (a => b)").unwrap());
+        assert_eq!(proof.to_dimacs(path), Ok(()));
+
+        // the exported clauses are unsatisfiable, so their negation is provable
+        let imported = Proof::from_dimacs(path).unwrap();
+        let (goal, hyps) = &imported.goals[0];
+        assert!(hyps.is_empty());
+        assert!(sat::classically_valid(&[], goal));
+        let text = std::fs::read_to_string(path).unwrap();
+        assert!(text.contains("c atom 1 a\n") && text.contains("c atom 2 b\n"));
+
+        std::fs::write(path, "p cnf 1 2\n1 0\n").unwrap();
+        assert_eq!(
+            Proof::from_dimacs(path),
+            Err(format!(
+                "Error while parsing file '{}': header declares 2 clauses, found 1",
+                path
+            ))
+        );
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn tptp() {
+        let path = std::env::temp_dir().join(format!("poulet_tptp_{}.p", std::process::id()));
+        let path = path.to_str().unwrap();
+        std::fs::write(
+            path,
+            "% SYJ-like problem\nfof(a1, axiom, p | q).\nfof(a2, axiom, p => r).\n\
+             fof(a3, axiom, q => r).\nfof(c, conjecture, r & (p | q)).\n",
+        )
+        .unwrap();
+        let imported = Proof::from_tptp(path).unwrap();
+        assert_eq!(imported.goals.len(), 1);
+        let (goal, hyps) = &imported.goals[0];
+        assert_eq!(**goal, Prop::parse_infix("r ^ (p | q)").unwrap());
+        assert_eq!(hyps.len(), 3);
+
+        assert_eq!(imported.to_tptp(path), Ok(()));
+        let again = Proof::from_tptp(path).unwrap();
+        assert_eq!(again.goals, imported.goals);
+
+        std::fs::write(path, "fof(a, axiom, p & ~p).").unwrap();
+        let contradiction = Proof::from_tptp(path).unwrap();
+        assert_eq!(*contradiction.goals[0].0, Prop::False);
+        assert_eq!(contradiction.goals[0].1.len(), 1);
+
+        std::fs::write(path, "fof(a, axiom, p & ~p)").unwrap();
+        assert_eq!(
+            Proof::from_tptp(path),
+            Err(format!(
+                "Error while parsing file '{}', line 1: unexpected end of input",
+                path
+            ))
+        );
+        assert_eq!(
+            Proof::new().to_tptp(path),
+            Err(String::from("no goal to export"))
         );
+        let _ = std::fs::remove_file(path);
     }
 
     #[test]
@@ -371,8 +1720,11 @@ mod tests {
         assert_eq!(proof.number_of_goals(), 1);
         assert_eq!(proof.active_goal_index(), 0);
         assert_eq!(proof.set_active_goal(0), Ok(()));
-        assert_eq!(proof.set_active_goal(1), Err("Out of bounds"));
-        assert_eq!(proof.goals, vec![(Rc::new(Prop::True), vec![])]);
+        assert_eq!(
+            proof.set_active_goal(1),
+            Err(StrategyError::GoalOutOfBounds { index: 1, len: 1 })
+        );
+        assert_eq!(proof.goals, vec![(PropRef::new(Prop::True), vec![])]);
 
         proof.add_goal_from_prop(Prop::False);
         assert_eq!(proof.number_of_goals(), 2);
@@ -382,8 +1734,8 @@ mod tests {
         assert_eq!(
             proof.goals,
             vec![
-                (Rc::new(Prop::True), vec![]),
-                (Rc::new(Prop::False), vec![])
+                (PropRef::new(Prop::True), vec![]),
+                (PropRef::new(Prop::False), vec![])
             ]
         );
     }
@@ -392,54 +1744,168 @@ mod tests {
     fn clean() {
         let mut proof = Proof {
             goals: vec![
-                (Rc::new(Prop::False), vec![]),
-                (Rc::new(Prop::True), vec![]),
-                (Rc::new(Prop::from_name(String::from("name"))), vec![]),
-                (Rc::new(Prop::True), vec![]),
+                (PropRef::new(Prop::False), vec![]),
+                (PropRef::new(Prop::True), vec![]),
+                (PropRef::new(Prop::from_name(String::from("name"))), vec![]),
+                (PropRef::new(Prop::True), vec![]),
                 (
-                    Rc::new(Prop::imply(Prop::True, Prop::False)),
+                    PropRef::new(Prop::imply(Prop::True, Prop::False)),
                     vec![
-                        Rc::new(Prop::from_name(String::from("b"))),
-                        Rc::new(Prop::from_name(String::from("a"))),
-                        Rc::new(Prop::True),
+                        PropRef::new(Prop::from_name(String::from("b"))),
+                        PropRef::new(Prop::from_name(String::from("a"))),
+                        PropRef::new(Prop::True),
                     ],
                 ),
                 (
-                    Rc::new(Prop::False),
+                    PropRef::new(Prop::False),
                     vec![
-                        Rc::new(Prop::from_name(String::from("b"))),
-                        Rc::new(Prop::from_name(String::from("a"))),
-                        Rc::new(Prop::True),
+                        PropRef::new(Prop::from_name(String::from("b"))),
+                        PropRef::new(Prop::from_name(String::from("a"))),
+                        PropRef::new(Prop::True),
                     ],
                 ),
             ],
             active_goal: 0,
+            record: None,
+            logic: Logic::Intuitionistic,
+            hyp_names: vec![],
+            hyp_aliases: vec![],
+            lemmas: Rc::default(),
         };
         let cleaned_proof = Proof {
             goals: vec![
-                (Rc::new(Prop::from_name(String::from("name"))), vec![]),
+                (PropRef::new(Prop::from_name(String::from("name"))), vec![]),
                 (
-                    Rc::new(Prop::imply(Prop::True, Prop::False)),
+                    PropRef::new(Prop::imply(Prop::True, Prop::False)),
                     vec![
-                        Rc::new(Prop::from_name(String::from("a"))),
-                        Rc::new(Prop::from_name(String::from("b"))),
+                        PropRef::new(Prop::from_name(String::from("a"))),
+                        PropRef::new(Prop::from_name(String::from("b"))),
                     ],
                 ),
-                (Rc::new(Prop::False), vec![]),
+                (PropRef::new(Prop::False), vec![]),
                 (
-                    Rc::new(Prop::False),
+                    PropRef::new(Prop::False),
                     vec![
-                        Rc::new(Prop::from_name(String::from("a"))),
-                        Rc::new(Prop::from_name(String::from("b"))),
+                        PropRef::new(Prop::from_name(String::from("a"))),
+                        PropRef::new(Prop::from_name(String::from("b"))),
                     ],
                 ),
             ],
             active_goal: 0,
+            record: None,
+            logic: Logic::Intuitionistic,
+            hyp_names: vec![],
+            hyp_aliases: vec![],
+            lemmas: Rc::default(),
         };
 
         proof.clean();
 
         assert_eq!(proof, cleaned_proof);
+        assert_eq!(proof.hyp_names(3), [h(1), h(0)]);
+    }
+
+    #[test]
+    fn hyp_names() {
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::parse_infix("c => (a ^ b) => b").unwrap());
+        proof.add_hyp_from_prop(Prop::parse_infix("T").unwrap());
+        assert_eq!(proof.execute(&StrategyArg::Intro(None)), Ok(()));
+        assert_eq!(
+            proof.execute(&StrategyArg::Intro(Some(h(1)))),
+            Err(StrategyError::NameTaken(h(1)))
+        );
+        assert_eq!(
+            proof.execute(&StrategyArg::Intro(Some(String::from("hab")))),
+            Ok(())
+        );
+        assert_eq!(proof.hyp_names(0), [h(0), h(1), String::from("hab")]);
+        for name in ["x:y", "T", "", "h'"] {
+            assert_eq!(
+                proof.execute(&StrategyArg::Intro(Some(String::from(name)))),
+                Err(StrategyError::InvalidName(String::from(name)))
+            );
+        }
+        assert_eq!(
+            StrategyArg::Intro(Some(String::from("hab"))).to_string(),
+            "intro hab"
+        );
+
+        // cleaning drops T and reorders the rest, names stay attached
+        proof.clean();
+        assert_eq!(proof.goals[0].1[1].to_string(), "( a ^ b )");
+        assert_eq!(proof.hyp_names(0), [h(1), String::from("hab")]);
+        assert_eq!(
+            proof.execute(&StrategyArg::HypSplit(String::from("hab"))),
+            Ok(())
+        );
+        assert_eq!(proof.hyp_names(0), [h(1), String::from("hab"), h(0)]);
+        assert_eq!(proof.execute(&StrategyArg::Exact(h(0))), Ok(()));
+        assert!(proof.finished_terms().is_some());
+
+        // the name of a duplicate dropped by cleaning still finds the
+        // hypothesis that was kept, with or without proof terms
+        for recorded in [true, false] {
+            let mut proof = Proof::new();
+            if !recorded {
+                proof.record = None;
+            }
+            proof.add_goal_from_prop(Prop::parse_infix("a => a => b => a").unwrap());
+            for name in ["h1", "h2"] {
+                let intro = StrategyArg::Intro(Some(String::from(name)));
+                assert_eq!(proof.execute(&intro), Ok(()));
+            }
+            proof.clean();
+            assert_eq!(proof.hyp_names(0), [String::from("h1")]);
+            assert_eq!(
+                proof.execute(&StrategyArg::Intro(Some(String::from("h2")))),
+                Err(StrategyError::NameTaken(String::from("h2")))
+            );
+            assert_eq!(proof.execute(&StrategyArg::Intro(None)), Ok(()));
+            assert_eq!(proof.hyp_names(0), [String::from("h1"), h(0)]);
+            assert_eq!(
+                proof.execute(&StrategyArg::Exact(String::from("h3"))),
+                Err(StrategyError::UnknownHyp(String::from("h3")))
+            );
+            assert_eq!(
+                proof.execute(&StrategyArg::Exact(String::from("h2"))),
+                Ok(())
+            );
+            if recorded {
+                let terms = proof.finished_terms().unwrap();
+                assert_eq!(kernel::check_theorem(&terms[0].0, &terms[0].1), Ok(()));
+            }
+        }
+
+        let path = std::env::temp_dir().join(format!("poulet_names_{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::parse_infix("forall x. P(x) => P(x)").unwrap());
+        assert_eq!(
+            proof.execute(&StrategyArg::Intro(Some(String::from("y")))),
+            Ok(())
+        );
+        assert_eq!(proof.goals[0].0.to_string(), "( P(y) => P(y) )");
+        assert_eq!(
+            proof.execute(&StrategyArg::Intro(Some(String::from("hp")))),
+            Ok(())
+        );
+        assert_eq!(proof.to_file(path), Ok(()));
+        let loaded = Proof::from_file(path).unwrap();
+        assert_eq!(loaded.goals, proof.goals);
+        assert_eq!(loaded.hyp_names(0), [String::from("hp")]);
+        std::fs::write(path, "G:a\nH:a\nH:H0:a\n").unwrap();
+        assert!(Proof::from_file(path).is_err());
+        std::fs::write(path, "G:a\nH::a\n").unwrap();
+        assert_eq!(
+            Proof::from_file(path),
+            Err(format!(
+                "Error while reading file '{}', line 2: {}",
+                path,
+                StrategyError::InvalidName(String::new())
+            ))
+        );
+        let _ = std::fs::remove_file(path);
     }
 
     #[test]
@@ -447,24 +1913,24 @@ mod tests {
         let mut proof_before = Proof {
             goals: vec![
                 (
-                    Rc::new(Prop::imply(
+                    PropRef::new(Prop::imply(
                         Prop::from_name(String::from("a")),
                         Prop::from_name(String::from("b")),
                     )),
                     vec![],
                 ),
-                (Rc::new(Prop::True), vec![]),
-                (Rc::new(Prop::False), vec![]),
-                (Rc::new(Prop::from_name(String::from("name"))), vec![]),
+                (PropRef::new(Prop::True), vec![]),
+                (PropRef::new(Prop::False), vec![]),
+                (PropRef::new(Prop::from_name(String::from("name"))), vec![]),
                 (
-                    Rc::new(Prop::and(
+                    PropRef::new(Prop::and(
                         Prop::from_name(String::from("a")),
                         Prop::from_name(String::from("b")),
                     )),
                     vec![],
                 ),
                 (
-                    Rc::new(Prop::or(
+                    PropRef::new(Prop::or(
                         Prop::from_name(String::from("a")),
                         Prop::from_name(String::from("b")),
                     )),
@@ -472,26 +1938,31 @@ mod tests {
                 ),
             ],
             active_goal: 0,
+            record: None,
+            logic: Logic::Intuitionistic,
+            hyp_names: vec![],
+            hyp_aliases: vec![],
+            lemmas: Rc::default(),
         };
 
         let proof_after = Proof {
             goals: vec![
                 (
-                    Rc::new(Prop::from_name(String::from("b"))),
-                    vec![Rc::new(Prop::from_name(String::from("a")))],
+                    PropRef::new(Prop::from_name(String::from("b"))),
+                    vec![PropRef::new(Prop::from_name(String::from("a")))],
                 ),
-                (Rc::new(Prop::True), vec![]),
-                (Rc::new(Prop::False), vec![]),
-                (Rc::new(Prop::from_name(String::from("name"))), vec![]),
+                (PropRef::new(Prop::True), vec![]),
+                (PropRef::new(Prop::False), vec![]),
+                (PropRef::new(Prop::from_name(String::from("name"))), vec![]),
                 (
-                    Rc::new(Prop::and(
+                    PropRef::new(Prop::and(
                         Prop::from_name(String::from("a")),
                         Prop::from_name(String::from("b")),
                     )),
                     vec![],
                 ),
                 (
-                    Rc::new(Prop::or(
+                    PropRef::new(Prop::or(
                         Prop::from_name(String::from("a")),
                         Prop::from_name(String::from("b")),
                     )),
@@ -499,34 +1970,60 @@ mod tests {
                 ),
             ],
             active_goal: 0,
+            record: None,
+            logic: Logic::Intuitionistic,
+            hyp_names: vec![],
+            hyp_aliases: vec![],
+            lemmas: Rc::default(),
         };
 
-        assert_eq!(proof_before.execute(&StrategyArg::Intro), Ok(()));
+        assert_eq!(proof_before.execute(&StrategyArg::Intro(None)), Ok(()));
         assert_eq!(proof_before, proof_after);
         let _ = proof_before.set_active_goal(1);
         assert_eq!(
-            proof_before.execute(&StrategyArg::Intro),
-            Err("Strategy could not be applied")
+            proof_before.execute(&StrategyArg::Intro(None)),
+            Err(StrategyError::GoalShapeMismatch {
+                expected: PropShape::Implies,
+                found: Prop::True
+            })
         );
         let _ = proof_before.set_active_goal(2);
         assert_eq!(
-            proof_before.execute(&StrategyArg::Intro),
-            Err("Strategy could not be applied")
+            proof_before.execute(&StrategyArg::Intro(None)),
+            Err(StrategyError::GoalShapeMismatch {
+                expected: PropShape::Implies,
+                found: Prop::False
+            })
         );
         let _ = proof_before.set_active_goal(3);
         assert_eq!(
-            proof_before.execute(&StrategyArg::Intro),
-            Err("Strategy could not be applied")
+            proof_before.execute(&StrategyArg::Intro(None)),
+            Err(StrategyError::GoalShapeMismatch {
+                expected: PropShape::Implies,
+                found: Prop::from_name(String::from("name"))
+            })
         );
         let _ = proof_before.set_active_goal(4);
         assert_eq!(
-            proof_before.execute(&StrategyArg::Intro),
-            Err("Strategy could not be applied")
+            proof_before.execute(&StrategyArg::Intro(None)),
+            Err(StrategyError::GoalShapeMismatch {
+                expected: PropShape::Implies,
+                found: Prop::and(
+                    Prop::from_name(String::from("a")),
+                    Prop::from_name(String::from("b"))
+                )
+            })
         );
         let _ = proof_before.set_active_goal(5);
         assert_eq!(
-            proof_before.execute(&StrategyArg::Intro),
-            Err("Strategy could not be applied")
+            proof_before.execute(&StrategyArg::Intro(None)),
+            Err(StrategyError::GoalShapeMismatch {
+                expected: PropShape::Implies,
+                found: Prop::or(
+                    Prop::from_name(String::from("a")),
+                    Prop::from_name(String::from("b"))
+                )
+            })
         );
     }
 
@@ -535,24 +2032,24 @@ mod tests {
         let mut proof_before = Proof {
             goals: vec![
                 (
-                    Rc::new(Prop::and(
+                    PropRef::new(Prop::and(
                         Prop::from_name(String::from("a")),
                         Prop::from_name(String::from("b")),
                     )),
                     vec![],
                 ),
-                (Rc::new(Prop::True), vec![]),
-                (Rc::new(Prop::False), vec![]),
-                (Rc::new(Prop::from_name(String::from("name"))), vec![]),
+                (PropRef::new(Prop::True), vec![]),
+                (PropRef::new(Prop::False), vec![]),
+                (PropRef::new(Prop::from_name(String::from("name"))), vec![]),
                 (
-                    Rc::new(Prop::imply(
+                    PropRef::new(Prop::imply(
                         Prop::from_name(String::from("a")),
                         Prop::from_name(String::from("b")),
                     )),
                     vec![],
                 ),
                 (
-                    Rc::new(Prop::or(
+                    PropRef::new(Prop::or(
                         Prop::from_name(String::from("a")),
                         Prop::from_name(String::from("b")),
                     )),
@@ -560,68 +2057,105 @@ mod tests {
                 ),
             ],
             active_goal: 0,
+            record: None,
+            logic: Logic::Intuitionistic,
+            hyp_names: vec![],
+            hyp_aliases: vec![],
+            lemmas: Rc::default(),
         };
 
         let proof_after = Proof {
             goals: vec![
-                (Rc::new(Prop::from_name(String::from("a"))), vec![]),
-                (Rc::new(Prop::True), vec![]),
-                (Rc::new(Prop::False), vec![]),
-                (Rc::new(Prop::from_name(String::from("name"))), vec![]),
+                (PropRef::new(Prop::from_name(String::from("a"))), vec![]),
+                (PropRef::new(Prop::True), vec![]),
+                (PropRef::new(Prop::False), vec![]),
+                (PropRef::new(Prop::from_name(String::from("name"))), vec![]),
                 (
-                    Rc::new(Prop::imply(
+                    PropRef::new(Prop::imply(
                         Prop::from_name(String::from("a")),
                         Prop::from_name(String::from("b")),
                     )),
                     vec![],
                 ),
                 (
-                    Rc::new(Prop::or(
+                    PropRef::new(Prop::or(
                         Prop::from_name(String::from("a")),
                         Prop::from_name(String::from("b")),
                     )),
                     vec![],
                 ),
-                (Rc::new(Prop::from_name(String::from("b"))), vec![]),
+                (PropRef::new(Prop::from_name(String::from("b"))), vec![]),
             ],
             active_goal: 0,
+            record: None,
+            logic: Logic::Intuitionistic,
+            hyp_names: vec![],
+            hyp_aliases: vec![],
+            lemmas: Rc::default(),
         };
 
         assert_eq!(proof_before.execute(&StrategyArg::Split), Ok(()));
         assert_eq!(proof_before, proof_after);
         assert_eq!(
             proof_before.execute(&StrategyArg::Split),
-            Err("Strategy could not be applied")
+            Err(StrategyError::GoalShapeMismatch {
+                expected: PropShape::And,
+                found: Prop::from_name(String::from("a"))
+            })
         );
         let _ = proof_before.set_active_goal(1);
         assert_eq!(
             proof_before.execute(&StrategyArg::Split),
-            Err("Strategy could not be applied")
+            Err(StrategyError::GoalShapeMismatch {
+                expected: PropShape::And,
+                found: Prop::True
+            })
         );
         let _ = proof_before.set_active_goal(2);
         assert_eq!(
             proof_before.execute(&StrategyArg::Split),
-            Err("Strategy could not be applied")
+            Err(StrategyError::GoalShapeMismatch {
+                expected: PropShape::And,
+                found: Prop::False
+            })
         );
         let _ = proof_before.set_active_goal(3);
         assert_eq!(
             proof_before.execute(&StrategyArg::Split),
-            Err("Strategy could not be applied")
+            Err(StrategyError::GoalShapeMismatch {
+                expected: PropShape::And,
+                found: Prop::from_name(String::from("name"))
+            })
         );
         let _ = proof_before.set_active_goal(4);
         assert_eq!(
             proof_before.execute(&StrategyArg::Split),
-            Err("Strategy could not be applied")
+            Err(StrategyError::GoalShapeMismatch {
+                expected: PropShape::And,
+                found: Prop::imply(
+                    Prop::from_name(String::from("a")),
+                    Prop::from_name(String::from("b"))
+                )
+            })
         );
         let _ = proof_before.set_active_goal(5);
         assert_eq!(
             proof_before.execute(&StrategyArg::Split),
-            Err("Strategy could not be applied")
+            Err(StrategyError::GoalShapeMismatch {
+                expected: PropShape::And,
+                found: Prop::or(
+                    Prop::from_name(String::from("a")),
+                    Prop::from_name(String::from("b"))
+                )
+            })
         );
         let _ = proof_before.set_active_goal(6);
         assert_eq!(
             proof_before.execute(&StrategyArg::Split),
-            Err("Strategy could not be applied")
+            Err(StrategyError::GoalShapeMismatch {
+                expected: PropShape::And,
+                found: Prop::from_name(String::from("b"))
+            })
         );
     }
 
@@ -629,79 +2163,119 @@ mod tests {
     fn hyp_split() {
         let mut proof_before = Proof {
             goals: vec![(
-                Rc::new(Prop::False),
+                PropRef::new(Prop::False),
                 vec![
-                    Rc::new(Prop::and(
+                    PropRef::new(Prop::and(
                         Prop::from_name(String::from("a")),
                         Prop::from_name(String::from("b")),
                     )),
-                    Rc::new(Prop::True),
-                    Rc::new(Prop::False),
-                    Rc::new(Prop::from_name(String::from("name"))),
-                    Rc::new(Prop::imply(
+                    PropRef::new(Prop::True),
+                    PropRef::new(Prop::False),
+                    PropRef::new(Prop::from_name(String::from("name"))),
+                    PropRef::new(Prop::imply(
                         Prop::from_name(String::from("a")),
                         Prop::from_name(String::from("b")),
                     )),
-                    Rc::new(Prop::or(
+                    PropRef::new(Prop::or(
                         Prop::from_name(String::from("a")),
                         Prop::from_name(String::from("b")),
                     )),
                 ],
             )],
             active_goal: 0,
+            record: None,
+            logic: Logic::Intuitionistic,
+            hyp_names: vec![],
+            hyp_aliases: vec![],
+            lemmas: Rc::default(),
         };
 
         let proof_after = Proof {
             goals: vec![(
-                Rc::new(Prop::False),
+                PropRef::new(Prop::False),
                 vec![
-                    Rc::new(Prop::from_name(String::from("a"))),
-                    Rc::new(Prop::True),
-                    Rc::new(Prop::False),
-                    Rc::new(Prop::from_name(String::from("name"))),
-                    Rc::new(Prop::imply(
+                    PropRef::new(Prop::from_name(String::from("a"))),
+                    PropRef::new(Prop::True),
+                    PropRef::new(Prop::False),
+                    PropRef::new(Prop::from_name(String::from("name"))),
+                    PropRef::new(Prop::imply(
                         Prop::from_name(String::from("a")),
                         Prop::from_name(String::from("b")),
                     )),
-                    Rc::new(Prop::or(
+                    PropRef::new(Prop::or(
                         Prop::from_name(String::from("a")),
                         Prop::from_name(String::from("b")),
                     )),
-                    Rc::new(Prop::from_name(String::from("b"))),
+                    PropRef::new(Prop::from_name(String::from("b"))),
                 ],
             )],
             active_goal: 0,
+            record: None,
+            logic: Logic::Intuitionistic,
+            hyp_names: vec![],
+            hyp_aliases: vec![],
+            lemmas: Rc::default(),
         };
 
-        assert_eq!(proof_before.execute(&StrategyArg::HypSplit(0)), Ok(()));
+        assert_eq!(proof_before.execute(&StrategyArg::HypSplit(h(0))), Ok(()));
         assert_eq!(proof_before, proof_after);
         assert_eq!(
-            proof_before.execute(&StrategyArg::HypSplit(1)),
-            Err("Strategy could not be applied")
+            proof_before.execute(&StrategyArg::HypSplit(h(1))),
+            Err(StrategyError::HypShapeMismatch {
+                name: h(1),
+                expected: PropShape::And,
+                found: Prop::True
+            })
         );
         assert_eq!(
-            proof_before.execute(&StrategyArg::HypSplit(2)),
-            Err("Strategy could not be applied")
+            proof_before.execute(&StrategyArg::HypSplit(h(2))),
+            Err(StrategyError::HypShapeMismatch {
+                name: h(2),
+                expected: PropShape::And,
+                found: Prop::False
+            })
         );
         assert_eq!(
-            proof_before.execute(&StrategyArg::HypSplit(3)),
-            Err("Strategy could not be applied")
+            proof_before.execute(&StrategyArg::HypSplit(h(3))),
+            Err(StrategyError::HypShapeMismatch {
+                name: h(3),
+                expected: PropShape::And,
+                found: Prop::from_name(String::from("name"))
+            })
         );
         assert_eq!(
-            proof_before.execute(&StrategyArg::HypSplit(4)),
-            Err("Strategy could not be applied")
+            proof_before.execute(&StrategyArg::HypSplit(h(4))),
+            Err(StrategyError::HypShapeMismatch {
+                name: h(4),
+                expected: PropShape::And,
+                found: Prop::imply(
+                    Prop::from_name(String::from("a")),
+                    Prop::from_name(String::from("b"))
+                )
+            })
         );
         assert_eq!(
-            proof_before.execute(&StrategyArg::HypSplit(5)),
-            Err("Strategy could not be applied")
+            proof_before.execute(&StrategyArg::HypSplit(h(5))),
+            Err(StrategyError::HypShapeMismatch {
+                name: h(5),
+                expected: PropShape::And,
+                found: Prop::or(
+                    Prop::from_name(String::from("a")),
+                    Prop::from_name(String::from("b"))
+                )
+            })
         );
         assert_eq!(
-            proof_before.execute(&StrategyArg::HypSplit(6)),
-            Err("Strategy could not be applied")
+            proof_before.execute(&StrategyArg::HypSplit(h(6))),
+            Err(StrategyError::HypShapeMismatch {
+                name: h(6),
+                expected: PropShape::And,
+                found: Prop::from_name(String::from("b"))
+            })
         );
         assert_eq!(
-            proof_before.execute(&StrategyArg::HypSplit(8)),
-            Err("Out of bounds")
+            proof_before.execute(&StrategyArg::HypSplit(h(8))),
+            Err(StrategyError::UnknownHyp(h(8)))
         )
     }
 
@@ -710,24 +2284,24 @@ mod tests {
         let mut proof_before_left = Proof {
             goals: vec![
                 (
-                    Rc::new(Prop::or(
+                    PropRef::new(Prop::or(
                         Prop::from_name(String::from("a")),
                         Prop::from_name(String::from("b")),
                     )),
                     vec![],
                 ),
-                (Rc::new(Prop::True), vec![]),
-                (Rc::new(Prop::False), vec![]),
-                (Rc::new(Prop::from_name(String::from("name"))), vec![]),
+                (PropRef::new(Prop::True), vec![]),
+                (PropRef::new(Prop::False), vec![]),
+                (PropRef::new(Prop::from_name(String::from("name"))), vec![]),
                 (
-                    Rc::new(Prop::imply(
+                    PropRef::new(Prop::imply(
                         Prop::from_name(String::from("a")),
                         Prop::from_name(String::from("b")),
                     )),
                     vec![],
                 ),
                 (
-                    Rc::new(Prop::and(
+                    PropRef::new(Prop::and(
                         Prop::from_name(String::from("a")),
                         Prop::from_name(String::from("b")),
                     )),
@@ -735,23 +2309,28 @@ mod tests {
                 ),
             ],
             active_goal: 0,
+            record: None,
+            logic: Logic::Intuitionistic,
+            hyp_names: vec![],
+            hyp_aliases: vec![],
+            lemmas: Rc::default(),
         };
 
         let proof_after_left = Proof {
             goals: vec![
-                (Rc::new(Prop::from_name(String::from("a"))), vec![]),
-                (Rc::new(Prop::True), vec![]),
-                (Rc::new(Prop::False), vec![]),
-                (Rc::new(Prop::from_name(String::from("name"))), vec![]),
+                (PropRef::new(Prop::from_name(String::from("a"))), vec![]),
+                (PropRef::new(Prop::True), vec![]),
+                (PropRef::new(Prop::False), vec![]),
+                (PropRef::new(Prop::from_name(String::from("name"))), vec![]),
                 (
-                    Rc::new(Prop::imply(
+                    PropRef::new(Prop::imply(
                         Prop::from_name(String::from("a")),
                         Prop::from_name(String::from("b")),
                     )),
                     vec![],
                 ),
                 (
-                    Rc::new(Prop::and(
+                    PropRef::new(Prop::and(
                         Prop::from_name(String::from("a")),
                         Prop::from_name(String::from("b")),
                     )),
@@ -759,25 +2338,30 @@ mod tests {
                 ),
             ],
             active_goal: 0,
+            record: None,
+            logic: Logic::Intuitionistic,
+            hyp_names: vec![],
+            hyp_aliases: vec![],
+            lemmas: Rc::default(),
         };
 
         let mut proof_before_right = proof_before_left.clone();
 
         let proof_after_right = Proof {
             goals: vec![
-                (Rc::new(Prop::from_name(String::from("b"))), vec![]),
-                (Rc::new(Prop::True), vec![]),
-                (Rc::new(Prop::False), vec![]),
-                (Rc::new(Prop::from_name(String::from("name"))), vec![]),
+                (PropRef::new(Prop::from_name(String::from("b"))), vec![]),
+                (PropRef::new(Prop::True), vec![]),
+                (PropRef::new(Prop::False), vec![]),
+                (PropRef::new(Prop::from_name(String::from("name"))), vec![]),
                 (
-                    Rc::new(Prop::imply(
+                    PropRef::new(Prop::imply(
                         Prop::from_name(String::from("a")),
                         Prop::from_name(String::from("b")),
                     )),
                     vec![],
                 ),
                 (
-                    Rc::new(Prop::and(
+                    PropRef::new(Prop::and(
                         Prop::from_name(String::from("a")),
                         Prop::from_name(String::from("b")),
                     )),
@@ -785,6 +2369,11 @@ mod tests {
                 ),
             ],
             active_goal: 0,
+            record: None,
+            logic: Logic::Intuitionistic,
+            hyp_names: vec![],
+            hyp_aliases: vec![],
+            lemmas: Rc::default(),
         };
 
         assert_eq!(
@@ -800,38 +2389,703 @@ mod tests {
 
         assert_eq!(
             proof_before_left.execute(&StrategyArg::OrSplit(true)),
-            Err("Strategy could not be applied")
+            Err(StrategyError::GoalShapeMismatch {
+                expected: PropShape::Or,
+                found: Prop::from_name(String::from("a"))
+            })
         );
         let _ = proof_before_left.set_active_goal(1);
         assert_eq!(
             proof_before_left.execute(&StrategyArg::OrSplit(true)),
-            Err("Strategy could not be applied")
+            Err(StrategyError::GoalShapeMismatch {
+                expected: PropShape::Or,
+                found: Prop::True
+            })
         );
         let _ = proof_before_left.set_active_goal(2);
         assert_eq!(
             proof_before_left.execute(&StrategyArg::OrSplit(true)),
-            Err("Strategy could not be applied")
+            Err(StrategyError::GoalShapeMismatch {
+                expected: PropShape::Or,
+                found: Prop::False
+            })
         );
         let _ = proof_before_left.set_active_goal(3);
         assert_eq!(
             proof_before_left.execute(&StrategyArg::OrSplit(true)),
-            Err("Strategy could not be applied")
+            Err(StrategyError::GoalShapeMismatch {
+                expected: PropShape::Or,
+                found: Prop::from_name(String::from("name"))
+            })
         );
         let _ = proof_before_left.set_active_goal(4);
         assert_eq!(
             proof_before_left.execute(&StrategyArg::OrSplit(true)),
-            Err("Strategy could not be applied")
+            Err(StrategyError::GoalShapeMismatch {
+                expected: PropShape::Or,
+                found: Prop::imply(
+                    Prop::from_name(String::from("a")),
+                    Prop::from_name(String::from("b"))
+                )
+            })
         );
         let _ = proof_before_left.set_active_goal(5);
         assert_eq!(
             proof_before_left.execute(&StrategyArg::OrSplit(true)),
-            Err("Strategy could not be applied")
+            Err(StrategyError::GoalShapeMismatch {
+                expected: PropShape::Or,
+                found: Prop::and(
+                    Prop::from_name(String::from("a")),
+                    Prop::from_name(String::from("b"))
+                )
+            })
         );
         let _ = proof_before_left.set_active_goal(6);
         assert_eq!(
             proof_before_left.execute(&StrategyArg::OrSplit(true)),
-            Err("Strategy could not be applied")
+            Err(StrategyError::GoalShapeMismatch {
+                expected: PropShape::Or,
+                found: Prop::and(
+                    Prop::from_name(String::from("a")),
+                    Prop::from_name(String::from("b"))
+                )
+            })
+        );
+    }
+
+    #[test]
+    fn destruct() {
+        let mut proof_before = Proof {
+            goals: vec![(
+                PropRef::new(Prop::from_name(String::from("c"))),
+                vec![
+                    PropRef::new(Prop::from_name(String::from("d"))),
+                    PropRef::new(Prop::or(
+                        Prop::from_name(String::from("a")),
+                        Prop::from_name(String::from("b")),
+                    )),
+                ],
+            )],
+            active_goal: 0,
+            record: None,
+            logic: Logic::Intuitionistic,
+            hyp_names: vec![],
+            hyp_aliases: vec![],
+            lemmas: Rc::default(),
+        };
+
+        let proof_after = Proof {
+            goals: vec![
+                (
+                    PropRef::new(Prop::from_name(String::from("c"))),
+                    vec![
+                        PropRef::new(Prop::from_name(String::from("d"))),
+                        PropRef::new(Prop::from_name(String::from("a"))),
+                    ],
+                ),
+                (
+                    PropRef::new(Prop::from_name(String::from("c"))),
+                    vec![
+                        PropRef::new(Prop::from_name(String::from("d"))),
+                        PropRef::new(Prop::from_name(String::from("b"))),
+                    ],
+                ),
+            ],
+            active_goal: 0,
+            record: None,
+            logic: Logic::Intuitionistic,
+            hyp_names: vec![],
+            hyp_aliases: vec![],
+            lemmas: Rc::default(),
+        };
+
+        assert_eq!(proof_before.execute(&StrategyArg::Destruct(h(1))), Ok(()));
+        assert_eq!(proof_before, proof_after);
+        assert_eq!(
+            proof_before.execute(&StrategyArg::Destruct(h(0))),
+            Err(StrategyError::HypShapeMismatch {
+                name: h(0),
+                expected: PropShape::Or,
+                found: Prop::from_name(String::from("d")),
+            })
+        );
+        assert_eq!(
+            proof_before.execute(&StrategyArg::Destruct(h(2))),
+            Err(StrategyError::UnknownHyp(h(2)))
+        );
+    }
+
+    #[test]
+    fn imply_left() {
+        let steps = |statement: &str, steps: &[(usize, StrategyArg)]| {
+            let mut proof = Proof::new();
+            proof.add_goal_from_prop(Prop::parse_infix(statement).unwrap());
+            for (goal, strat) in steps {
+                assert_eq!(proof.set_active_goal(*goal), Ok(()));
+                assert_eq!(proof.execute(strat), Ok(()));
+            }
+            let terms = proof.finished_terms().unwrap();
+            assert_eq!(kernel::check_theorem(&terms[0].0, &terms[0].1), Ok(()));
+            proof
+        };
+
+        let proof = steps(
+            "((a => b) => c) => b => c",
+            &[
+                (0, StrategyArg::Intro(None)),
+                (0, StrategyArg::Intro(None)),
+                (0, StrategyArg::ImplyLeft(h(0))),
+                (0, StrategyArg::Exact(h(1))),
+                (1, StrategyArg::Exact(h(0))),
+            ],
+        );
+        assert_eq!(proof.number_of_goals(), 2);
+
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::parse_infix("((a => b) => c) => b => c").unwrap());
+        let _ = proof.execute(&StrategyArg::Intro(None));
+        let _ = proof.execute(&StrategyArg::Intro(None));
+        assert_eq!(proof.execute(&StrategyArg::ImplyLeft(h(0))), Ok(()));
+        let props = |s: &str| -> Vec<PropRef> {
+            s.split(',')
+                .map(|p| PropRef::new(Prop::parse_infix(p).unwrap()))
+                .collect()
+        };
+        assert_eq!(
+            proof.goals,
+            vec![
+                (
+                    PropRef::new(Prop::from_name(String::from("b"))),
+                    props("b => c, b, a")
+                ),
+                (
+                    PropRef::new(Prop::from_name(String::from("c"))),
+                    props("c, b")
+                ),
+            ]
+        );
+        assert_eq!(
+            proof.execute(&StrategyArg::ImplyLeft(h(2))),
+            Err(StrategyError::HypShapeMismatch {
+                name: h(2),
+                expected: PropShape::CompoundImplies,
+                found: Prop::from_name(String::from("a")),
+            })
+        );
+        assert_eq!(
+            proof.execute(&StrategyArg::ImplyLeft(h(0))),
+            Err(StrategyError::HypShapeMismatch {
+                name: h(0),
+                expected: PropShape::CompoundImplies,
+                found: Prop::parse_infix("b => c").unwrap(),
+            })
+        );
+
+        steps(
+            "(a ^ b => c) => a => b => c",
+            &[
+                (0, StrategyArg::Intro(None)),
+                (0, StrategyArg::Intro(None)),
+                (0, StrategyArg::Intro(None)),
+                (0, StrategyArg::ImplyLeft(h(0))),
+                (0, StrategyArg::ApplyIn(h(1), h(0), false)),
+                (0, StrategyArg::ApplyIn(h(2), h(1), false)),
+                (0, StrategyArg::Exact(h(2))),
+            ],
+        );
+        steps(
+            "(a | b => c) => b => c",
+            &[
+                (0, StrategyArg::Intro(None)),
+                (0, StrategyArg::Intro(None)),
+                (0, StrategyArg::ImplyLeft(h(0))),
+                (0, StrategyArg::ApplyIn(h(1), h(2), false)),
+                (0, StrategyArg::Exact(h(1))),
+            ],
+        );
+        steps(
+            "(T => c) => (F => c) => c",
+            &[
+                (0, StrategyArg::Intro(None)),
+                (0, StrategyArg::Intro(None)),
+                (0, StrategyArg::ImplyLeft(h(1))),
+                (0, StrategyArg::ImplyLeft(h(0))),
+                (0, StrategyArg::Exact(h(0))),
+            ],
+        );
+    }
+
+    #[test]
+    fn unfold() {
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::parse_infix("(a <=> b) => !b => !a").unwrap());
+        assert_eq!(proof.execute(&StrategyArg::Intro(None)), Ok(()));
+        assert_eq!(proof.execute(&StrategyArg::Intro(None)), Ok(()));
+        assert_eq!(
+            proof.execute(&StrategyArg::UnfoldIff(None)),
+            Err(StrategyError::GoalShapeMismatch {
+                expected: PropShape::WithIff,
+                found: Prop::parse_infix("!a").unwrap(),
+            })
+        );
+        assert_eq!(
+            proof.execute(&StrategyArg::UnfoldNot(Some(h(0)))),
+            Err(StrategyError::HypShapeMismatch {
+                name: h(0),
+                expected: PropShape::WithNot,
+                found: Prop::parse_infix("a <=> b").unwrap(),
+            })
+        );
+        assert_eq!(proof.execute(&StrategyArg::UnfoldNot(None)), Ok(()));
+        assert_eq!(proof.execute(&StrategyArg::UnfoldNot(Some(h(1)))), Ok(()));
+        assert_eq!(proof.execute(&StrategyArg::UnfoldIff(Some(h(0)))), Ok(()));
+        assert_eq!(
+            proof.goals[0].1[0].to_string(),
+            "( ( a => b ) ^ ( b => a ) )"
+        );
+        assert_eq!(
+            StrategyArg::UnfoldIff(Some(h(0))).to_string(),
+            "unfold_iff H0"
+        );
+        assert_eq!(StrategyArg::UnfoldNot(None).to_string(), "unfold_not");
+        for strat in [
+            StrategyArg::Intro(None),
+            StrategyArg::HypSplit(h(0)),
+            StrategyArg::ApplyIn(h(2), h(0), false),
+            StrategyArg::ApplyIn(h(2), h(1), false),
+            StrategyArg::FalseIsHyp,
+        ] {
+            assert_eq!(proof.execute(&strat), Ok(()));
+        }
+        // the theorem keeps its notations, its term checks all the same
+        let terms = proof.finished_terms().unwrap();
+        assert_eq!(
+            terms[0].0.goal.to_string(),
+            "( ( a <=> b ) => ( ! b => ! a ) )"
+        );
+        assert_eq!(kernel::check_theorem(&terms[0].0, &terms[0].1), Ok(()));
+    }
+
+    #[test]
+    fn quantifiers() {
+        let x = || FoTerm::Var(String::from("x"));
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(
+            Prop::parse_infix("(forall x. P(x) => Q(x)) => (forall y. P(y)) => forall z. Q(z)")
+                .unwrap(),
+        );
+        for strat in [
+            StrategyArg::Intro(None),
+            StrategyArg::Intro(None),
+            StrategyArg::Intro(None),
+            StrategyArg::Instantiate(h(0), FoTerm::Var(String::from("z"))),
+            StrategyArg::Instantiate(h(1), FoTerm::Var(String::from("z"))),
+            StrategyArg::Apply(h(2)),
+            StrategyArg::Exact(h(3)),
+        ] {
+            assert_eq!(proof.execute(&strat), Ok(()), "{}", strat);
+        }
+        let terms = proof.finished_terms().unwrap();
+        assert_eq!(
+            terms[0].1.to_string(),
+            "(λx1: ( forall x. ( P(x) => Q(x) ) ). (λx3: ( forall y. P(y) ). (Λz. \
+             (let x6 = (x1 z) in (let x8 = (x3 z) in (x6 x8))))))"
+        );
+        assert_eq!(kernel::check_theorem(&terms[0].0, &terms[0].1), Ok(()));
+
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(
+            Prop::parse_infix("(exists x. P(x) ^ Q(x)) => exists y. Q(y)").unwrap(),
+        );
+        for strat in [
+            StrategyArg::Intro(None),
+            StrategyArg::Destruct(h(0)),
+            StrategyArg::HypSplit(h(0)),
+            StrategyArg::Witness(x()),
+            StrategyArg::Exact(h(1)),
+        ] {
+            assert_eq!(proof.execute(&strat), Ok(()), "{}", strat);
+        }
+        let terms = proof.finished_terms().unwrap();
+        assert_eq!(kernel::check_theorem(&terms[0].0, &terms[0].1), Ok(()));
+
+        // apply and apply_in accept propositions equal up to bound variables
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::parse_infix("forall y. P(y)").unwrap());
+        proof.add_hyp_from_prop(Prop::parse_infix("a => forall x. P(x)").unwrap());
+        proof.add_hyp_from_prop(Prop::parse_infix("(forall z. P(z)) => a").unwrap());
+        proof.add_hyp_from_prop(Prop::parse_infix("forall x. P(x)").unwrap());
+        let applicable: Vec<StrategyArg> = proof
+            .get_applicable_strategies()
+            .into_iter()
+            .map(|(_, _, strat)| strat)
+            .collect();
+        assert!(applicable.contains(&StrategyArg::Apply(h(0))));
+        assert!(applicable.contains(&StrategyArg::ApplyIn(h(2), h(1), true)));
+        for strat in [
+            StrategyArg::Apply(h(0)),
+            StrategyArg::ApplyIn(h(2), h(1), true),
+            StrategyArg::Exact(h(3)),
+        ] {
+            assert_eq!(proof.execute(&strat), Ok(()), "{}", strat);
+        }
+        let terms = proof.finished_terms().unwrap();
+        assert_eq!(kernel::check_theorem(&terms[0].0, &terms[0].1), Ok(()));
+
+        // the variable introduced is arbitrary, it cannot be the x of P(x)
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::parse_infix("forall x. P(x)").unwrap());
+        proof.add_hyp_from_prop(Prop::parse_infix("P(x)").unwrap());
+        assert_eq!(proof.execute(&StrategyArg::Intro(None)), Ok(()));
+        assert_eq!(proof.goals[0].0.to_string(), "P(x')");
+        assert!(proof.execute(&StrategyArg::Exact(h(0))).is_err());
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::parse_infix("Q(x)").unwrap());
+        proof.add_hyp_from_prop(Prop::parse_infix("exists x. P(x)").unwrap());
+        assert_eq!(proof.execute(&StrategyArg::Destruct(h(0))), Ok(()));
+        assert_eq!(proof.goals[0].1[0].to_string(), "P(x')");
+
+        assert_eq!(
+            proof.execute(&StrategyArg::Instantiate(h(0), x())),
+            Err(StrategyError::HypShapeMismatch {
+                name: h(0),
+                expected: PropShape::Forall,
+                found: Prop::parse_infix("P(x')").unwrap(),
+            })
+        );
+        assert_eq!(
+            proof
+                .execute(&StrategyArg::Witness(x()))
+                .map_err(|err| err.to_string()),
+            Err(String::from(
+                "goal should be an existential quantification, found Q(x)"
+            ))
+        );
+        assert_eq!(
+            StrategyArg::Instantiate(h(2), FoTerm::App(String::from("f"), vec![x()])).to_string(),
+            "instantiate H2 f(x)"
+        );
+        assert_eq!(StrategyArg::Witness(x()).to_string(), "exists x");
+
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::parse_infix("exists y. Q(y)").unwrap());
+        proof.add_hyp_from_prop(Prop::parse_infix("forall x. Q(x)").unwrap());
+        proof.add_hyp_from_prop(Prop::parse_infix("exists z. P(z)").unwrap());
+        assert_eq!(
+            proof.get_applicable_strategies(),
+            vec![
+                (2, 0, StrategyArg::Destruct(h(1))),
+                (
+                    4,
+                    0,
+                    StrategyArg::Instantiate(h(0), FoTerm::Var(String::from("x")))
+                ),
+                (4, 0, StrategyArg::Witness(FoTerm::Var(String::from("y")))),
+            ]
+        );
+    }
+
+    #[test]
+    fn logics() {
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::parse_infix("F => a").unwrap());
+        proof.set_logic(Logic::Minimal);
+        assert_eq!(proof.execute(&StrategyArg::Intro(None)), Ok(()));
+        assert_eq!(proof.get_applicable_strategies(), vec![]);
+        assert_eq!(
+            proof.execute(&StrategyArg::FalseIsHyp),
+            Err(StrategyError::NotInLogic(Logic::Minimal))
+        );
+        proof.set_logic(Logic::Intuitionistic);
+        assert_eq!(
+            proof.get_applicable_strategies(),
+            vec![(0, 0, StrategyArg::FalseIsHyp)]
+        );
+        assert_eq!(
+            proof.execute(&StrategyArg::ByContradiction),
+            Err(StrategyError::NotInLogic(Logic::Intuitionistic))
+        );
+
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::parse_infix("!!a => a").unwrap());
+        proof.set_logic(Logic::Classical);
+        for strat in [
+            StrategyArg::Intro(None),
+            StrategyArg::ByContradiction,
+            StrategyArg::UnfoldNot(Some(h(0))),
+            StrategyArg::Apply(h(0)),
+            StrategyArg::UnfoldNot(Some(h(1))),
+            StrategyArg::Exact(h(1)),
+        ] {
+            assert_eq!(proof.execute(&strat), Ok(()), "{}", strat);
+        }
+        let terms = proof.finished_terms().unwrap();
+        assert_eq!(kernel::check_theorem(&terms[0].0, &terms[0].1), Ok(()));
+        // the same term is no intuitionistic proof
+        proof.set_logic(Logic::Intuitionistic);
+        let terms = proof.finished_terms().unwrap();
+        assert_eq!(
+            kernel::check_theorem(&terms[0].0, &terms[0].1),
+            Err(kernel::KernelError::WrongLogic {
+                needed: Logic::Classical,
+                allowed: Logic::Intuitionistic,
+            })
+        );
+
+        let mut proof = Proof::new();
+        proof.set_logic(Logic::Classical);
+        proof.add_goal_from_prop(Prop::parse_infix("a | !a").unwrap());
+        proof.add_hyp_from_prop(Prop::parse_infix("b").unwrap());
+        let a = PropRef::new(Prop::from_name(String::from("a")));
+        let b = PropRef::new(Prop::from_name(String::from("b")));
+        assert_eq!(
+            proof.get_applicable_strategies(),
+            vec![
+                (2, 0, StrategyArg::UnfoldNot(None)),
+                (3, 0, StrategyArg::OrSplit(false)),
+                (3, 0, StrategyArg::OrSplit(true)),
+                (4, 0, StrategyArg::ByContradiction),
+                (4, 0, StrategyArg::ExcludedMiddle(a.clone())),
+                (4, 0, StrategyArg::ExcludedMiddle(b)),
+            ]
+        );
+        assert_eq!(
+            proof.execute(&StrategyArg::ExcludedMiddle(a.clone())),
+            Ok(())
+        );
+        assert_eq!(proof.execute(&StrategyArg::Exact(h(1))), Ok(()));
+        let terms = proof.finished_terms().unwrap();
+        assert_eq!(terms[0].1.to_string(), "(let x2 = em a in x2)");
+        assert_eq!(kernel::check_theorem(&terms[0].0, &terms[0].1), Ok(()));
+        assert_eq!(
+            StrategyArg::ExcludedMiddle(a).to_string(),
+            "excluded_middle a"
+        );
+        assert_eq!(
+            StrategyError::NotInLogic(Logic::Minimal).to_string(),
+            "strategy not available in minimal logic"
+        );
+    }
+
+    #[test]
+    fn proof_terms() {
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::parse_infix("a ^ b => b ^ a").unwrap());
+        assert_eq!(proof.execute(&StrategyArg::Intro(None)), Ok(()));
+        assert_eq!(proof.execute(&StrategyArg::HypSplit(h(0))), Ok(()));
+        assert_eq!(proof.execute(&StrategyArg::Split), Ok(()));
+        assert_eq!(proof.execute(&StrategyArg::Exact(h(1))), Ok(()));
+        assert_eq!(proof.finished_terms(), None);
+        assert_eq!(
+            proof.proof_terms()[0].1.to_string(),
+            "(λx1: ( a ^ b ). (let x3 = fst x1 in (let x4 = snd x1 in <x4, ?7>)))"
+        );
+        let _ = proof.set_active_goal(1);
+        assert_eq!(proof.execute(&StrategyArg::Exact(h(0))), Ok(()));
+        let terms = proof.finished_terms().unwrap();
+        assert_eq!(terms[0].0.to_string(), " |- ( ( a ^ b ) => ( b ^ a ) )");
+        assert_eq!(
+            terms[0].1.to_string(),
+            "(λx1: ( a ^ b ). (let x3 = fst x1 in (let x4 = snd x1 in <x4, x3>)))"
+        );
+
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::parse_infix("a | a => a").unwrap());
+        assert_eq!(proof.execute(&StrategyArg::Intro(None)), Ok(()));
+        assert_eq!(proof.execute(&StrategyArg::Destruct(h(0))), Ok(()));
+        proof.clean();
+        assert_eq!(proof.number_of_goals(), 1);
+        assert_eq!(proof.execute(&StrategyArg::Exact(h(0))), Ok(()));
+        assert_eq!(
+            proof.finished_terms().unwrap()[0].1.to_string(),
+            "(λx1: ( a | a ). (case x1 of inl x3 -> x3 | inr x4 -> x4))"
+        );
+
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::parse_infix("a => T").unwrap());
+        proof.add_hyp_from_prop(Prop::False);
+        assert_eq!(proof.execute(&StrategyArg::Intro(None)), Ok(()));
+        let terms = proof.finished_terms().unwrap();
+        assert_eq!(terms[0].0.to_string(), "x1: F |- ( a => T )");
+        assert_eq!(terms[0].1.to_string(), "(λx2: a. ())");
+    }
+
+    #[test]
+    fn lemmas() {
+        let mut library = Proof::new();
+        library.add_goal_from_prop(Prop::parse_infix("a ^ b => b ^ a").unwrap());
+        assert_eq!(
+            library.save_lemma("and_comm"),
+            Err(String::from("the proof is not finished"))
+        );
+        assert_eq!(library.execute(&StrategyArg::Intro(None)), Ok(()));
+        assert_eq!(library.execute(&StrategyArg::HypSplit(h(0))), Ok(()));
+        assert_eq!(library.execute(&StrategyArg::Split), Ok(()));
+        assert_eq!(library.execute(&StrategyArg::Exact(h(1))), Ok(()));
+        let _ = library.set_active_goal(1);
+        assert_eq!(library.execute(&StrategyArg::Exact(h(0))), Ok(()));
+        assert_eq!(library.save_lemma("and_comm"), Ok(()));
+        assert_eq!(
+            library.save_lemma("and_comm"),
+            Err(String::from("a lemma named and_comm already exists"))
+        );
+
+        let mut proof = Proof::new();
+        proof.inherit_lemmas(&library);
+        assert_eq!(
+            proof.lemmas(),
+            vec![(
+                String::from("and_comm"),
+                PropRef::new(Prop::parse_infix("a ^ b => b ^ a").unwrap())
+            )]
+        );
+        proof.add_goal_from_prop(Prop::parse_infix("(p | q) ^ r => r ^ (p | q)").unwrap());
+        assert_eq!(
+            proof.execute(&StrategyArg::Use(String::from("or_comm"), vec![])),
+            Err(StrategyError::UnknownLemma(String::from("or_comm")))
+        );
+        let subst = vec![
+            (
+                String::from("a"),
+                PropRef::new(Prop::parse_infix("p | q").unwrap()),
+            ),
+            (
+                String::from("b"),
+                PropRef::new(Prop::from_name(String::from("r"))),
+            ),
+        ];
+        let strat = StrategyArg::Use(String::from("and_comm"), subst);
+        assert_eq!(
+            strat.to_string(),
+            "use and_comm with a := ( p | q ), b := r"
+        );
+        assert_eq!(proof.execute(&strat), Ok(()));
+        assert_eq!(proof.execute(&StrategyArg::Intro(None)), Ok(()));
+        assert_eq!(proof.execute(&StrategyArg::Apply(h(0))), Ok(()));
+        assert_eq!(proof.execute(&StrategyArg::Exact(h(1))), Ok(()));
+        // the lemma is inlined in the proof term, checked as a whole
+        let terms = proof.finished_terms().unwrap();
+        assert_eq!(kernel::check_theorem(&terms[0].0, &terms[0].1), Ok(()));
+
+        let mut classical = Proof::new();
+        classical.set_logic(Logic::Classical);
+        classical.add_goal_from_prop(Prop::parse_infix("a | ~a").unwrap());
+        let a = PropRef::new(Prop::from_name(String::from("a")));
+        assert_eq!(classical.execute(&StrategyArg::ExcludedMiddle(a)), Ok(()));
+        assert_eq!(classical.execute(&StrategyArg::Exact(h(0))), Ok(()));
+        assert_eq!(classical.save_lemma("em"), Ok(()));
+        let mut proof = Proof::new();
+        proof.inherit_lemmas(&classical);
+        proof.add_goal_from_prop(Prop::parse_infix("b | ~b").unwrap());
+        assert_eq!(
+            proof.execute(&StrategyArg::Use(String::from("em"), vec![])),
+            Err(StrategyError::NotInLogic(Logic::Intuitionistic))
+        );
+
+        let mut first_order = Proof::new();
+        first_order.add_goal_from_prop(Prop::parse_infix("forall x. a => a").unwrap());
+        assert_eq!(first_order.execute(&StrategyArg::Intro(None)), Ok(()));
+        assert_eq!(first_order.execute(&StrategyArg::Intro(None)), Ok(()));
+        assert_eq!(first_order.execute(&StrategyArg::Exact(h(0))), Ok(()));
+        assert_eq!(first_order.save_lemma("id"), Ok(()));
+        let mut proof = Proof::new();
+        proof.inherit_lemmas(&first_order);
+        proof.add_goal_from_prop(Prop::parse_infix("forall y. P(y)").unwrap());
+        let subst = vec![(
+            String::from("a"),
+            PropRef::new(Prop::parse_infix("P(x)").unwrap()),
+        )];
+        assert_eq!(
+            proof.execute(&StrategyArg::Use(String::from("id"), subst)),
+            Err(StrategyError::CapturingSubstitution(String::from("a")))
+        );
+    }
+
+    #[test]
+    fn strategy_errors() {
+        let mut proof = Proof {
+            goals: vec![(
+                PropRef::new(Prop::from_name(String::from("c"))),
+                vec![
+                    PropRef::new(Prop::from_name(String::from("a"))),
+                    PropRef::new(Prop::imply(
+                        Prop::from_name(String::from("b")),
+                        Prop::from_name(String::from("c")),
+                    )),
+                    PropRef::new(Prop::imply(
+                        Prop::from_name(String::from("a")),
+                        Prop::from_name(String::from("b")),
+                    )),
+                ],
+            )],
+            active_goal: 0,
+            record: None,
+            logic: Logic::Intuitionistic,
+            hyp_names: vec![],
+            hyp_aliases: vec![],
+            lemmas: Rc::default(),
+        };
+
+        assert_eq!(
+            proof.execute(&StrategyArg::Exact(h(0))),
+            Err(StrategyError::PremiseMismatch {
+                expected: Prop::from_name(String::from("c")),
+                found: Prop::from_name(String::from("a")),
+            })
+        );
+        assert_eq!(
+            proof.execute(&StrategyArg::Exact(h(3))),
+            Err(StrategyError::UnknownHyp(h(3)))
+        );
+        assert_eq!(
+            proof.execute(&StrategyArg::Apply(h(0))),
+            Err(StrategyError::HypShapeMismatch {
+                name: h(0),
+                expected: PropShape::Implies,
+                found: Prop::from_name(String::from("a")),
+            })
+        );
+        assert_eq!(
+            proof.execute(&StrategyArg::Apply(h(2))),
+            Err(StrategyError::PremiseMismatch {
+                expected: Prop::from_name(String::from("c")),
+                found: Prop::from_name(String::from("b")),
+            })
+        );
+        assert_eq!(
+            proof.execute(&StrategyArg::ApplyIn(h(0), h(1), false)),
+            Err(StrategyError::PremiseMismatch {
+                expected: Prop::from_name(String::from("b")),
+                found: Prop::from_name(String::from("a")),
+            })
+        );
+        assert_eq!(
+            proof.execute(&StrategyArg::ApplyIn(h(0), h(4), false)),
+            Err(StrategyError::UnknownHyp(h(4)))
+        );
+        assert_eq!(
+            proof.execute(&StrategyArg::FalseIsHyp),
+            Err(StrategyError::NoFalseHyp)
+        );
+        assert_eq!(
+            proof.execute(&StrategyArg::Destruct(h(0))),
+            Err(StrategyError::HypShapeMismatch {
+                name: h(0),
+                expected: PropShape::Or,
+                found: Prop::from_name(String::from("a")),
+            })
+        );
+        assert_eq!(
+            StrategyError::UnknownHyp(h(3)).to_string(),
+            "the active goal has no hypothesis named H3"
+        );
+
+        assert_eq!(
+            proof.execute(&StrategyArg::ApplyIn(h(0), h(2), false)),
+            Ok(())
         );
+        assert_eq!(proof.execute(&StrategyArg::Apply(h(1))), Ok(()));
+        assert_eq!(proof.execute(&StrategyArg::Exact(h(0))), Ok(()));
     }
 
     #[test]
@@ -840,47 +3094,72 @@ mod tests {
         assert_eq!(empty_proof.get_applicable_strategies(), vec![]);
 
         let only_name = Proof {
-            goals: vec![(Rc::new(Prop::Name(String::from("a"))), vec![])],
+            goals: vec![(PropRef::new(Prop::Name(String::from("a"))), vec![])],
             active_goal: 0,
+            record: None,
+            logic: Logic::Intuitionistic,
+            hyp_names: vec![],
+            hyp_aliases: vec![],
+            lemmas: Rc::default(),
         };
         assert_eq!(only_name.get_applicable_strategies(), vec![]);
 
         let only_true = Proof {
-            goals: vec![(Rc::new(Prop::True), vec![])],
+            goals: vec![(PropRef::new(Prop::True), vec![])],
             active_goal: 0,
+            record: None,
+            logic: Logic::Intuitionistic,
+            hyp_names: vec![],
+            hyp_aliases: vec![],
+            lemmas: Rc::default(),
         };
         assert_eq!(only_true.get_applicable_strategies(), vec![]);
 
         let only_false = Proof {
-            goals: vec![(Rc::new(Prop::False), vec![])],
+            goals: vec![(PropRef::new(Prop::False), vec![])],
             active_goal: 0,
+            record: None,
+            logic: Logic::Intuitionistic,
+            hyp_names: vec![],
+            hyp_aliases: vec![],
+            lemmas: Rc::default(),
         };
         assert_eq!(only_false.get_applicable_strategies(), vec![]);
 
         let one_intro = Proof {
             goals: vec![(
-                Rc::new(Prop::imply(
+                PropRef::new(Prop::imply(
                     Prop::Name(String::from("a")),
                     Prop::Name(String::from("b")),
                 )),
                 vec![],
             )],
             active_goal: 0,
+            record: None,
+            logic: Logic::Intuitionistic,
+            hyp_names: vec![],
+            hyp_aliases: vec![],
+            lemmas: Rc::default(),
         };
         assert_eq!(
             one_intro.get_applicable_strategies(),
-            vec![(3, 0, StrategyArg::Intro)]
+            vec![(3, 0, StrategyArg::Intro(None))]
         );
 
         let one_split = Proof {
             goals: vec![(
-                Rc::new(Prop::and(
+                PropRef::new(Prop::and(
                     Prop::Name(String::from("a")),
                     Prop::Name(String::from("b")),
                 )),
                 vec![],
             )],
             active_goal: 0,
+            record: None,
+            logic: Logic::Intuitionistic,
+            hyp_names: vec![],
+            hyp_aliases: vec![],
+            lemmas: Rc::default(),
         };
         assert_eq!(
             one_split.get_applicable_strategies(),
@@ -889,13 +3168,18 @@ mod tests {
 
         let left_right_no_false = Proof {
             goals: vec![(
-                Rc::new(Prop::or(
+                PropRef::new(Prop::or(
                     Prop::Name(String::from("a")),
                     Prop::Name(String::from("b")),
                 )),
                 vec![],
             )],
             active_goal: 0,
+            record: None,
+            logic: Logic::Intuitionistic,
+            hyp_names: vec![],
+            hyp_aliases: vec![],
+            lemmas: Rc::default(),
         };
         assert_eq!(
             left_right_no_false.get_applicable_strategies(),
@@ -907,10 +3191,15 @@ mod tests {
 
         let left_right_false = Proof {
             goals: vec![(
-                Rc::new(Prop::or(Prop::Name(String::from("a")), Prop::False)),
+                PropRef::new(Prop::or(Prop::Name(String::from("a")), Prop::False)),
                 vec![],
             )],
             active_goal: 0,
+            record: None,
+            logic: Logic::Intuitionistic,
+            hyp_names: vec![],
+            hyp_aliases: vec![],
+            lemmas: Rc::default(),
         };
         assert_eq!(
             left_right_false.get_applicable_strategies(),
@@ -922,10 +3211,15 @@ mod tests {
 
         let left_false_right = Proof {
             goals: vec![(
-                Rc::new(Prop::or(Prop::False, Prop::Name(String::from("b")))),
+                PropRef::new(Prop::or(Prop::False, Prop::Name(String::from("b")))),
                 vec![],
             )],
             active_goal: 0,
+            record: None,
+            logic: Logic::Intuitionistic,
+            hyp_names: vec![],
+            hyp_aliases: vec![],
+            lemmas: Rc::default(),
         };
         assert_eq!(
             left_false_right.get_applicable_strategies(),
@@ -0,0 +1,644 @@
+use crate::libpoulet::fol::FoTerm;
+use crate::libpoulet::intern::PropRef;
+use crate::libpoulet::logic;
+use crate::libpoulet::strategies::{Proof, StrategyArg, StrategyError};
+
+// strategies combined, a tactic being run on a goal and leaving the goals
+// it produced still to be proven
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Tactic {
+    Strategy(StrategyArg),
+    // `t1; t2`, the second one run on every goal left by the first
+    Then(Box<Tactic>, Box<Tactic>),
+    // never fails, the goal is left as it was instead
+    Try(Box<Tactic>),
+    // run again on every goal left, until it fails or changes nothing
+    Repeat(Box<Tactic>),
+    // `first [t1 | t2]`, the first alternative that succeeds
+    First(Vec<Tactic>),
+    // `all: t`, run on every open goal of the proof
+    All(Box<Tactic>),
+    // `do n t`, run n times in sequence
+    Do(usize, Box<Tactic>),
+}
+
+// successful runs of a repeated tactic, guarding against those that keep
+// adding hypotheses
+const REPEAT_LIMIT: usize = 1000;
+
+const STRATEGIES: [&str; 19] = [
+    "intro",
+    "split",
+    "left",
+    "right",
+    "false",
+    "by_contradiction",
+    "unfold_not",
+    "unfold_iff",
+    "hyp_split",
+    "destruct",
+    "exact",
+    "apply",
+    "imply_left",
+    "instantiate",
+    "exists",
+    "excluded_middle",
+    "apply_in",
+    "apply_in_keep",
+    "use",
+];
+
+const COMBINATORS: [&str; 5] = ["try", "repeat", "first", "all", "do"];
+
+// whether the line is meant as a tactic rather than a REPL command
+pub fn is_tactic(input: &str) -> bool {
+    let input = input.trim_start();
+    let word = input
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .next()
+        .unwrap_or("");
+    input.starts_with('(') || STRATEGIES.contains(&word) || COMBINATORS.contains(&word)
+}
+
+pub fn parse(input: &str) -> Result<Tactic, String> {
+    let mut parser = Parser {
+        input,
+        pos: 0,
+        groups: 0,
+        alternatives: 0,
+    };
+    let tactic = parser.sequence()?;
+    match parser.peek() {
+        None => Ok(tactic),
+        Some(c) => Err(format!("unexpected '{}'", c)),
+    }
+}
+
+// hypotheses are named by a single word
+fn hyp_name(arg: &str) -> Result<String, String> {
+    match arg.split_whitespace().collect::<Vec<&str>>().as_slice() {
+        [name] => Ok(String::from(*name)),
+        _ => Err(String::from("Invalid argument")),
+    }
+}
+
+fn parse_term(input: &str) -> Result<FoTerm, String> {
+    FoTerm::parse(input).map_err(|err| format!("{}\n{}", err, err.underline(input)))
+}
+
+const SUBST_USAGE: &str = "expected 'with <atom> := <proposition>, ...'";
+
+// `a := p, b := q`, the propositions being split at commas outside of
+// parentheses
+fn parse_subst(input: &str) -> Result<Vec<(String, PropRef)>, String> {
+    let mut parts = vec![];
+    let (mut depth, mut start) = (0usize, 0);
+    for (i, c) in input.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                parts.push(&input[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    parts.push(&input[start..]);
+    parts
+        .into_iter()
+        .map(|part| {
+            let Some((atom, prop)) = part.split_once(":=") else {
+                return Err(String::from(SUBST_USAGE));
+            };
+            let (atom, prop) = (atom.trim(), prop.trim());
+            if atom.is_empty() || !atom.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                return Err(String::from(SUBST_USAGE));
+            }
+            match logic::Prop::parse_infix(prop) {
+                Ok(p) => Ok((String::from(atom), PropRef::new(p))),
+                Err(err) => Err(format!("{}\n{}", err, err.underline(prop))),
+            }
+        })
+        .collect()
+}
+
+// a single strategy, written as the REPL command carrying it out
+fn parse_strategy(input: &str) -> Result<StrategyArg, String> {
+    let (command, rest) = match input.trim().split_once(char::is_whitespace) {
+        Some((command, rest)) => (command, rest.trim()),
+        None => (input.trim(), ""),
+    };
+    let missing = |usage: &str| Err(format!("missing argument: {}", usage));
+    match (command, rest) {
+        ("intro", "") => Ok(StrategyArg::Intro(None)),
+        ("intro", _) => Ok(StrategyArg::Intro(Some(hyp_name(rest)?))),
+        ("split", "") => Ok(StrategyArg::Split),
+        ("left", "") => Ok(StrategyArg::OrSplit(true)),
+        ("right", "") => Ok(StrategyArg::OrSplit(false)),
+        ("false", "") => Ok(StrategyArg::FalseIsHyp),
+        ("by_contradiction", "") => Ok(StrategyArg::ByContradiction),
+        ("unfold_not", "") => Ok(StrategyArg::UnfoldNot(None)),
+        ("unfold_not", _) => Ok(StrategyArg::UnfoldNot(Some(hyp_name(rest)?))),
+        ("unfold_iff", "") => Ok(StrategyArg::UnfoldIff(None)),
+        ("unfold_iff", _) => Ok(StrategyArg::UnfoldIff(Some(hyp_name(rest)?))),
+        ("hyp_split" | "destruct" | "exact" | "apply" | "imply_left", "") => missing("<hyp name>"),
+        ("hyp_split", _) => Ok(StrategyArg::HypSplit(hyp_name(rest)?)),
+        ("destruct", _) => Ok(StrategyArg::Destruct(hyp_name(rest)?)),
+        ("exact", _) => Ok(StrategyArg::Exact(hyp_name(rest)?)),
+        ("apply", _) => Ok(StrategyArg::Apply(hyp_name(rest)?)),
+        ("imply_left", _) => Ok(StrategyArg::ImplyLeft(hyp_name(rest)?)),
+        ("instantiate", "") => Err(String::from("missing arguments: <hyp name> <term>")),
+        ("instantiate", _) => match rest.split_once(char::is_whitespace) {
+            Some((hyp, term)) => Ok(StrategyArg::Instantiate(
+                String::from(hyp),
+                parse_term(term.trim())?,
+            )),
+            None => missing("<term>"),
+        },
+        ("exists", "") => missing("<term>"),
+        ("exists", _) => Ok(StrategyArg::Witness(parse_term(rest)?)),
+        ("excluded_middle", "") => missing("<proposition infix format>"),
+        ("excluded_middle", _) => match logic::Prop::parse_infix(rest) {
+            Ok(prop) => Ok(StrategyArg::ExcludedMiddle(PropRef::new(prop))),
+            Err(err) => Err(format!("{}\n{}", err, err.underline(rest))),
+        },
+        ("apply_in" | "apply_in_keep", "") => Err(String::from(
+            "missing arguments: <hyp name target> <hyp name to apply>",
+        )),
+        ("apply_in" | "apply_in_keep", _) => match rest.split_once(char::is_whitespace) {
+            Some((first, second)) => Ok(StrategyArg::ApplyIn(
+                String::from(first),
+                hyp_name(second)?,
+                command == "apply_in_keep",
+            )),
+            None => missing("<hyp name to apply>"),
+        },
+        ("use", "") => missing("<lemma name>"),
+        ("use", _) => match rest.split_once(char::is_whitespace) {
+            None => Ok(StrategyArg::Use(String::from(rest), vec![])),
+            Some((name, subst)) => match subst.trim_start().strip_prefix("with ") {
+                Some(subst) => Ok(StrategyArg::Use(String::from(name), parse_subst(subst)?)),
+                None => Err(String::from(SUBST_USAGE)),
+            },
+        },
+        _ if STRATEGIES.contains(&command) => Err(String::from("Invalid argument")),
+        _ => Err(String::from("Unknown command")),
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+    // how many parentheses and `first [` are open, their closing
+    // characters ending a strategy only then
+    groups: usize,
+    alternatives: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        self.peek();
+        let rest = self.rest();
+        let ends_word = |rest: &str| !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_');
+        if rest.starts_with(keyword) && ends_word(&rest[keyword.len()..]) {
+            self.pos += keyword.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn sequence(&mut self) -> Result<Tactic, String> {
+        let mut tactic = self.unary()?;
+        while self.eat(';') {
+            tactic = Tactic::Then(Box::new(tactic), Box::new(self.unary()?));
+        }
+        Ok(tactic)
+    }
+
+    fn unary(&mut self) -> Result<Tactic, String> {
+        if self.eat('(') {
+            self.groups += 1;
+            let tactic = self.sequence()?;
+            self.groups -= 1;
+            return if self.eat(')') {
+                Ok(tactic)
+            } else {
+                Err(String::from("missing ')'"))
+            };
+        }
+        if self.keyword("try") {
+            return Ok(Tactic::Try(Box::new(self.unary()?)));
+        }
+        if self.keyword("repeat") {
+            return Ok(Tactic::Repeat(Box::new(self.unary()?)));
+        }
+        if self.keyword("all") {
+            if !self.eat(':') {
+                return Err(String::from("expected ':' after all"));
+            }
+            return Ok(Tactic::All(Box::new(self.unary()?)));
+        }
+        if self.keyword("do") {
+            self.peek();
+            let rest = self.rest();
+            let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            let Ok(times) = rest[..digits].parse::<usize>() else {
+                return Err(String::from("do expects a number of times"));
+            };
+            self.pos += digits;
+            return Ok(Tactic::Do(times, Box::new(self.unary()?)));
+        }
+        if self.keyword("first") {
+            if !self.eat('[') {
+                return Err(String::from("expected '[' after first"));
+            }
+            self.alternatives += 1;
+            let mut tactics = vec![self.sequence()?];
+            while self.eat('|') {
+                tactics.push(self.sequence()?);
+            }
+            self.alternatives -= 1;
+            if !self.eat(']') {
+                return Err(String::from("missing ']'"));
+            }
+            return Ok(Tactic::First(tactics));
+        }
+        self.strategy()
+    }
+
+    // up to the next separator outside of the parentheses of its arguments
+    fn strategy(&mut self) -> Result<Tactic, String> {
+        self.peek();
+        let rest = self.rest();
+        let mut depth = 0;
+        let end = rest
+            .char_indices()
+            .find(|&(_, c)| match c {
+                '(' => {
+                    depth += 1;
+                    false
+                }
+                ')' if depth > 0 => {
+                    depth -= 1;
+                    false
+                }
+                ';' => true,
+                ')' => self.groups > 0,
+                '|' | ']' => depth == 0 && self.alternatives > 0,
+                _ => false,
+            })
+            .map_or(rest.len(), |(i, _)| i);
+        if rest[..end].trim().is_empty() {
+            return Err(String::from("expected a strategy"));
+        }
+        self.pos += end;
+        Ok(Tactic::Strategy(parse_strategy(&rest[..end])?))
+    }
+}
+
+fn open_goals(proof: &Proof, goals: impl IntoIterator<Item = usize>) -> Vec<usize> {
+    goals
+        .into_iter()
+        .filter(|&goal| *proof.goals[goal].0 != logic::Prop::True)
+        .collect()
+}
+
+impl Tactic {
+    // on the active goal, the proof being left untouched when it fails and
+    // focused on the first goal left open otherwise
+    pub fn run(&self, proof: &mut Proof) -> Result<(), StrategyError> {
+        if proof.goals.is_empty() {
+            return Err(StrategyError::NoGoals);
+        }
+        let mut state = proof.clone();
+        let goal = state.active_goal_index();
+        let left = self.run_on(&mut state, goal)?;
+        let next = left
+            .first()
+            .or(open_goals(&state, 0..state.number_of_goals()).first())
+            .copied();
+        if let Some(goal) = next {
+            state.set_active_goal(goal)?;
+        }
+        *proof = state;
+        Ok(())
+    }
+
+    // the goals left open by running on `goal`, goals keeping their index
+    // as executing a strategy only ever appends new ones
+    fn run_on(&self, proof: &mut Proof, goal: usize) -> Result<Vec<usize>, StrategyError> {
+        match self {
+            Tactic::Strategy(strat) => {
+                let before = proof.number_of_goals();
+                proof.set_active_goal(goal)?;
+                proof.execute(strat)?;
+                let new_goals = before..proof.number_of_goals();
+                Ok(open_goals(proof, std::iter::once(goal).chain(new_goals)))
+            }
+            Tactic::Then(first, second) => {
+                let mut left = vec![];
+                for goal in first.run_on(proof, goal)? {
+                    left.extend(second.run_on(proof, goal)?);
+                }
+                Ok(left)
+            }
+            Tactic::Try(tactic) => {
+                let saved = proof.clone();
+                tactic.run_on(proof, goal).or_else(|_| {
+                    *proof = saved;
+                    Ok(vec![goal])
+                })
+            }
+            Tactic::Repeat(tactic) => Ok(tactic.repeat(proof, goal)),
+            Tactic::First(tactics) => {
+                let saved = proof.clone();
+                let mut error = StrategyError::NoGoals;
+                for tactic in tactics {
+                    match tactic.run_on(proof, goal) {
+                        Ok(left) => return Ok(left),
+                        Err(err) => {
+                            *proof = saved.clone();
+                            error = err;
+                        }
+                    }
+                }
+                Err(error)
+            }
+            Tactic::All(tactic) => {
+                let mut left = vec![];
+                for goal in open_goals(proof, 0..proof.number_of_goals()) {
+                    left.extend(tactic.run_on(proof, goal)?);
+                }
+                Ok(left)
+            }
+            Tactic::Do(times, tactic) => {
+                let mut goals = vec![goal];
+                for _ in 0..*times {
+                    let mut left = vec![];
+                    for goal in goals {
+                        left.extend(tactic.run_on(proof, goal)?);
+                    }
+                    goals = left;
+                }
+                Ok(goals)
+            }
+        }
+    }
+
+    fn repeat(&self, proof: &mut Proof, goal: usize) -> Vec<usize> {
+        let mut pending = vec![goal];
+        let mut left = vec![];
+        let mut fuel = REPEAT_LIMIT;
+        while let Some(goal) = pending.pop() {
+            let saved = proof.clone();
+            match self.run_on(proof, goal) {
+                Ok(goals) if fuel > 0 && proof.goals != saved.goals => {
+                    fuel -= 1;
+                    pending.extend(goals.into_iter().rev());
+                }
+                _ => {
+                    *proof = saved;
+                    left.push(goal);
+                }
+            }
+        }
+        left
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::libpoulet::kernel;
+    use logic::Prop;
+
+    fn proof(statement: &str) -> Proof {
+        let mut proof = Proof::new();
+        proof.add_goal_from_prop(Prop::parse_infix(statement).unwrap());
+        proof
+    }
+
+    fn run(proof: &mut Proof, tactic: &str) -> Result<(), StrategyError> {
+        parse(tactic).unwrap().run(proof)
+    }
+
+    fn open(proof: &Proof) -> usize {
+        open_goals(proof, 0..proof.number_of_goals()).len()
+    }
+
+    fn checked(proof: &Proof) -> bool {
+        proof.finished_terms().is_some_and(|terms| {
+            terms
+                .iter()
+                .all(|(theorem, term)| kernel::check_theorem(theorem, term).is_ok())
+        })
+    }
+
+    #[test]
+    fn parsing() {
+        let strategy = |strat| Box::new(Tactic::Strategy(strat));
+        assert_eq!(
+            parse("intro h; try (split; exact H0)"),
+            Ok(Tactic::Then(
+                strategy(StrategyArg::Intro(Some(String::from("h")))),
+                Box::new(Tactic::Try(Box::new(Tactic::Then(
+                    strategy(StrategyArg::Split),
+                    strategy(StrategyArg::Exact(String::from("H0")))
+                ))))
+            ))
+        );
+        assert_eq!(
+            parse("first [left | excluded_middle (a | b)]"),
+            Ok(Tactic::First(vec![
+                Tactic::Strategy(StrategyArg::OrSplit(true)),
+                Tactic::Strategy(StrategyArg::ExcludedMiddle(PropRef::new(
+                    Prop::parse_infix("a | b").unwrap()
+                ))),
+            ]))
+        );
+        assert_eq!(
+            parse("excluded_middle a | b"),
+            Ok(Tactic::Strategy(StrategyArg::ExcludedMiddle(PropRef::new(
+                Prop::parse_infix("a | b").unwrap()
+            ))))
+        );
+        assert_eq!(
+            parse("do 3 all: exists f(x)"),
+            Ok(Tactic::Do(
+                3,
+                Box::new(Tactic::All(strategy(StrategyArg::Witness(
+                    FoTerm::parse("f(x)").unwrap()
+                ))))
+            ))
+        );
+        assert_eq!(
+            parse("use and_comm with a := P(x, y) | q, b := c"),
+            Ok(Tactic::Strategy(StrategyArg::Use(
+                String::from("and_comm"),
+                vec![
+                    (
+                        String::from("a"),
+                        PropRef::new(Prop::parse_infix("P(x, y) | q").unwrap())
+                    ),
+                    (
+                        String::from("b"),
+                        PropRef::new(Prop::from_name(String::from("c")))
+                    ),
+                ]
+            )))
+        );
+        assert_eq!(parse("repeat"), Err(String::from("expected a strategy")));
+        assert_eq!(
+            parse("use"),
+            Err(String::from("missing argument: <lemma name>"))
+        );
+        assert_eq!(parse("use l with a"), Err(String::from(SUBST_USAGE)));
+        assert_eq!(parse("(intro"), Err(String::from("missing ')'")));
+        assert_eq!(
+            parse("first [left | right"),
+            Err(String::from("missing ']'"))
+        );
+        assert_eq!(
+            parse("do intro"),
+            Err(String::from("do expects a number of times"))
+        );
+        assert_eq!(parse("split 0"), Err(String::from("Invalid argument")));
+        assert_eq!(
+            parse("exact"),
+            Err(String::from("missing argument: <hyp name>"))
+        );
+        assert_eq!(parse("intro; foo"), Err(String::from("Unknown command")));
+        assert!(is_tactic("intro"));
+        assert!(is_tactic("all: split"));
+        assert!(is_tactic("(left)"));
+        assert!(!is_tactic("auto"));
+        assert!(!is_tactic("introduce"));
+    }
+
+    #[test]
+    fn strategies_read_back() {
+        // what the REPL lists as applicable strategies can be typed back
+        let prop = |s| PropRef::new(Prop::parse_infix(s).unwrap());
+        let name = || String::from("h");
+        let term = FoTerm::parse("f(x, y)").unwrap();
+        let subst = vec![
+            (String::from("a"), prop("p | q")),
+            (String::from("b"), prop("P(x)")),
+        ];
+        for strat in [
+            StrategyArg::Intro(None),
+            StrategyArg::Intro(Some(name())),
+            StrategyArg::Split,
+            StrategyArg::HypSplit(name()),
+            StrategyArg::OrSplit(true),
+            StrategyArg::OrSplit(false),
+            StrategyArg::Destruct(name()),
+            StrategyArg::FalseIsHyp,
+            StrategyArg::Exact(name()),
+            StrategyArg::Apply(name()),
+            StrategyArg::ApplyIn(name(), String::from("H0"), true),
+            StrategyArg::ApplyIn(name(), String::from("H0"), false),
+            StrategyArg::ImplyLeft(name()),
+            StrategyArg::UnfoldNot(None),
+            StrategyArg::UnfoldNot(Some(name())),
+            StrategyArg::UnfoldIff(None),
+            StrategyArg::UnfoldIff(Some(name())),
+            StrategyArg::Instantiate(name(), term.clone()),
+            StrategyArg::Witness(term),
+            StrategyArg::ByContradiction,
+            StrategyArg::ExcludedMiddle(prop("a | !a => b")),
+            StrategyArg::Use(String::from("l"), vec![]),
+            StrategyArg::Use(String::from("l"), subst),
+        ] {
+            assert_eq!(
+                parse(&strat.to_string()),
+                Ok(Tactic::Strategy(strat.clone())),
+                "{}",
+                strat
+            );
+        }
+    }
+
+    #[test]
+    fn sequencing() {
+        // the second tactic runs on both goals left by split
+        let mut p = proof("a => b => (a ^ b) ^ (b ^ a)");
+        assert_eq!(run(&mut p, "do 2 intro; split; split"), Ok(()));
+        assert_eq!(open(&p), 4);
+        assert_eq!(run(&mut p, "all: first [exact H0 | exact H1]"), Ok(()));
+        assert_eq!(open(&p), 0);
+        assert!(checked(&p));
+
+        // the failing second step takes the first one back with it
+        let mut p = proof("a => b");
+        let before = p.clone();
+        assert_eq!(
+            run(&mut p, "intro; exact H0"),
+            Err(StrategyError::PremiseMismatch {
+                expected: Prop::from_name(String::from("b")),
+                found: Prop::from_name(String::from("a")),
+            })
+        );
+        assert_eq!(p, before);
+        assert_eq!(p.hyp_names(0), Vec::<String>::new());
+    }
+
+    #[test]
+    fn combinators() {
+        let mut p = proof("a => b => c => d => a ^ (b ^ (c ^ d))");
+        assert_eq!(run(&mut p, "repeat intro"), Ok(()));
+        assert_eq!(p.goals[0].1.len(), 4);
+        assert_eq!(run(&mut p, "repeat split"), Ok(()));
+        assert_eq!(open(&p), 4);
+        assert_eq!(
+            run(
+                &mut p,
+                "all: first [exact H0 | exact H1 | exact H2 | exact H3]"
+            ),
+            Ok(())
+        );
+        assert!(checked(&p));
+
+        // try leaves the goal alone, repeat stops once nothing changes
+        let mut p = proof("a | b");
+        assert_eq!(run(&mut p, "try split"), Ok(()));
+        assert_eq!(run(&mut p, "repeat try intro"), Ok(()));
+        assert_eq!(p.goals[0].0.to_string(), "( a | b )");
+        assert!(run(&mut p, "first [split | intro]").is_err());
+        assert_eq!(run(&mut p, "first [split | right]"), Ok(()));
+        assert_eq!(p.goals[0].0.to_string(), "b");
+
+        // repeat still ends with a strategy that always adds a hypothesis
+        let mut p = proof("(forall x. P(x)) => Q");
+        assert_eq!(run(&mut p, "intro h"), Ok(()));
+        assert_eq!(run(&mut p, "repeat instantiate h y"), Ok(()));
+        assert_eq!(p.goals[0].1.len(), REPEAT_LIMIT + 1);
+
+        assert_eq!(run(&mut p, "do 0 split"), Ok(()));
+        assert_eq!(
+            run(&mut Proof::new(), "try intro"),
+            Err(StrategyError::NoGoals)
+        );
+    }
+}
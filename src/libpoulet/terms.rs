@@ -0,0 +1,395 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    rc::Rc,
+};
+
+use crate::libpoulet::fol::FoTerm;
+use crate::libpoulet::intern::PropRef;
+use crate::libpoulet::logic::Logic;
+
+// proof terms of the simply typed lambda calculus with products, sums,
+// unit and empty types, read as propositions through Curry-Howard, and
+// the first-order abstraction over individuals and dependent pairs
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Term {
+    Var(usize),
+    Lambda(usize, PropRef, Rc<Term>),
+    App(Rc<Term>, Rc<Term>),
+    Pair(Rc<Term>, Rc<Term>),
+    Fst(Rc<Term>),
+    Snd(Rc<Term>),
+    Inl(Rc<Term>, PropRef),
+    Inr(PropRef, Rc<Term>),
+    Case(Rc<Term>, usize, Rc<Term>, usize, Rc<Term>),
+    Let(usize, Rc<Term>, Rc<Term>),
+    Absurd(Rc<Term>, PropRef),
+    Unit,
+    Hole(usize),
+    // Λx. t, the variable being arbitrary
+    ForallIntro(String, Rc<Term>),
+    Inst(Rc<Term>, FoTerm),
+    // the witness, the proof of the instance and the existential proven
+    Witness(FoTerm, Rc<Term>, PropRef),
+    // let <x, h> = t in u
+    Unpack(Rc<Term>, String, usize, Rc<Term>),
+    // a | !a, only in classical logic
+    ExcludedMiddle(PropRef),
+}
+
+impl Term {
+    pub fn is_complete(&self) -> bool {
+        match self {
+            Term::Hole(_) => false,
+            Term::Var(_) | Term::Unit | Term::ExcludedMiddle(_) => true,
+            Term::Lambda(_, _, t)
+            | Term::Fst(t)
+            | Term::Snd(t)
+            | Term::Inl(t, _)
+            | Term::Inr(_, t)
+            | Term::Absurd(t, _)
+            | Term::ForallIntro(_, t)
+            | Term::Inst(t, _)
+            | Term::Witness(_, t, _) => t.is_complete(),
+            Term::App(t, u) | Term::Pair(t, u) | Term::Let(_, t, u) | Term::Unpack(t, _, _, u) => {
+                t.is_complete() && u.is_complete()
+            }
+            Term::Case(t, _, u, _, v) => t.is_complete() && u.is_complete() && v.is_complete(),
+        }
+    }
+
+    // whether the variable occurs in the term, bound occurrences included
+    pub fn mentions(&self, x: usize) -> bool {
+        match self {
+            Term::Var(y) => *y == x,
+            Term::Unit | Term::Hole(_) | Term::ExcludedMiddle(_) => false,
+            Term::Lambda(y, _, t) => *y == x || t.mentions(x),
+            Term::Fst(t)
+            | Term::Snd(t)
+            | Term::Inl(t, _)
+            | Term::Inr(_, t)
+            | Term::Absurd(t, _)
+            | Term::ForallIntro(_, t)
+            | Term::Inst(t, _)
+            | Term::Witness(_, t, _) => t.mentions(x),
+            Term::App(t, u) | Term::Pair(t, u) => t.mentions(x) || u.mentions(x),
+            Term::Let(y, t, u) | Term::Unpack(t, _, y, u) => {
+                *y == x || t.mentions(x) || u.mentions(x)
+            }
+            Term::Case(t, y, u, z, v) => {
+                *y == x || *z == x || t.mentions(x) || u.mentions(x) || v.mentions(x)
+            }
+        }
+    }
+
+    // variable ids are only ever bound once, so no capture can happen here
+    fn rename(&self, map: &HashMap<usize, usize>) -> Term {
+        let r = |t: &Rc<Term>| Rc::new(t.rename(map));
+        match self {
+            Term::Var(x) => Term::Var(*map.get(x).unwrap_or(x)),
+            Term::Lambda(x, a, t) => Term::Lambda(*x, a.clone(), r(t)),
+            Term::App(t, u) => Term::App(r(t), r(u)),
+            Term::Pair(t, u) => Term::Pair(r(t), r(u)),
+            Term::Fst(t) => Term::Fst(r(t)),
+            Term::Snd(t) => Term::Snd(r(t)),
+            Term::Inl(t, b) => Term::Inl(r(t), b.clone()),
+            Term::Inr(a, t) => Term::Inr(a.clone(), r(t)),
+            Term::Case(t, x, u, y, v) => Term::Case(r(t), *x, r(u), *y, r(v)),
+            Term::Let(x, u, t) => Term::Let(*x, r(u), r(t)),
+            Term::Absurd(t, a) => Term::Absurd(r(t), a.clone()),
+            Term::ForallIntro(x, t) => Term::ForallIntro(x.clone(), r(t)),
+            Term::Inst(t, u) => Term::Inst(r(t), u.clone()),
+            Term::Witness(u, t, a) => Term::Witness(u.clone(), r(t), a.clone()),
+            Term::Unpack(t, x, h, u) => Term::Unpack(r(t), x.clone(), *h, r(u)),
+            Term::Unit | Term::Hole(_) | Term::ExcludedMiddle(_) => self.clone(),
+        }
+    }
+
+    // a copy of a complete term binding fresh variables of `record`, the
+    // atoms of its annotations substituted, for it to be used in another
+    // proof
+    pub fn instance(&self, subst: &BTreeMap<String, PropRef>, record: &mut ProofRecord) -> Term {
+        self.copy(subst, record, &mut HashMap::new())
+    }
+
+    fn copy(
+        &self,
+        subst: &BTreeMap<String, PropRef>,
+        record: &mut ProofRecord,
+        vars: &mut HashMap<usize, usize>,
+    ) -> Term {
+        let p = |a: &PropRef| PropRef::new(a.subst_atoms(subst));
+        fn bind(x: usize, record: &mut ProofRecord, vars: &mut HashMap<usize, usize>) -> usize {
+            let y = record.fresh();
+            vars.insert(x, y);
+            y
+        }
+        match self {
+            Term::Var(x) => Term::Var(*vars.get(x).unwrap_or(x)),
+            Term::Lambda(x, a, t) => {
+                let y = bind(*x, record, vars);
+                Term::Lambda(y, p(a), Rc::new(t.copy(subst, record, vars)))
+            }
+            Term::App(t, u) => Term::App(
+                Rc::new(t.copy(subst, record, vars)),
+                Rc::new(u.copy(subst, record, vars)),
+            ),
+            Term::Pair(t, u) => Term::Pair(
+                Rc::new(t.copy(subst, record, vars)),
+                Rc::new(u.copy(subst, record, vars)),
+            ),
+            Term::Fst(t) => Term::Fst(Rc::new(t.copy(subst, record, vars))),
+            Term::Snd(t) => Term::Snd(Rc::new(t.copy(subst, record, vars))),
+            Term::Inl(t, b) => Term::Inl(Rc::new(t.copy(subst, record, vars)), p(b)),
+            Term::Inr(a, t) => Term::Inr(p(a), Rc::new(t.copy(subst, record, vars))),
+            Term::Case(t, x, u, y, v) => {
+                let t = t.copy(subst, record, vars);
+                let x = bind(*x, record, vars);
+                let u = u.copy(subst, record, vars);
+                let y = bind(*y, record, vars);
+                let v = v.copy(subst, record, vars);
+                Term::Case(Rc::new(t), x, Rc::new(u), y, Rc::new(v))
+            }
+            Term::Let(x, u, t) => {
+                let u = u.copy(subst, record, vars);
+                let x = bind(*x, record, vars);
+                Term::Let(x, Rc::new(u), Rc::new(t.copy(subst, record, vars)))
+            }
+            Term::Absurd(t, a) => Term::Absurd(Rc::new(t.copy(subst, record, vars)), p(a)),
+            Term::ForallIntro(x, t) => {
+                Term::ForallIntro(x.clone(), Rc::new(t.copy(subst, record, vars)))
+            }
+            Term::Inst(t, u) => Term::Inst(Rc::new(t.copy(subst, record, vars)), u.clone()),
+            Term::Witness(u, t, a) => {
+                Term::Witness(u.clone(), Rc::new(t.copy(subst, record, vars)), p(a))
+            }
+            Term::Unpack(t, x, h, u) => {
+                let t = t.copy(subst, record, vars);
+                let h = bind(*h, record, vars);
+                Term::Unpack(
+                    Rc::new(t),
+                    x.clone(),
+                    h,
+                    Rc::new(u.copy(subst, record, vars)),
+                )
+            }
+            Term::ExcludedMiddle(a) => Term::ExcludedMiddle(p(a)),
+            Term::Unit | Term::Hole(_) => self.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Term::Var(x) => write!(f, "x{}", x),
+            Term::Lambda(x, a, t) => write!(f, "(λx{}: {}. {})", x, a.to_string(), t),
+            Term::App(t, u) => write!(f, "({} {})", t, u),
+            Term::Pair(t, u) => write!(f, "<{}, {}>", t, u),
+            Term::Fst(t) => write!(f, "fst {}", t),
+            Term::Snd(t) => write!(f, "snd {}", t),
+            Term::Inl(t, _) => write!(f, "inl {}", t),
+            Term::Inr(_, t) => write!(f, "inr {}", t),
+            Term::Case(t, x, u, y, v) => write!(
+                f,
+                "(case {} of inl x{} -> {} | inr x{} -> {})",
+                t, x, u, y, v
+            ),
+            Term::Let(x, u, t) => write!(f, "(let x{} = {} in {})", x, u, t),
+            Term::Absurd(t, _) => write!(f, "absurd {}", t),
+            Term::Unit => write!(f, "()"),
+            Term::Hole(h) => write!(f, "?{}", h),
+            Term::ForallIntro(x, t) => write!(f, "(Λ{}. {})", x, t),
+            Term::Inst(t, u) => write!(f, "({} {})", t, u),
+            Term::Witness(u, t, _) => write!(f, "<{}, {}>", u, t),
+            Term::Unpack(t, x, h, u) => write!(f, "(let <{}, x{}> = {} in {})", x, h, t, u),
+            Term::ExcludedMiddle(a) => write!(f, "em {}", a.to_string()),
+        }
+    }
+}
+
+// the sequent a term was started for: named hypotheses and the goal, and
+// the logic it is to be proven in
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Theorem {
+    pub hyps: Vec<(usize, PropRef)>,
+    pub goal: PropRef,
+    pub logic: Logic,
+    root: usize,
+}
+
+impl fmt::Display for Theorem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hyps: Vec<String> = self
+            .hyps
+            .iter()
+            .map(|(x, hyp)| format!("x{}: {}", x, hyp.to_string()))
+            .collect();
+        write!(f, "{} |- {}", hyps.join(", "), self.goal.to_string())
+    }
+}
+
+// term variables of the hypotheses and the hole to fill for one open goal
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GoalContext {
+    pub hole: usize,
+    pub vars: Vec<usize>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ProofRecord {
+    pub theorems: Vec<Theorem>,
+    pub contexts: Vec<GoalContext>,
+    fills: HashMap<usize, Term>,
+    // hole of a merged duplicate goal -> (kept hole, renaming of its variables)
+    aliases: HashMap<usize, (usize, HashMap<usize, usize>)>,
+    next_id: usize,
+    logic: Logic,
+}
+
+impl ProofRecord {
+    pub fn fresh(&mut self) -> usize {
+        self.next_id += 1;
+        self.next_id - 1
+    }
+
+    pub fn fill(&mut self, hole: usize, term: Term) {
+        self.fills.insert(hole, term);
+    }
+
+    pub fn is_filled(&self, hole: usize) -> bool {
+        self.fills.contains_key(&hole) || self.aliases.contains_key(&hole)
+    }
+
+    pub fn alias(&mut self, hole: usize, kept: &GoalContext, vars: &[usize]) {
+        let renaming = kept
+            .vars
+            .iter()
+            .copied()
+            .zip(vars.iter().copied())
+            .collect();
+        self.aliases.insert(hole, (kept.hole, renaming));
+    }
+
+    pub fn add_theorem(&mut self, goal: PropRef) {
+        let root = self.fresh();
+        self.theorems.push(Theorem {
+            hyps: vec![],
+            goal,
+            logic: self.logic,
+            root,
+        });
+        self.contexts.push(GoalContext {
+            hole: root,
+            vars: vec![],
+        });
+    }
+
+    // the theorems already stated are moved to the new logic as well
+    pub fn set_logic(&mut self, logic: Logic) {
+        self.logic = logic;
+        for theorem in self.theorems.iter_mut() {
+            theorem.logic = logic;
+        }
+    }
+
+    pub fn add_hyp(&mut self, goal_index: usize, hyp: PropRef) {
+        let var = self.fresh();
+        let hole = self.contexts[goal_index].hole;
+        self.contexts[goal_index].vars.push(var);
+        // hypotheses given before the first step belong to the statement
+        if let Some(theorem) = self.theorems.iter_mut().find(|t| t.root == hole) {
+            theorem.hyps.push((var, hyp));
+        }
+    }
+
+    pub fn resolve(&self, term: &Term) -> Term {
+        let r = |t: &Rc<Term>| Rc::new(self.resolve(t));
+        match term {
+            Term::Hole(h) => {
+                if let Some(filled) = self.fills.get(h) {
+                    self.resolve(filled)
+                } else if let Some((kept, renaming)) = self.aliases.get(h) {
+                    self.resolve(&Term::Hole(*kept)).rename(renaming)
+                } else {
+                    term.clone()
+                }
+            }
+            Term::Var(_) | Term::Unit | Term::ExcludedMiddle(_) => term.clone(),
+            Term::Lambda(x, a, t) => Term::Lambda(*x, a.clone(), r(t)),
+            Term::App(t, u) => Term::App(r(t), r(u)),
+            Term::Pair(t, u) => Term::Pair(r(t), r(u)),
+            Term::Fst(t) => Term::Fst(r(t)),
+            Term::Snd(t) => Term::Snd(r(t)),
+            Term::Inl(t, b) => Term::Inl(r(t), b.clone()),
+            Term::Inr(a, t) => Term::Inr(a.clone(), r(t)),
+            Term::Case(t, x, u, y, v) => Term::Case(r(t), *x, r(u), *y, r(v)),
+            Term::Let(x, u, t) => Term::Let(*x, r(u), r(t)),
+            Term::Absurd(t, a) => Term::Absurd(r(t), a.clone()),
+            Term::ForallIntro(x, t) => Term::ForallIntro(x.clone(), r(t)),
+            Term::Inst(t, u) => Term::Inst(r(t), u.clone()),
+            Term::Witness(u, t, a) => Term::Witness(u.clone(), r(t), a.clone()),
+            Term::Unpack(t, x, h, u) => Term::Unpack(r(t), x.clone(), *h, r(u)),
+        }
+    }
+
+    pub fn theorem_terms(&self) -> Vec<(Theorem, Term)> {
+        self.theorems
+            .iter()
+            .map(|theorem| (theorem.clone(), self.resolve(&Term::Hole(theorem.root))))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::libpoulet::logic::Prop;
+
+    #[test]
+    fn display() {
+        let a = PropRef::new(Prop::from_name(String::from("a")));
+        let term = Term::Lambda(
+            0,
+            PropRef::new(Prop::and(
+                Prop::from_name(String::from("a")),
+                Prop::from_name(String::from("b")),
+            )),
+            Rc::new(Term::Pair(
+                Rc::new(Term::Snd(Rc::new(Term::Var(0)))),
+                Rc::new(Term::Inl(Rc::new(Term::Fst(Rc::new(Term::Var(0)))), a)),
+            )),
+        );
+        assert_eq!(term.to_string(), "(λx0: ( a ^ b ). <snd x0, inl fst x0>)");
+        assert!(term.is_complete());
+        assert!(!Term::App(Rc::new(Term::Var(0)), Rc::new(Term::Hole(1))).is_complete());
+    }
+
+    #[test]
+    fn resolve() {
+        let mut record = ProofRecord::default();
+        record.add_theorem(PropRef::new(Prop::True));
+        record.add_hyp(0, PropRef::new(Prop::False));
+        let h1 = record.fresh();
+        let h2 = record.fresh();
+        record.fill(
+            0,
+            Term::Pair(Rc::new(Term::Hole(h1)), Rc::new(Term::Hole(h2))),
+        );
+        record.fill(h1, Term::Var(1));
+        assert!(!record.theorem_terms()[0].1.is_complete());
+
+        let kept = GoalContext {
+            hole: h1,
+            vars: vec![1],
+        };
+        record.alias(h2, &kept, &[5]);
+        assert!(record.is_filled(h2));
+        let (theorem, term) = record.theorem_terms().remove(0);
+        assert_eq!(theorem.to_string(), "x1: F |- T");
+        assert_eq!(
+            term,
+            Term::Pair(Rc::new(Term::Var(1)), Rc::new(Term::Var(5)))
+        );
+    }
+}
@@ -0,0 +1,409 @@
+use std::{error::Error, fmt};
+
+use crate::libpoulet::intern::PropRef;
+use crate::libpoulet::logic::Prop;
+
+// Propositional problems in the TPTP syntax, as used by the ILTP library:
+// `fof(name, role, formula).` or `tff(...)`, formulas built from atoms,
+// $true, $false, ~, &, |, =>, <=, <=>, <~>, ~| and ~&.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TptpError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TptpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for TptpError {}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Problem {
+    // axioms, hypotheses and the other roles asserted to be true
+    pub axioms: Vec<Prop>,
+    pub conjectures: Vec<Prop>,
+}
+
+const BINARY: [&str; 8] = ["<~>", "<=>", "=>", "<=", "~|", "~&", "&", "|"];
+
+// longest first, so that `~|` is not read as `~` then `|`
+const OPERATORS: [&str; 10] = ["<~>", "<=>", "=>", "<=", "~|", "~&", "~", "&", "|", ","];
+
+fn tokenize(input: &str) -> Result<Vec<(usize, String)>, TptpError> {
+    let mut tokens = vec![];
+    let mut line = 1;
+    let mut rest = input;
+    while let Some(c) = rest.chars().next() {
+        if c == '\n' {
+            line += 1;
+        }
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if c == '%' {
+            rest = rest.find('\n').map_or("", |end| &rest[end..]);
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            let Some(end) = comment.find("*/") else {
+                return Err(TptpError {
+                    line,
+                    message: String::from("unterminated comment"),
+                });
+            };
+            line += comment[..end].matches('\n').count();
+            rest = &comment[end + 2..];
+        } else if c == '\'' {
+            // quoted atoms may contain anything but an unescaped quote
+            let mut end = 1;
+            let bytes = rest.as_bytes();
+            while end < bytes.len() && bytes[end] != b'\'' {
+                end += if bytes[end] == b'\\' { 2 } else { 1 };
+            }
+            if end >= bytes.len() {
+                return Err(TptpError {
+                    line,
+                    message: String::from("unterminated quoted atom"),
+                });
+            }
+            tokens.push((line, String::from(&rest[..=end])));
+            rest = &rest[end + 1..];
+        } else if c.is_alphanumeric() || c == '_' || c == '$' {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+                .unwrap_or(rest.len());
+            tokens.push((line, String::from(&rest[..end])));
+            rest = &rest[end..];
+        } else if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            tokens.push((line, String::from(*op)));
+            rest = &rest[op.len()..];
+        } else if "().[]:".contains(c) {
+            tokens.push((line, String::from(c)));
+            rest = &rest[1..];
+        } else {
+            return Err(TptpError {
+                line,
+                message: format!("unexpected character '{}'", c),
+            });
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, String)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(|(_, token)| token.as_str())
+    }
+
+    fn error(&self, message: String) -> TptpError {
+        let line = match self.tokens.get(self.pos).or(self.tokens.last()) {
+            Some((line, _)) => *line,
+            None => 1,
+        };
+        TptpError { line, message }
+    }
+
+    fn next(&mut self) -> Result<String, TptpError> {
+        match self.tokens.get(self.pos) {
+            Some((_, token)) => {
+                self.pos += 1;
+                Ok(token.clone())
+            }
+            None => Err(self.error(String::from("unexpected end of input"))),
+        }
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), TptpError> {
+        let token = self.next()?;
+        if token == expected {
+            Ok(())
+        } else {
+            self.pos -= 1;
+            Err(self.error(format!("expected '{}', found '{}'", expected, token)))
+        }
+    }
+
+    // skips a term such as the annotations or a type, up to `stop` outside
+    // of any parentheses or brackets
+    fn skip_until(&mut self, stop: &[&str]) -> Result<(), TptpError> {
+        let mut depth = 0;
+        loop {
+            match self.peek() {
+                Some(token) if depth == 0 && stop.contains(&token) => return Ok(()),
+                Some("(") | Some("[") => depth += 1,
+                Some(")") | Some("]") => depth -= 1,
+                Some(_) => (),
+                None => return Err(self.error(String::from("unexpected end of input"))),
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn formula(&mut self) -> Result<Prop, TptpError> {
+        let first = self.unary()?;
+        let Some(op) = self.peek().map(String::from) else {
+            return Ok(first);
+        };
+        match op.as_str() {
+            // the only associative connectives, they may be chained
+            "&" | "|" => {
+                let mut acc = first;
+                while self.peek() == Some(op.as_str()) {
+                    self.pos += 1;
+                    let next = self.unary()?;
+                    acc = if op == "&" {
+                        Prop::and(acc, next)
+                    } else {
+                        Prop::or(acc, next)
+                    };
+                }
+                if let Some(other) = self.peek()
+                    && BINARY.contains(&other)
+                {
+                    return Err(self.error(format!("'{}' after '{}' needs parentheses", other, op)));
+                }
+                Ok(acc)
+            }
+            "=>" | "<=" | "<=>" | "<~>" | "~|" | "~&" => {
+                self.pos += 1;
+                let second = self.unary()?;
+                if let Some(other) = self.peek()
+                    && BINARY.contains(&other)
+                {
+                    return Err(self.error(format!("'{}' after '{}' needs parentheses", other, op)));
+                }
+                Ok(match op.as_str() {
+                    "=>" => Prop::imply(first, second),
+                    "<=" => Prop::imply(second, first),
+                    "<=>" => Prop::equiv(first, second),
+                    "<~>" => Prop::not(Prop::equiv(first, second)),
+                    "~|" => Prop::not(Prop::or(first, second)),
+                    _ => Prop::not(Prop::and(first, second)),
+                })
+            }
+            _ => Ok(first),
+        }
+    }
+
+    fn unary(&mut self) -> Result<Prop, TptpError> {
+        let token = self.next()?;
+        match token.as_str() {
+            "~" => Ok(Prop::not(self.unary()?)),
+            "(" => {
+                let prop = self.formula()?;
+                self.expect(")")?;
+                Ok(prop)
+            }
+            "$true" => Ok(Prop::True),
+            "$false" => Ok(Prop::False),
+            _ if token.starts_with('\'') => {
+                let name = token[1..token.len() - 1]
+                    .replace("\\'", "'")
+                    .replace("\\\\", "\\");
+                Ok(Prop::Name(name))
+            }
+            _ if token.starts_with(|c: char| c.is_lowercase() || c.is_ascii_digit()) => {
+                Ok(Prop::Name(token))
+            }
+            _ => {
+                self.pos -= 1;
+                Err(self.error(format!("expected a formula, found '{}'", token)))
+            }
+        }
+    }
+
+    fn problem(&mut self) -> Result<Problem, TptpError> {
+        let mut problem = Problem::default();
+        while let Some(kind) = self.peek().map(String::from) {
+            if kind != "fof" && kind != "tff" {
+                return Err(self.error(format!("'{}' is not supported, only fof and tff", kind)));
+            }
+            self.pos += 1;
+            self.expect("(")?;
+            self.next()?;
+            self.expect(",")?;
+            let role = self.next()?;
+            self.expect(",")?;
+            match role.as_str() {
+                "type" => self.skip_until(&[")", ","])?,
+                "conjecture" => problem.conjectures.push(self.formula()?),
+                "axiom" | "hypothesis" | "definition" | "assumption" | "lemma" | "theorem"
+                | "corollary" => problem.axioms.push(self.formula()?),
+                _ => return Err(self.error(format!("role '{}' is not supported", role))),
+            }
+            if self.peek() == Some(",") {
+                self.skip_until(&[")"])?;
+            }
+            self.expect(")")?;
+            self.expect(".")?;
+        }
+        Ok(problem)
+    }
+}
+
+pub fn parse(input: &str) -> Result<Problem, TptpError> {
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        pos: 0,
+    };
+    parser.problem()
+}
+
+fn atom(name: &str) -> String {
+    let mut chars = name.chars();
+    let lower_word = chars.next().is_some_and(|c| c.is_ascii_lowercase())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if lower_word {
+        String::from(name)
+    } else {
+        format!("'{}'", name.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+}
+
+fn formula_to_string(prop: &Prop) -> String {
+    match prop {
+        Prop::Name(name) => atom(name),
+        // only reached through the propositional reading of a formula
+        Prop::Pred(_, _) | Prop::Forall(_, _) | Prop::Exists(_, _) => {
+            atom(&prop.atom_name().unwrap())
+        }
+        Prop::True => String::from("$true"),
+        Prop::False => String::from("$false"),
+        Prop::Not(a) => format!("~ {}", formula_to_string(a)),
+        Prop::Iff(a, b) => format!("( {} <=> {} )", formula_to_string(a), formula_to_string(b)),
+        Prop::Implies(a, b) => {
+            format!("( {} => {} )", formula_to_string(a), formula_to_string(b))
+        }
+        Prop::And(a, b) => format!("( {} & {} )", formula_to_string(a), formula_to_string(b)),
+        Prop::Or(a, b) => format!("( {} | {} )", formula_to_string(a), formula_to_string(b)),
+    }
+}
+
+pub fn problem_to_string(hyps: &[PropRef], goal: &Prop) -> String {
+    let mut out = String::new();
+    for (index, hyp) in hyps.iter().enumerate() {
+        out.push_str(&format!(
+            "fof(hyp{}, axiom, {}).\n",
+            index,
+            formula_to_string(hyp)
+        ));
+    }
+    out.push_str(&format!(
+        "fof(goal, conjecture, {}).\n",
+        formula_to_string(goal)
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prop(s: &str) -> Prop {
+        Prop::parse_infix(s).unwrap()
+    }
+
+    #[test]
+    fn parse_problem() {
+        let input = "%--------------------------------------------------------------------------
+% File     : SYJ201+1 : ILTP v1.1.2
+/* a block
+   comment */
+fof(axiom1, axiom, ( p1 <=> p2 ) ).
+tff(q_type, type, q: $o).
+fof(axiom2, hypothesis, ~ (p1 & ~p2 & q) | $false, [annotation(1)]).
+fof(con, conjecture, (p1 => (p2 <= q)) ).
+";
+        let problem = parse(input).unwrap();
+        assert_eq!(
+            problem,
+            Problem {
+                axioms: vec![prop("p1 <=> p2"), prop("!(p1 ^ !p2 ^ q) | F")],
+                conjectures: vec![prop("p1 => q => p2")],
+            }
+        );
+        assert_eq!(
+            parse("fof(a, axiom, (a <~> b) ~| 'Big one' ~& $true).").unwrap_err(),
+            TptpError {
+                line: 1,
+                message: String::from("'~&' after '~|' needs parentheses"),
+            }
+        );
+        let xor = parse("fof(a, axiom, (a <~> b) ~| ('B\\'s' ~& $true)).").unwrap();
+        assert_eq!(
+            xor.axioms,
+            vec![Prop::not(Prop::or(
+                Prop::not(prop("a <=> b")),
+                Prop::not(Prop::and(Prop::Name(String::from("B's")), Prop::True))
+            ))]
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        for (input, line, message) in [
+            (
+                "include('Axioms/SYN000+0.ax').",
+                1,
+                "'include' is not supported, only fof and tff",
+            ),
+            (
+                "fof(a, axiom, p).\n\nfof(b, plain, p).",
+                3,
+                "role 'plain' is not supported",
+            ),
+            (
+                "fof(a, axiom, p => q => r).",
+                1,
+                "'=>' after '=>' needs parentheses",
+            ),
+            (
+                "fof(a, axiom, p & q | r).",
+                1,
+                "'|' after '&' needs parentheses",
+            ),
+            ("fof(a, axiom, (p & q).", 1, "expected ')', found '.'"),
+            ("fof(a, axiom, P).", 1, "expected a formula, found 'P'"),
+            ("fof(a, axiom, p)", 1, "unexpected end of input"),
+            ("/* \n\n", 1, "unterminated comment"),
+            ("fof(a, axiom,\n p # q).", 2, "unexpected character '#'"),
+        ] {
+            assert_eq!(
+                parse(input),
+                Err(TptpError {
+                    line,
+                    message: String::from(message),
+                }),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let hyps = vec![
+            PropRef::new(prop("a ^ !b")),
+            PropRef::new(Prop::Name(String::from("Up'per"))),
+        ];
+        let goal = prop("(T => F) | (c => d_1)");
+        let text = problem_to_string(&hyps, &goal);
+        assert_eq!(
+            text,
+            "fof(hyp0, axiom, ( a & ~ b )).\nfof(hyp1, axiom, 'Up\\'per').\n\
+             fof(goal, conjecture, ( ( $true => $false ) | ( c => d_1 ) )).\n"
+        );
+        let problem = parse(&text).unwrap();
+        assert_eq!(
+            problem.axioms,
+            vec![prop("a ^ !b"), Prop::Name(String::from("Up'per"))]
+        );
+        assert_eq!(problem.conjectures, vec![goal]);
+    }
+}
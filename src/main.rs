@@ -2,5 +2,9 @@ mod cli;
 mod libpoulet;
 
 fn main() {
-    cli::repl();
+    let args: Vec<String> = std::env::args().collect();
+    match args.as_slice() {
+        [_, command, path] if command == "check" => cli::check_script(path),
+        _ => cli::repl(),
+    }
 }